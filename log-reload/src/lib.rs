@@ -1,15 +1,22 @@
 //! A [`log::Log`] implementation which dynamically reloads inner loggers.
 //!
 //! [`ReloadLog`] wraps an inner logger and provides a [`ReloadHandle`] to
-//! dynamically replace or modify the inner logger.
+//! dynamically replace or modify the inner logger.  The owner of a
+//! [`ReloadLog`] can also replace or modify the inner logger directly.
 //!
 //! This allows programs to dynamically change the log level or log target at
 //! runtime.
+//!
+//! Replacing or modifying the inner logger holds a write lock which blocks
+//! logging; [`ReloadLog::timing_write_locks`] measures how long, to diagnose
+//! logging stalls.
 
 #![deny(warnings, clippy::all, missing_docs)]
 #![forbid(unsafe_code)]
 
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, Instant};
 
 use log::Log;
 use thiserror::Error;
@@ -18,16 +25,44 @@ use thiserror::Error;
 ///
 /// Only forward log events whose log level is smaller or equal than the
 /// configured level to the underlying logger.
+///
+/// Optionally count the records which the filter dropped, see
+/// [`Self::counting_dropped`].
 #[derive(Debug)]
 pub struct LevelFilter<T> {
     level: log::Level,
     logger: T,
+    dropped: Option<AtomicU64>,
 }
 
 impl<T> LevelFilter<T> {
     /// Create a new level filter with the given max `level` around the given `logger`.
     pub fn new(level: log::Level, logger: T) -> Self {
-        Self { level, logger }
+        Self {
+            level,
+            logger,
+            dropped: None,
+        }
+    }
+
+    /// Count the records dropped by this filter.
+    ///
+    /// Count every record passed to [`Log::log`] whose level does not pass
+    /// the filter; [`Log::enabled`] only asks and thus counts nothing.  Get the
+    /// count with [`Self::dropped_count`].
+    pub fn counting_dropped(mut self) -> Self {
+        self.dropped = Some(AtomicU64::new(0));
+        self
+    }
+
+    /// Get the number of records dropped by this filter so far.
+    ///
+    /// Return `None` if this filter does not count dropped records, see
+    /// [`Self::counting_dropped`].
+    pub fn dropped_count(&self) -> Option<u64> {
+        self.dropped
+            .as_ref()
+            .map(|dropped| dropped.load(Ordering::Relaxed))
     }
 
     /// Get the current log level.
@@ -65,9 +100,13 @@ impl<T: Log> log::Log for LevelFilter<T> {
     }
 
     /// Forward a log `record` to the underlying logger if it passes the level filter.
+    ///
+    /// Count the `record` as dropped otherwise, if counting is enabled.
     fn log(&self, record: &log::Record) {
         if self.level_passes(record.metadata()) {
             self.logger.log(record)
+        } else if let Some(dropped) = &self.dropped {
+            dropped.fetch_add(1, Ordering::Relaxed);
         }
     }
 
@@ -77,29 +116,114 @@ impl<T: Log> log::Log for LevelFilter<T> {
     }
 }
 
+/// The inner logger of a [`ReloadLog`], shared with all its handles.
+#[derive(Debug)]
+struct Underlying<T> {
+    logger: RwLock<T>,
+    /// Whether to measure how long writers hold the lock.
+    timing_write_locks: AtomicBool,
+    /// The total time writers held the lock, in nanoseconds.
+    write_lock_nanos: AtomicU64,
+}
+
+impl<T> Underlying<T> {
+    /// Call `f` with the inner logger under a write lock, and measure the time the lock was held.
+    fn modify<F>(&self, f: F) -> Result<(), ReloadError>
+    where
+        F: FnOnce(&mut T),
+    {
+        // TODO: Overwrite and clear poison, once clear_poison() is stabilized
+        // See https://github.com/rust-lang/rust/issues/96469
+        let mut guard = self.logger.write().map_err(|_| ReloadError::Poisoned)?;
+        if self.timing_write_locks.load(Ordering::Relaxed) {
+            let start = Instant::now();
+            f(&mut *guard);
+            drop(guard);
+            let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            self.write_lock_nanos.fetch_add(nanos, Ordering::Relaxed);
+        } else {
+            f(&mut *guard);
+        }
+        Ok(())
+    }
+
+    fn write_lock_duration(&self) -> Option<Duration> {
+        self.timing_write_locks
+            .load(Ordering::Relaxed)
+            .then(|| Duration::from_nanos(self.write_lock_nanos.load(Ordering::Relaxed)))
+    }
+}
+
 /// A logger which can dynamically reload an inner logger.
 ///
 /// This enables applications to dyanmically change e.g. the log output or
 /// log level.
 #[derive(Debug)]
 pub struct ReloadLog<T> {
-    underlying: Arc<RwLock<T>>,
+    underlying: Arc<Underlying<T>>,
 }
 
 impl<T> ReloadLog<T> {
     /// Create a new reloadable logger over the given `logger`.
     pub fn new(logger: T) -> Self {
         Self {
-            underlying: Arc::new(RwLock::new(logger)),
+            underlying: Arc::new(Underlying {
+                logger: RwLock::new(logger),
+                timing_write_locks: AtomicBool::new(false),
+                write_lock_nanos: AtomicU64::new(0),
+            }),
         }
     }
 
+    /// Measure how long replacing or modifying the inner logger holds the write lock.
+    ///
+    /// The write lock blocks all logging, so a slow closure passed to
+    /// [`Self::modify`] or [`ReloadHandle::modify`] stalls logging, e.g. if
+    /// creating a new logger for a different log target takes long.  Add up
+    /// the time each [`Self::replace`], [`Self::modify`], and the corresponding
+    /// methods of all handles of this logger held the write lock, and get the
+    /// total with [`Self::write_lock_duration`] or
+    /// [`ReloadHandle::write_lock_duration`].
+    pub fn timing_write_locks(self) -> Self {
+        self.underlying
+            .timing_write_locks
+            .store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// Get the total time replacing or modifying the inner logger held the write lock.
+    ///
+    /// Return `None` if this logger does not measure the time, see
+    /// [`Self::timing_write_locks`].
+    pub fn write_lock_duration(&self) -> Option<Duration> {
+        self.underlying.write_lock_duration()
+    }
+
     /// Obtain a handle to reload or modify the inner logger.
     pub fn handle(&self) -> ReloadHandle<T> {
         ReloadHandle {
             underlying: Arc::downgrade(&self.underlying),
         }
     }
+
+    /// Replace the inner logger.
+    ///
+    /// Like [`ReloadHandle::replace`], but without a handle, for owners of
+    /// this logger.  This never fails with [`ReloadError::Gone`].
+    pub fn replace(&self, logger: T) -> Result<(), ReloadError> {
+        self.modify(|l| *l = logger)
+    }
+
+    /// Modify the inner logger.
+    ///
+    /// Like [`ReloadHandle::modify`], but without a handle, for owners of this
+    /// logger.  This never fails with [`ReloadError::Gone`].
+    pub fn modify<F>(&self, f: F) -> Result<(), ReloadError>
+    where
+        F: FnOnce(&mut T),
+    {
+        self.underlying.modify(f)
+    }
 }
 
 impl<T: Log> Log for ReloadLog<T> {
@@ -110,8 +234,9 @@ impl<T: Log> Log for ReloadLog<T> {
     /// while it was modified, so we indicate that this logger shouldn't be used at all.
     fn enabled(&self, metadata: &log::Metadata) -> bool {
         self.underlying
+            .logger
             .read()
-            .map_or(false, |l| l.enabled(metadata))
+            .is_ok_and(|l| l.enabled(metadata))
     }
 
     /// Log the given `record` with the inner logger.
@@ -121,7 +246,7 @@ impl<T: Log> Log for ReloadLog<T> {
     /// while it was modified.  The `record` is likely lost in this case.
     fn log(&self, record: &log::Record) {
         // We can't reasonably do anything if the lock is poisoned so we ignore the result
-        let _ = self.underlying.read().map(|l| l.log(record));
+        let _ = self.underlying.logger.read().map(|l| l.log(record));
     }
 
     /// Flush the inner logger
@@ -131,7 +256,7 @@ impl<T: Log> Log for ReloadLog<T> {
     /// while it was modified.
     fn flush(&self) {
         // We can't reasonably do anything if the lock is poisoned so we ignore the result
-        let _ = self.underlying.read().map(|l| l.flush());
+        let _ = self.underlying.logger.read().map(|l| l.flush());
     }
 }
 
@@ -160,7 +285,7 @@ pub enum ReloadError {
 /// A handle to reload a logger inside a [`ReloadLog`].
 #[derive(Debug, Clone)]
 pub struct ReloadHandle<T> {
-    underlying: Weak<RwLock<T>>,
+    underlying: Weak<Underlying<T>>,
 }
 
 impl<T> ReloadHandle<T> {
@@ -168,12 +293,7 @@ impl<T> ReloadHandle<T> {
     ///
     /// This replaces the inner logger of the referenced [`ReloadLog`] with the given `logger`.
     pub fn replace(&self, logger: T) -> Result<(), ReloadError> {
-        let lock = self.underlying.upgrade().ok_or(ReloadError::Gone)?;
-        // TODO: Overwrite and clear poison, once clear_poison() is stabilized
-        // See https://github.com/rust-lang/rust/issues/96469
-        let mut guard = lock.write().map_err(|_| ReloadError::Poisoned)?;
-        *guard = logger;
-        Ok(())
+        self.modify(|l| *l = logger)
     }
 
     /// Modify the inner logger.
@@ -187,21 +307,44 @@ impl<T> ReloadHandle<T> {
     where
         F: FnOnce(&mut T),
     {
-        let lock = self.underlying.upgrade().ok_or(ReloadError::Gone)?;
-        // TODO: Overwrite and clear poison, once clear_poison() is stabilized
-        // See https://github.com/rust-lang/rust/issues/96469
-        let mut guard = lock.write().map_err(|_| ReloadError::Poisoned)?;
-        f(&mut *guard);
-        Ok(())
+        let underlying = self.underlying.upgrade().ok_or(ReloadError::Gone)?;
+        underlying.modify(f)
+    }
+
+    /// Inspect the inner logger.
+    ///
+    /// Call the given function with a shared reference to the logger, and
+    /// return its result.  Unlike [`Self::modify`] this only takes a read
+    /// lock, so the logger keeps processing log messages while `f` runs.
+    pub fn with<F, R>(&self, f: F) -> Result<R, ReloadError>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let underlying = self.underlying.upgrade().ok_or(ReloadError::Gone)?;
+        let guard = underlying
+            .logger
+            .read()
+            .map_err(|_| ReloadError::Poisoned)?;
+        Ok(f(&*guard))
+    }
+
+    /// Get the total time replacing or modifying the inner logger held the write lock.
+    ///
+    /// See [`ReloadLog::write_lock_duration`].  Fail with [`ReloadError::Gone`]
+    /// if the referenced logger was dropped.
+    pub fn write_lock_duration(&self) -> Result<Option<Duration>, ReloadError> {
+        let underlying = self.underlying.upgrade().ok_or(ReloadError::Gone)?;
+        Ok(underlying.write_lock_duration())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{LevelFilter, ReloadLog};
+    use crate::{LevelFilter, ReloadError, ReloadLog};
     use log::{Log, Record};
     use similar_asserts::assert_eq;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     struct CollectMessages {
         messages: Mutex<Vec<String>>,
@@ -269,6 +412,51 @@ mod tests {
         assert_eq!(*messages, &["ERROR", "WARN", "INFO", "DEBUG"]);
     }
 
+    #[test]
+    fn level_filter_dropped_count() {
+        let collect_logs = Arc::new(CollectMessages::new());
+        let filter = LevelFilter::new(log::Level::Warn, collect_logs.clone());
+        assert_eq!(filter.dropped_count(), None);
+
+        let mut filter = filter.counting_dropped();
+        assert_eq!(filter.dropped_count(), Some(0));
+        let levels = [
+            log::Level::Error,
+            log::Level::Trace,
+            log::Level::Info,
+            log::Level::Warn,
+            log::Level::Debug,
+            log::Level::Info,
+        ];
+        for level in levels {
+            filter.log(
+                &Record::builder()
+                    .level(level)
+                    .args(format_args!("{level}"))
+                    .build(),
+            );
+        }
+        // Asking whether a level is enabled does not count
+        assert!(!filter.enabled(&log::Metadata::builder().level(log::Level::Debug).build()));
+        assert_eq!(filter.dropped_count(), Some(4));
+
+        filter.set_level(log::Level::Info);
+        for level in levels {
+            filter.log(
+                &Record::builder()
+                    .level(level)
+                    .args(format_args!("{level}"))
+                    .build(),
+            );
+        }
+        assert_eq!(filter.dropped_count(), Some(6));
+        let messages = collect_logs.messages.try_lock().unwrap();
+        assert_eq!(
+            *messages,
+            &["ERROR", "WARN", "ERROR", "INFO", "WARN", "INFO"]
+        );
+    }
+
     #[test]
     fn reloadlog_replace() {
         let collect_logs_1 = Arc::new(CollectMessages::new());
@@ -311,4 +499,84 @@ mod tests {
         let messages = collect_logs.messages.try_lock().unwrap();
         assert_eq!(*messages, &["Message 2"]);
     }
+
+    #[test]
+    fn reloadhandle_with() {
+        let reload_log = ReloadLog::new(LevelFilter::new(
+            log::Level::Info,
+            Arc::new(CollectMessages::new()),
+        ));
+        let reload_handle = reload_log.handle();
+        assert_eq!(reload_handle.with(|l| l.level()).unwrap(), log::Level::Info);
+
+        reload_handle
+            .modify(|l| l.set_level(log::Level::Debug))
+            .unwrap();
+        assert_eq!(
+            reload_handle.with(LevelFilter::level).unwrap(),
+            log::Level::Debug
+        );
+
+        drop(reload_log);
+        assert!(matches!(
+            reload_handle.with(|l| l.level()),
+            Err(ReloadError::Gone)
+        ));
+    }
+
+    #[test]
+    fn reloadlog_replace_and_modify_directly() {
+        let collect_logs_1 = Arc::new(CollectMessages::new());
+        let collect_logs_2 = Arc::new(CollectMessages::new());
+
+        let reload_log = ReloadLog::new(LevelFilter::new(log::Level::Info, collect_logs_1.clone()));
+        reload_log.log(&Record::builder().args(format_args!("Message 1")).build());
+
+        reload_log
+            .replace(LevelFilter::new(log::Level::Info, collect_logs_2.clone()))
+            .unwrap();
+        reload_log.log(&Record::builder().args(format_args!("Message 2")).build());
+
+        reload_log
+            .modify(|l| l.set_level(log::Level::Warn))
+            .unwrap();
+        reload_log.log(
+            &Record::builder()
+                .level(log::Level::Info)
+                .args(format_args!("Message 3"))
+                .build(),
+        );
+
+        let messages_1 = collect_logs_1.messages.try_lock().unwrap();
+        let messages_2 = collect_logs_2.messages.try_lock().unwrap();
+        assert_eq!(*messages_1, &["Message 1"]);
+        assert_eq!(*messages_2, &["Message 2"]);
+    }
+
+    #[test]
+    fn write_lock_duration() {
+        let reload_log = ReloadLog::new(Arc::new(CollectMessages::new()));
+        let reload_handle = reload_log.handle();
+        reload_handle.modify(|_| ()).unwrap();
+        assert_eq!(reload_log.write_lock_duration(), None);
+        assert_eq!(reload_handle.write_lock_duration().unwrap(), None);
+
+        let reload_log = reload_log.timing_write_locks();
+        assert_eq!(reload_log.write_lock_duration(), Some(Duration::ZERO));
+        reload_handle
+            .modify(|_| std::thread::sleep(Duration::from_millis(20)))
+            .unwrap();
+        let duration = reload_handle.write_lock_duration().unwrap().unwrap();
+        assert!(duration >= Duration::from_millis(20), "{duration:?}");
+        reload_log
+            .replace(Arc::new(CollectMessages::new()))
+            .unwrap();
+        assert!(reload_log.write_lock_duration().unwrap() >= duration);
+
+        drop(reload_log);
+        assert!(matches!(
+            reload_handle.write_lock_duration(),
+            Err(ReloadError::Gone)
+        ));
+    }
 }
@@ -12,36 +12,86 @@
 //!
 //! For the `journal` target this crate uses the [`systemd_journal_logger`] crate.
 //!
+//! With the `fern` feature this crate provides a `FernFactory` which creates
+//! console loggers from a `fern::Dispatch`.  Likewise, the `env_logger`
+//! feature provides an `EnvLoggerFactory` which creates console loggers from an
+//! `env_logger::Builder`, and `EnvLoggerLogControl1`, a [`LogController`] on
+//! top of `env_logger`.
+//!
+//! [`WriterLogFactory`] writes console records to a shared writer, which helps
+//! to capture console output in tests.
+//!
+//! Besides the console and the journal [`LogController`] supports custom
+//! `socket:<path>` targets, which send records as datagrams to a Unix socket,
+//! see [`SOCKET_TARGET_PREFIX`] and [`DatagramSocketLog`], and a custom
+//! `channel` target, which sends records to a consumer in the same process,
+//! see [`CHANNEL_TARGET`] and [`ChannelLog`].
+//!
+//! [`install_panic_logger`] logs panics through the installed logger, so that
+//! panics of services end up in the journal.
+//!
 //! See [`LogController::install_auto`] for the recommended entry point to this crate.
 
 #![deny(warnings, clippy::all, missing_docs)]
 #![forbid(unsafe_code)]
 
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::os::unix::net::UnixDatagram;
+use std::panic::Location;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use log::kv::{Key, Source, Value, VisitSource};
 use log::Log;
 use log_reload::LevelFilter;
 use log_reload::ReloadHandle;
 use log_reload::ReloadLog;
 use logcontrol::KnownLogTarget;
+use logcontrol::LevelGuard;
 use logcontrol::LogControl1;
 use logcontrol::LogControl1Error;
+use logcontrol::LogControlCapabilities;
 use logcontrol::LogLevel;
+use logcontrol::UnsupportedTargetPolicy;
 
 pub use logcontrol;
 pub use logcontrol::stderr_connected_to_journal;
 pub use logcontrol::syslog_identifier;
 use systemd_journal_logger::JournalLog;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The prefix of custom log targets which send records to a Unix datagram socket.
+///
+/// [`LogController`] supports targets of the form `socket:<path>`, e.g.
+/// `socket:/run/collector.sock`, and creates loggers for these targets with
+/// [`LogFactory::create_socket_log`].
+pub const SOCKET_TARGET_PREFIX: &str = "socket:";
+
+/// The custom log target which sends records to a channel.
+///
+/// [`LogController`] creates loggers for this target with
+/// [`LogFactory::create_channel_log`], see [`ChannelLog`].
+pub const CHANNEL_TARGET: &str = "channel";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum SupportedLogTarget {
     Console,
     Journal,
+    /// A socket target, with the complete target including [`SOCKET_TARGET_PREFIX`].
+    Socket(String),
+    Channel,
 }
 
-impl From<SupportedLogTarget> for KnownLogTarget {
-    fn from(value: SupportedLogTarget) -> Self {
-        match value {
-            SupportedLogTarget::Console => KnownLogTarget::Console,
-            SupportedLogTarget::Journal => KnownLogTarget::Journal,
+impl SupportedLogTarget {
+    fn as_str(&self) -> &str {
+        match self {
+            SupportedLogTarget::Console => KnownLogTarget::Console.as_str(),
+            SupportedLogTarget::Journal => KnownLogTarget::Journal.as_str(),
+            SupportedLogTarget::Socket(target) => target,
+            SupportedLogTarget::Channel => CHANNEL_TARGET,
         }
     }
 }
@@ -50,9 +100,11 @@ fn from_known_log_target(
     target: KnownLogTarget,
     connected_to_journal: bool,
 ) -> Result<SupportedLogTarget, LogControl1Error> {
+    let target = match target {
+        KnownLogTarget::Auto => logcontrol::resolve_auto(connected_to_journal),
+        other => other,
+    };
     match target {
-        KnownLogTarget::Auto if connected_to_journal => Ok(SupportedLogTarget::Journal),
-        KnownLogTarget::Auto => Ok(SupportedLogTarget::Console),
         KnownLogTarget::Console => Ok(SupportedLogTarget::Console),
         KnownLogTarget::Journal => Ok(SupportedLogTarget::Journal),
         other => Err(LogControl1Error::UnsupportedLogTarget(
@@ -61,6 +113,23 @@ fn from_known_log_target(
     }
 }
 
+/// Parse a `target` given to [`LogController`].
+///
+/// Support custom socket and channel targets in addition to known targets.  Ignore leading
+/// and trailing whitespace, which `systemctl` passes through.
+fn parse_log_target(
+    target: &str,
+    connected_to_journal: bool,
+) -> Result<SupportedLogTarget, LogControl1Error> {
+    let target = target.trim();
+    match target.strip_prefix(SOCKET_TARGET_PREFIX) {
+        Some(path) if !path.is_empty() => Ok(SupportedLogTarget::Socket(target.to_string())),
+        Some(_) => Err(LogControl1Error::UnsupportedLogTarget(target.to_string())),
+        None if target == CHANNEL_TARGET => Ok(SupportedLogTarget::Channel),
+        None => from_known_log_target(KnownLogTarget::try_from(target)?, connected_to_journal),
+    }
+}
+
 /// Convert [`logcontrol::LogLevel`] to [`log::Level`].
 ///
 /// Return an error if the systemd log level is not supported, i.e. does not map to a
@@ -76,6 +145,16 @@ pub fn from_log_level(level: LogLevel) -> Result<log::Level, LogControl1Error> {
     }
 }
 
+/// Convert [`logcontrol::LogLevel`] to [`log::LevelFilter`].
+///
+/// Map levels like [`from_log_level`], but saturate the levels above
+/// [`LogLevel::Err`] which have no corresponding [`log::Level`]: emerg, alert,
+/// and crit all map to [`log::LevelFilter::Error`], which is the least verbose
+/// filter that still lets messages at these levels through.
+pub fn level_filter_from_log_level(level: LogLevel) -> log::LevelFilter {
+    from_log_level(level).map_or(log::LevelFilter::Error, |level| level.to_level_filter())
+}
+
 /// Convert [`log::Level`] to [`logcontrol::LogLevel`].
 fn to_log_level(level: log::Level) -> LogLevel {
     match level {
@@ -88,14 +167,32 @@ fn to_log_level(level: log::Level) -> LogLevel {
 }
 
 fn create_logger<F: LogFactory>(
-    target: SupportedLogTarget,
+    target: &SupportedLogTarget,
     factory: &F,
     syslog_identifier: &str,
 ) -> Result<Box<dyn Log>, LogControl1Error> {
     match target {
         SupportedLogTarget::Console => factory.create_console_log(),
         SupportedLogTarget::Journal => factory.create_journal_log(syslog_identifier.to_string()),
+        SupportedLogTarget::Socket(target) => {
+            let path = &target[SOCKET_TARGET_PREFIX.len()..];
+            factory.create_socket_log(Path::new(path))
+        }
+        SupportedLogTarget::Channel => factory.create_channel_log(),
+    }
+}
+
+/// A logger which drops all records, for a muted [`LogController`].
+struct MutedLog;
+
+impl Log for MutedLog {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        false
     }
+
+    fn log(&self, _record: &log::Record) {}
+
+    fn flush(&self) {}
 }
 
 /// A factory for log implementations.
@@ -108,6 +205,7 @@ pub trait LogFactory {
     /// The implementation should use `syslog_identifier` for the corresponding journal field.
     ///
     /// The default implementation creates a [`systemd_journal_logger::JournalLog`].
+    /// Wrap a factory in [`CodeFuncFactory`] to add the `CODE_FUNC` field.
     fn create_journal_log(
         &self,
         syslog_identifier: String,
@@ -116,6 +214,617 @@ pub trait LogFactory {
             JournalLog::empty()?.with_syslog_identifier(syslog_identifier),
         ))
     }
+
+    /// Create a logger for a socket target `socket:<path>`.
+    ///
+    /// The default implementation creates a [`DatagramSocketLog`] connected to
+    /// `path`.
+    fn create_socket_log(&self, path: &Path) -> Result<Box<dyn Log>, LogControl1Error> {
+        Ok(Box::new(DatagramSocketLog::connect(path)?))
+    }
+
+    /// Create a logger for the `channel` target, see [`CHANNEL_TARGET`].
+    ///
+    /// Implementations typically return a clone of a [`ChannelLog`] whose
+    /// receiver the application drains.  The default implementation fails
+    /// with [`LogControl1Error::UnsupportedLogTarget`].
+    fn create_channel_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+        Err(LogControl1Error::UnsupportedLogTarget(
+            CHANNEL_TARGET.to_string(),
+        ))
+    }
+}
+
+/// A logger which sends records as datagrams to a Unix socket.
+///
+/// Send one datagram per record, with a single line in the format
+/// `LEVEL target: message`, e.g. to a collector running as sidecar.  Sending
+/// blocks if the receive buffer of the socket is full.
+///
+/// [`log::Log::log`] has no way to report errors, so it ignores failures to
+/// send records; use [`Self::send`] to handle them.
+#[derive(Debug)]
+pub struct DatagramSocketLog {
+    socket: UnixDatagram,
+}
+
+impl DatagramSocketLog {
+    /// Connect to the datagram socket at `path`.
+    ///
+    /// Return an error if `path` does not exist, or is no datagram socket.
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self { socket })
+    }
+
+    /// Send `record` to the socket.
+    ///
+    /// Return an error if sending fails, e.g. if the receiving end is gone.
+    pub fn send(&self, record: &log::Record) -> io::Result<()> {
+        let payload = format!("{} {}: {}", record.level(), record.target(), record.args());
+        self.socket.send(payload.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Log for DatagramSocketLog {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let _ = self.send(record);
+    }
+
+    fn flush(&self) {}
+}
+
+/// A logger which sends formatted records to a bounded channel.
+///
+/// Format each record as a single line `LEVEL target: message`, like
+/// [`DatagramSocketLog`], and send it to the channel without blocking, e.g. to
+/// ship records over the network from an async task.  If the channel is full,
+/// or the receiver is gone, drop the record and count it, see [`Self::dropped`].
+///
+/// Clones send to the same channel and share the count of dropped records.
+/// Return a clone from [`LogFactory::create_channel_log`] to select this
+/// logger with the `channel` target.
+#[derive(Debug, Clone)]
+pub struct ChannelLog {
+    sender: SyncSender<String>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ChannelLog {
+    /// Create a logger and the receiver of its channel.
+    ///
+    /// The channel holds up to `capacity` records.  Receiving blocks, so drain
+    /// the receiver in a thread or a blocking task of the async runtime.
+    pub fn bounded(capacity: usize) -> (Self, Receiver<String>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let log = Self {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        (log, receiver)
+    }
+
+    /// The number of records dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Log for ChannelLog {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+        match self.sender.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Add the `CODE_FUNC` journal field to log records.
+///
+/// [`JournalLog`] already adds `CODE_FILE`, `CODE_LINE`, and `CODE_MODULE` from
+/// [`log::Record::file`], [`log::Record::line`], and [`log::Record::module_path`],
+/// but [`log`] has no notion of the function a record originates from.  This
+/// logger adds the next best thing, the [`log::Record::module_path`], as
+/// `CODE_FUNC` key-value to each record, which [`JournalLog`] then sends as
+/// `CODE_FUNC` journal field.
+///
+/// Note that `CODE_FUNC` thus contains the module path, not the name of a
+/// function, and duplicates the `CODE_MODULE` field.  Use this logger only if
+/// your tools need `CODE_FUNC` to be present at all.  [`CodeFuncFactory`]
+/// wraps all journal loggers of a [`LogController`] in this logger.
+///
+/// Records without module path are forwarded unchanged.
+#[derive(Debug)]
+pub struct CodeFuncLog<T> {
+    inner: T,
+}
+
+impl<T> CodeFuncLog<T> {
+    /// Add `CODE_FUNC` to all records forwarded to `inner`.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Log> Log for CodeFuncLog<T> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        match record.module_path() {
+            Some(module_path) => {
+                let key_values = WithCodeFunc {
+                    source: record.key_values(),
+                    code_func: module_path,
+                };
+                self.inner
+                    .log(&record.to_builder().key_values(&key_values).build());
+            }
+            None => self.inner.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// A [`LogFactory`] which adds the `CODE_FUNC` field to journal loggers.
+///
+/// Wrap the journal loggers of the inner factory in [`CodeFuncLog`], and
+/// create all other loggers with the inner factory unchanged.  Journal entries
+/// do not contain `CODE_FUNC` by default; use this factory to opt in:
+///
+/// ```rust
+/// use logcontrol_log::{CodeFuncFactory, WriterLogFactory};
+///
+/// let factory = CodeFuncFactory::new(WriterLogFactory::new(std::io::stderr()));
+/// ```
+#[derive(Debug)]
+pub struct CodeFuncFactory<F> {
+    inner: F,
+}
+
+impl<F: LogFactory> CodeFuncFactory<F> {
+    /// Add `CODE_FUNC` to the journal loggers of `inner`.
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+impl<F: LogFactory> LogFactory for CodeFuncFactory<F> {
+    fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+        self.inner.create_console_log()
+    }
+
+    fn create_journal_log(
+        &self,
+        syslog_identifier: String,
+    ) -> Result<Box<dyn Log>, LogControl1Error> {
+        let journal = self.inner.create_journal_log(syslog_identifier)?;
+        Ok(Box::new(CodeFuncLog::new(journal)))
+    }
+
+    fn create_socket_log(&self, path: &Path) -> Result<Box<dyn Log>, LogControl1Error> {
+        self.inner.create_socket_log(path)
+    }
+
+    fn create_channel_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+        self.inner.create_channel_log()
+    }
+}
+
+/// Key-values of a record, followed by `CODE_FUNC`.
+struct WithCodeFunc<'a> {
+    source: &'a dyn Source,
+    code_func: &'a str,
+}
+
+impl Source for WithCodeFunc<'_> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), log::kv::Error> {
+        self.source.visit(visitor)?;
+        visitor.visit_pair(Key::from_str("CODE_FUNC"), Value::from(self.code_func))
+    }
+}
+
+/// A [`LogFactory`] which uses [`fern`] for the console log target.
+///
+/// Invokes the given function to create a new [`fern::Dispatch`] whenever the
+/// console target is selected.
+///
+/// [`LogController`] applies the level selected over the log control interface,
+/// so this factory resets the default level of the dispatch to
+/// [`log::LevelFilter::Trace`].  Levels for specific targets set with
+/// [`fern::Dispatch::level_for`] still apply on top of the selected level.
+///
+/// For the journal target this factory uses the default [`LogFactory::create_journal_log`].
+///
+/// ```rust
+/// use logcontrol_log::{FernFactory, LogController};
+///
+/// let factory = FernFactory::new(|| {
+///     fern::Dispatch::new()
+///         .format(|out, message, record| {
+///             out.finish(format_args!("[{}] {}", record.level(), message))
+///         })
+///         .chain(std::io::stderr())
+/// });
+/// let (control, logger) = LogController::new_auto(factory, log::Level::Info).unwrap();
+/// ```
+#[cfg(feature = "fern")]
+pub struct FernFactory<D> {
+    dispatch: D,
+}
+
+#[cfg(feature = "fern")]
+impl<D: Fn() -> fern::Dispatch> FernFactory<D> {
+    /// Create a new factory which uses `dispatch` to create console loggers.
+    pub fn new(dispatch: D) -> Self {
+        Self { dispatch }
+    }
+}
+
+#[cfg(feature = "fern")]
+impl<D: Fn() -> fern::Dispatch> LogFactory for FernFactory<D> {
+    fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+        let (_, log) = (self.dispatch)().level(log::LevelFilter::Trace).into_log();
+        Ok(log)
+    }
+}
+
+/// A [`LogFactory`] which uses [`env_logger`] for the console log target.
+///
+/// Invokes the given function to create a new [`env_logger::Builder`] whenever
+/// the console target is selected.
+///
+/// [`LogController`] applies the level selected over the log control interface,
+/// so this factory resets the default filter of the builder, e.g. from
+/// `$RUST_LOG`, to [`log::LevelFilter::Trace`].  Filters for specific modules
+/// still apply on top of the selected level.
+///
+/// For the journal target this factory uses the default [`LogFactory::create_journal_log`].
+///
+/// ```rust
+/// use logcontrol_log::{EnvLoggerFactory, EnvLoggerLogControl1};
+///
+/// let factory = EnvLoggerFactory::default();
+/// let (control, logger) = EnvLoggerLogControl1::new_auto(factory, log::Level::Info).unwrap();
+/// ```
+#[cfg(feature = "env_logger")]
+pub struct EnvLoggerFactory<B> {
+    builder: B,
+}
+
+#[cfg(feature = "env_logger")]
+impl<B: Fn() -> env_logger::Builder> EnvLoggerFactory<B> {
+    /// Create a new factory which uses `builder` to create console loggers.
+    pub fn new(builder: B) -> Self {
+        Self { builder }
+    }
+}
+
+/// Create console loggers from [`env_logger::Builder::from_default_env`].
+#[cfg(feature = "env_logger")]
+impl Default for EnvLoggerFactory<fn() -> env_logger::Builder> {
+    fn default() -> Self {
+        Self::new(env_logger::Builder::from_default_env)
+    }
+}
+
+#[cfg(feature = "env_logger")]
+impl<B: Fn() -> env_logger::Builder> LogFactory for EnvLoggerFactory<B> {
+    fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+        let log = (self.builder)()
+            .filter_level(log::LevelFilter::Trace)
+            .build();
+        Ok(Box::new(log))
+    }
+}
+
+/// A [`LogController`] on top of [`env_logger`].
+///
+/// Supports the console target through [`EnvLoggerFactory`], and the journal
+/// and the auto target like all [`LogController`]s.
+#[cfg(feature = "env_logger")]
+pub type EnvLoggerLogControl1<B = fn() -> env_logger::Builder> = LogController<EnvLoggerFactory<B>>;
+
+/// A [`LogFactory`] which writes console records to a shared writer.
+///
+/// All console loggers created by this factory write to the same writer, one
+/// line per record in the format `LEVEL target: message`.  Pass an
+/// `Arc<Mutex<Vec<u8>>>` to [`Self::from_shared`] to capture console output,
+/// e.g. in tests:
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+/// use logcontrol_log::{LogController, WriterLogFactory};
+/// use logcontrol_log::logcontrol::KnownLogTarget;
+///
+/// let output = Arc::new(Mutex::new(Vec::new()));
+/// let (control, logger) = LogController::new(
+///     WriterLogFactory::from_shared(output.clone()),
+///     false,
+///     "example".to_string(),
+///     KnownLogTarget::Console,
+///     log::Level::Info,
+/// )
+/// .unwrap();
+/// ```
+///
+/// For the journal target this factory uses the default [`LogFactory::create_journal_log`].
+#[derive(Debug)]
+pub struct WriterLogFactory<W> {
+    writer: Arc<Mutex<W>>,
+    priority_prefix: bool,
+}
+
+impl<W: Write + Send + 'static> WriterLogFactory<W> {
+    /// Create a new factory whose console loggers write to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self::from_shared(Arc::new(Mutex::new(writer)))
+    }
+
+    /// Create a new factory whose console loggers write to a shared `writer`.
+    pub fn from_shared(writer: Arc<Mutex<W>>) -> Self {
+        Self {
+            writer,
+            priority_prefix: false,
+        }
+    }
+
+    /// Whether to prefix every line with the journal priority of the record.
+    ///
+    /// Defaults to `false`.  When stdout or stderr of a service goes to the
+    /// journal, journald reads a `<N>` prefix at the start of each line as the
+    /// syslog priority `N` of the line, see
+    /// [`sd-daemon(3)`](https://www.freedesktop.org/software/systemd/man/sd-daemon.html).
+    /// Enable this if `writer` goes to the journal, e.g. with
+    /// [`logcontrol::stderr_connected_to_journal`], so that the journal gets
+    /// the priorities of records even with the console target.
+    pub fn with_priority_prefix(mut self, priority_prefix: bool) -> Self {
+        self.priority_prefix = priority_prefix;
+        self
+    }
+}
+
+impl<W: Write + Send + 'static> LogFactory for WriterLogFactory<W> {
+    fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+        Ok(Box::new(WriterLog {
+            writer: self.writer.clone(),
+            priority_prefix: self.priority_prefix,
+        }))
+    }
+}
+
+/// A console logger which writes records to a shared writer.
+struct WriterLog<W> {
+    writer: Arc<Mutex<W>>,
+    /// Whether to prefix lines with `<N>`.
+    priority_prefix: bool,
+}
+
+impl<W: Write + Send> Log for WriterLog<W> {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        // Keep logging if a previous write panicked, and ignore write errors,
+        // because a logger has no way to report them
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.priority_prefix {
+            let priority = to_log_level(record.level()).as_priority();
+            let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+            for line in line.lines() {
+                let _ = writeln!(writer, "<{priority}>{line}");
+            }
+        } else {
+            let _ = writeln!(
+                writer,
+                "{} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        let _ = self
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .flush();
+    }
+}
+
+/// Hold back records below the level, and emit them once an error occurs.
+///
+/// This logger wraps a [`LevelFilter`], and keeps the last `capacity` records
+/// which do not pass the level of the filter in a ring buffer, instead of
+/// discarding them right away.  When a record at or above the `trigger` level
+/// passes the filter, the logger first forwards all buffered records to the
+/// inner logger, and then the triggering record.  This way, applications can
+/// stay quiet at a high level, and still provide context for errors.
+///
+/// If the buffer is full, the oldest record is discarded.  Buffered records
+/// are owned copies of the original records; they retain level, target,
+/// message, source location, and key-values.
+///
+/// ```rust
+/// use log_reload::LevelFilter;
+/// use logcontrol_log::BufferingLevelFilter;
+///
+/// // Log warnings and errors, and on errors add the last 100 suppressed records.
+/// let filter = LevelFilter::new(log::Level::Warn, env_logger::Logger::from_default_env());
+/// let logger = BufferingLevelFilter::new(filter, log::Level::Error, 100);
+/// ```
+#[derive(Debug)]
+pub struct BufferingLevelFilter<T> {
+    filter: LevelFilter<T>,
+    trigger: log::Level,
+    capacity: usize,
+    buffer: Mutex<VecDeque<BufferedRecord>>,
+}
+
+impl<T> BufferingLevelFilter<T> {
+    /// Buffer up to `capacity` records which do not pass `filter`, until a record at or above `trigger` passes.
+    pub fn new(filter: LevelFilter<T>, trigger: log::Level, capacity: usize) -> Self {
+        Self {
+            filter,
+            trigger,
+            capacity,
+            buffer: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Get the level filter.
+    pub fn filter(&self) -> &LevelFilter<T> {
+        &self.filter
+    }
+
+    /// Change the maximum log level of the filter.
+    pub fn set_level(&mut self, level: log::Level) {
+        self.filter.set_level(level);
+    }
+
+    /// Get the level at which buffered records are emitted.
+    pub fn trigger(&self) -> log::Level {
+        self.trigger
+    }
+
+    /// Get the maximum number of buffered records.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Lock the buffer.
+    ///
+    /// The buffer only holds records which are discarded anyway, so we just
+    /// continue through a poisoned lock.
+    fn buffer(&self) -> MutexGuard<'_, VecDeque<BufferedRecord>> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Log> Log for BufferingLevelFilter<T> {
+    /// Whether this logger is enabled.
+    ///
+    /// Return `true` if the underlying logger is enabled, and the record either
+    /// passes the level filter or goes to the buffer.
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        (metadata.level() <= self.filter.level() || 0 < self.capacity)
+            && self.filter.inner().enabled(metadata)
+    }
+
+    /// Forward `record` to the level filter, or buffer it if it doesn't pass.
+    ///
+    /// If `record` passes and is at or above the trigger level, forward all
+    /// buffered records first.
+    fn log(&self, record: &log::Record) {
+        if record.level() <= self.filter.level() {
+            if record.level() <= self.trigger {
+                let buffered = std::mem::take(&mut *self.buffer());
+                for buffered_record in buffered {
+                    buffered_record.log_to(self.filter.inner());
+                }
+            }
+            self.filter.log(record);
+        } else if 0 < self.capacity {
+            let mut buffer = self.buffer();
+            if buffer.len() == self.capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back(BufferedRecord::from_record(record));
+        }
+    }
+
+    /// Flush the underlying logger.
+    ///
+    /// This does not emit buffered records.
+    fn flush(&self) {
+        self.filter.flush();
+    }
+}
+
+/// An owned copy of a record, held back by [`BufferingLevelFilter`].
+#[derive(Debug)]
+struct BufferedRecord {
+    level: log::Level,
+    target: String,
+    message: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    key_values: OwnedKeyValues,
+}
+
+impl BufferedRecord {
+    fn from_record(record: &log::Record) -> Self {
+        let mut key_values = OwnedKeyValues::default();
+        // Collecting into a vec never fails, except if the source fails itself,
+        // in which case we just keep whatever key-values we got so far.
+        let _ = record.key_values().visit(&mut key_values);
+        Self {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            module_path: record.module_path().map(ToString::to_string),
+            file: record.file().map(ToString::to_string),
+            line: record.line(),
+            key_values,
+        }
+    }
+
+    fn log_to<L: Log>(&self, logger: &L) {
+        logger.log(
+            &log::Record::builder()
+                .level(self.level)
+                .target(&self.target)
+                .args(format_args!("{}", self.message))
+                .module_path(self.module_path.as_deref())
+                .file(self.file.as_deref())
+                .line(self.line)
+                .key_values(&self.key_values)
+                .build(),
+        );
+    }
+}
+
+/// Owned key-values of a [`BufferedRecord`].
+#[derive(Debug, Default)]
+struct OwnedKeyValues(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for OwnedKeyValues {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+impl Source for OwnedKeyValues {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), log::kv::Error> {
+        for (key, value) in &self.0 {
+            visitor.visit_pair(Key::from_str(key), Value::from(value.as_str()))?;
+        }
+        Ok(())
+    }
 }
 
 /// The type of a controlled [`log::Log`].
@@ -134,6 +843,10 @@ pub type ControlledLog = ReloadLog<LevelFilter<Box<dyn Log>>>;
 /// - [`KnownLogTarget::Journal`]
 /// - [`KnownLogTarget::Auto`]
 ///
+/// Additionally, it supports custom `socket:<path>` targets, see
+/// [`SOCKET_TARGET_PREFIX`], and the custom `channel` target, see
+/// [`CHANNEL_TARGET`].
+///
 /// Any other target fails with [`LogControl1Error::UnsupportedLogTarget`].
 pub struct LogController<F: LogFactory> {
     /// The reload handler.
@@ -148,6 +861,16 @@ pub struct LogController<F: LogFactory> {
     level: LogLevel,
     /// The current target active in the target layer.
     target: SupportedLogTarget,
+    /// Whether [`KnownLogTarget::Auto`] selected the current target.
+    auto_detected: bool,
+    /// What to do if an unsupported target is requested.
+    unsupported_target_policy: UnsupportedTargetPolicy,
+    /// Levels to apply when switching to a target.
+    target_default_levels: HashMap<KnownLogTarget, LogLevel>,
+    /// Targets to try in order if switching to a target fails.
+    target_fallback_chain: Vec<KnownLogTarget>,
+    /// Whether the controlled logger currently drops all records.
+    muted: bool,
 }
 
 impl<F: LogFactory> LogController<F> {
@@ -179,51 +902,341 @@ impl<F: LogFactory> LogController<F> {
         level: log::Level,
     ) -> Result<(Self, ControlledLog), LogControl1Error> {
         let log_target = from_known_log_target(target, connected_to_journal)?;
-        let inner_logger = create_logger(log_target, &factory, &syslog_identifier)?;
-        let log = ReloadLog::new(LevelFilter::new(level, inner_logger));
-        let control = Self {
-            handle: log.handle(),
+        let inner_logger = create_logger(&log_target, &factory, &syslog_identifier)?;
+        Ok(Self::from_logger(
             factory,
             connected_to_journal,
             syslog_identifier,
-            level: to_log_level(level),
-            target: log_target,
-        };
-        Ok((control, log))
+            target,
+            log_target,
+            inner_logger,
+            level,
+        ))
     }
 
-    /// Create a new logger which can be controlled through the log control interface, using automatic defaults.
+    /// Create a new logger which can be controlled through the log control interface, with the first working target of `chain`.
     ///
-    /// Use [`logcontrol::syslog_identifier()`] as the syslog identifier, and
-    /// determine the initial log target automatically according to
-    /// [`logcontrol::stderr_connected_to_journal()`].
+    /// Try each target of `chain` in order as initial target, and start with
+    /// the first target for which creating a logger succeeds, e.g. to prefer
+    /// the journal, and fall back to the console.  The controller then uses
+    /// `chain` as fallback chain for [`LogControl1::set_target`], see
+    /// [`Self::with_target_fallback_chain`].
     ///
-    /// `level` denotes the initial level; for `factory` and returned errors,
-    ///  see [`Self::new`].
-    pub fn new_auto(
+    /// Return the error for the first target if all targets in `chain` fail,
+    /// and [`LogControl1Error::Failure`] if `chain` is empty.  For all other
+    /// arguments see [`Self::new`].
+    pub fn new_with_target_fallback_chain(
         factory: F,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        chain: Vec<KnownLogTarget>,
         level: log::Level,
     ) -> Result<(Self, ControlledLog), LogControl1Error> {
-        Self::new(
+        let (target, (log_target, inner_logger)) =
+            logcontrol::first_working_target(&chain, |target| {
+                let log_target = from_known_log_target(target, connected_to_journal)?;
+                let inner_logger = create_logger(&log_target, &factory, &syslog_identifier)?;
+                Ok((log_target, inner_logger))
+            })?;
+        let (control, log) = Self::from_logger(
             factory,
-            logcontrol::stderr_connected_to_journal(),
-            logcontrol::syslog_identifier(),
-            KnownLogTarget::Auto,
+            connected_to_journal,
+            syslog_identifier,
+            target,
+            log_target,
+            inner_logger,
             level,
-        )
+        );
+        Ok((control.with_target_fallback_chain(chain), log))
     }
 
-    /**
-     * Create and install a controlled logger, with automatic defaults.
-     *
-     * See [`Self::new_auto`] for arguments and errors. Additionally, this function
-     * fails with [`LogControl1Error::Failure`] if [`log::set_boxed_logger`] fails.
-     */
-    pub fn install_auto(factory: F, level: log::Level) -> Result<Self, LogControl1Error> {
-        let (control, logger) = Self::new_auto(factory, level)?;
-        log::set_boxed_logger(Box::new(logger))
-            .map_err(|error| LogControl1Error::Failure(format!("{error}")))?;
-        Ok(control)
+    /// Create a new controller around the given initial `inner_logger`.
+    fn from_logger(
+        factory: F,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        target: KnownLogTarget,
+        log_target: SupportedLogTarget,
+        inner_logger: Box<dyn Log>,
+        level: log::Level,
+    ) -> (Self, ControlledLog) {
+        let log = ReloadLog::new(LevelFilter::new(level, inner_logger));
+        let control = Self {
+            handle: log.handle(),
+            factory,
+            connected_to_journal,
+            syslog_identifier,
+            level: to_log_level(level),
+            target: log_target,
+            auto_detected: target == KnownLogTarget::Auto,
+            unsupported_target_policy: UnsupportedTargetPolicy::default(),
+            target_default_levels: HashMap::new(),
+            target_fallback_chain: Vec::new(),
+            muted: false,
+        };
+        (control, log)
+    }
+
+    /// Log a message which describes the current configuration.
+    ///
+    /// Log the target, the level, the syslog identifier, and whether the
+    /// target was automatically detected, at the current level, to help
+    /// debugging the logging setup itself.  See
+    /// [`logcontrol::describe_configuration`] for the message.
+    ///
+    /// Services typically call this once after installing the logger.
+    pub fn log_startup_message(&self) {
+        let message = logcontrol::describe_configuration(self, self.auto_detected);
+        let level = from_log_level(self.level).unwrap_or(log::Level::Error);
+        self.log_message(level, format_args!("{message}"));
+    }
+
+    /// Get the numeric syslog priority of the current level.
+    ///
+    /// Return [`LogLevel::as_priority`] of [`LogControl1::level`], i.e. the
+    /// largest journal priority which currently passes the level filter, for
+    /// services which also write to the journal by other means.
+    pub fn current_priority(&self) -> u8 {
+        self.level.as_priority()
+    }
+
+    /// Change the policy for unsupported log targets.
+    ///
+    /// By default [`LogControl1::set_target`] fails for unsupported targets;
+    /// see [`UnsupportedTargetPolicy`] for alternatives.  With
+    /// [`UnsupportedTargetPolicy::KeepCurrent`] the warning goes to the
+    /// controlled logger.
+    pub fn with_unsupported_target_policy(mut self, policy: UnsupportedTargetPolicy) -> Self {
+        self.unsupported_target_policy = policy;
+        self
+    }
+
+    /// Set the default level of `target`.
+    ///
+    /// Whenever [`LogControl1::set_target`] switches to `target`, change the
+    /// level to `level` as well, e.g. to log more verbosely on the console than
+    /// to the journal.  Targets without a default level keep the current level.
+    ///
+    /// The default applies to the target which `set_target` actually selects,
+    /// so [`KnownLogTarget::Auto`] applies the default of the journal or the
+    /// console, and a default for `Auto` itself never applies.  Socket targets
+    /// have no default level, and the default does not apply to the initial
+    /// target of this controller.
+    ///
+    /// An explicit [`LogControl1::set_level`] overrides the default until the
+    /// next switch to a target with a default level; [`LogControl1::reconfigure`]
+    /// always uses its explicit level.
+    pub fn with_target_default_level(mut self, target: KnownLogTarget, level: LogLevel) -> Self {
+        self.target_default_levels.insert(target, level);
+        self
+    }
+
+    /// Set the chain of targets to fall back to.
+    ///
+    /// If [`LogControl1::set_target`] fails to switch to the requested target,
+    /// try the targets in `chain` in order, skipping the requested target, and
+    /// stay with the first target which works, e.g. to fall back from the
+    /// journal to the console.  [`LogControl1::target`] then returns the
+    /// fallback target.
+    ///
+    /// If all targets of the chain fail as well, `set_target` returns the
+    /// error for the requested target, subject to the [`UnsupportedTargetPolicy`]
+    /// of this controller.  An empty chain, the default, disables fallbacks.
+    pub fn with_target_fallback_chain(mut self, chain: Vec<KnownLogTarget>) -> Self {
+        self.target_fallback_chain = chain;
+        self
+    }
+
+    /// Switch to `target`, or to the first working target of the fallback chain.
+    fn switch_target_with_fallback(&mut self, target: &str) -> Result<(), LogControl1Error> {
+        self.switch_target(target).or_else(|error| {
+            let chain = self
+                .target_fallback_chain
+                .iter()
+                .copied()
+                .filter(|fallback| fallback.as_str() != target.trim())
+                .collect::<Vec<_>>();
+            logcontrol::first_working_target(&chain, |fallback| {
+                self.switch_target(fallback.as_str())
+            })
+            .map(|_| ())
+            .map_err(|_| error)
+        })
+    }
+
+    /// Get the default level of the given target, if any.
+    fn target_default_level(&self, target: &SupportedLogTarget) -> Option<LogLevel> {
+        let target = KnownLogTarget::try_from(target.as_str()).ok()?;
+        self.target_default_levels.get(&target).copied()
+    }
+
+    /// Change the syslog identifier.
+    ///
+    /// If the journal is the current target, create a new journal logger with
+    /// the new `syslog_identifier`, so that all subsequent records carry the new
+    /// identifier.  Otherwise just remember the identifier for the next switch
+    /// to the journal target.
+    ///
+    /// Return an error if creating the new journal logger fails; the
+    /// previous identifier remains in place then.
+    pub fn set_syslog_identifier(
+        &mut self,
+        syslog_identifier: String,
+    ) -> Result<(), LogControl1Error> {
+        if self.target == SupportedLogTarget::Journal {
+            let new_logger = create_logger(&self.target, &self.factory, &syslog_identifier)?;
+            self.set_inner(new_logger).map_err(|error| {
+                LogControl1Error::reload_failed(
+                    format!("Failed to change syslog identifier to {syslog_identifier}"),
+                    error,
+                )
+            })?;
+        }
+        self.syslog_identifier = syslog_identifier;
+        Ok(())
+    }
+
+    /// Log a message from this controller.
+    ///
+    /// Log through the handle, so that the message also reaches a logger
+    /// which is not (yet) installed.
+    fn log_message(&self, level: log::Level, args: std::fmt::Arguments) {
+        let _ = self.handle.with(|l| {
+            l.log(
+                &log::Record::builder()
+                    .args(args)
+                    .level(level)
+                    .target(module_path!())
+                    .module_path_static(Some(module_path!()))
+                    .build(),
+            );
+        });
+    }
+
+    /// Replace the controlled logger with `logger`, unless muted.
+    ///
+    /// While muted drop `logger`; unmuting creates a new logger for the
+    /// current target.
+    fn set_inner(&self, logger: Box<dyn Log>) -> Result<(), log_reload::ReloadError> {
+        if self.muted {
+            Ok(())
+        } else {
+            self.handle.modify(|l| l.set_inner(logger))
+        }
+    }
+
+    /// Change the level until the returned guard drops.
+    ///
+    /// Set `level` now, and restore the current level when the returned
+    /// [`LevelGuard`] goes out of scope, e.g. for temporary debug logging
+    /// around a single operation.  Log a warning if restoring the level fails.
+    pub fn scoped_level(
+        &mut self,
+        level: LogLevel,
+    ) -> Result<LevelGuard<'_, Self>, LogControl1Error> {
+        LevelGuard::with_restore_error_handler(self, level, |error| {
+            log::warn!("Failed to restore log level: {error}");
+        })
+    }
+
+    /// Replace the log factory, and rebuild the controlled logger with it.
+    ///
+    /// Create a new logger with `factory` for the current target, e.g. to
+    /// switch output formats at runtime, and use `factory` for all further
+    /// target changes.  If creating the new logger fails keep the current
+    /// factory and logger, and return the error.  While muted only replace the
+    /// factory; unmuting then creates a logger with `factory`.
+    ///
+    /// Flush the current logger right before replacing it, while holding the
+    /// write lock of the controlled logger, so no in-flight record goes to the
+    /// old logger after the flush; records after this call go to the new logger.
+    pub fn set_factory(&mut self, factory: F) -> Result<(), LogControl1Error> {
+        if !self.muted {
+            let new_logger = create_logger(&self.target, &factory, &self.syslog_identifier)?;
+            self.handle
+                .modify(|l| {
+                    l.flush();
+                    l.set_inner(new_logger);
+                })
+                .map_err(|error| {
+                    LogControl1Error::reload_failed(
+                        format!(
+                            "Failed to replace the factory of log target {}",
+                            self.target.as_str()
+                        ),
+                        error,
+                    )
+                })?;
+        }
+        self.factory = factory;
+        Ok(())
+    }
+
+    /// Switch to the given `target`, and apply its default level if any.
+    fn switch_target(&mut self, target: &str) -> Result<(), LogControl1Error> {
+        let log_target = parse_log_target(target, self.connected_to_journal)?;
+        let default_level = self.target_default_level(&log_target);
+        let new_logger = create_logger(&log_target, &self.factory, &self.syslog_identifier)?;
+        self.set_inner(new_logger).map_err(|error| {
+            LogControl1Error::reload_failed(
+                format!("Failed to change log target to {target}"),
+                error,
+            )
+        })?;
+        self.target = log_target;
+        self.auto_detected = target.trim() == KnownLogTarget::Auto.as_str();
+        match default_level {
+            Some(level) => self.set_level(level),
+            None => Ok(()),
+        }
+    }
+
+    /// Create a new logger which can be controlled through the log control interface, using automatic defaults.
+    ///
+    /// Use [`logcontrol::syslog_identifier()`] as the syslog identifier, and
+    /// determine the initial log target automatically according to
+    /// [`logcontrol::stderr_connected_to_journal()`].
+    ///
+    /// `level` denotes the initial level; for `factory` and returned errors,
+    ///  see [`Self::new`].
+    ///
+    /// Like systemd's own tools, take the initial level and target from
+    /// `$SYSTEMD_LOG_LEVEL` and `$SYSTEMD_LOG_TARGET` if set, see
+    /// [`logcontrol::log_level_from_systemd_env()`] and
+    /// [`logcontrol::log_target_from_systemd_env()`].  Ignore unsupported
+    /// targets in `$SYSTEMD_LOG_TARGET`, and use the most severe supported
+    /// level for unsupported levels in `$SYSTEMD_LOG_LEVEL`.
+    pub fn new_auto(
+        factory: F,
+        level: log::Level,
+    ) -> Result<(Self, ControlledLog), LogControl1Error> {
+        let connected_to_journal = logcontrol::stderr_connected_to_journal();
+        let level = logcontrol::log_level_from_systemd_env().map_or(level, |level| {
+            from_log_level(level).unwrap_or(log::Level::Error)
+        });
+        let target = logcontrol::log_target_from_systemd_env()
+            .filter(|target| from_known_log_target(*target, connected_to_journal).is_ok())
+            .unwrap_or(KnownLogTarget::Auto);
+        Self::new(
+            factory,
+            connected_to_journal,
+            logcontrol::syslog_identifier(),
+            target,
+            level,
+        )
+    }
+
+    /**
+     * Create and install a controlled logger, with automatic defaults.
+     *
+     * See [`Self::new_auto`] for arguments and errors. Additionally, this function
+     * fails with [`LogControl1Error::Failure`] if [`log::set_boxed_logger`] fails.
+     */
+    pub fn install_auto(factory: F, level: log::Level) -> Result<Self, LogControl1Error> {
+        let (control, logger) = Self::new_auto(factory, level)?;
+        log::set_boxed_logger(Box::new(logger))
+            .map_err(|error| LogControl1Error::Failure(format!("{error}")))?;
+        Ok(control)
     }
 }
 
@@ -232,43 +1245,1300 @@ impl<F: LogFactory> LogControl1 for LogController<F> {
         self.level
     }
 
+    /// Change the level.
+    ///
+    /// Accept all levels: [`LogLevel::Emerg`], [`LogLevel::Alert`] and
+    /// [`LogLevel::Crit`] filter at [`log::Level::Error`], but [`Self::level`]
+    /// still returns them as set.
     fn set_level(
         &mut self,
         level: logcontrol::LogLevel,
     ) -> Result<(), logcontrol::LogControl1Error> {
-        let log_level = from_log_level(level)?;
+        let log_level = from_log_level(level).unwrap_or(log::Level::Error);
         self.handle
             .modify(|l| l.set_level(log_level))
             .map_err(|error| {
-                LogControl1Error::Failure(format!("Failed to change level to {level}: {error}"))
+                LogControl1Error::reload_failed(format!("Failed to change level to {level}"), error)
             })?;
         self.level = level;
         Ok(())
     }
 
     fn target(&self) -> &str {
-        KnownLogTarget::from(self.target).as_str()
+        self.target.as_str()
     }
 
+    /// Switch to the given `target`.
+    ///
+    /// If switching fails try the fallback chain of this controller, see
+    /// [`LogController::with_target_fallback_chain`].  Apply the
+    /// [`UnsupportedTargetPolicy`] of this controller if `target` is not
+    /// supported.
     fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), logcontrol::LogControl1Error> {
-        let log_target = from_known_log_target(
-            KnownLogTarget::try_from(target.as_ref())?,
-            self.connected_to_journal,
-        )?;
-        let new_logger = create_logger(log_target, &self.factory, &self.syslog_identifier)?;
+        let result = self.switch_target_with_fallback(target.as_ref());
+        self.unsupported_target_policy.apply(result, |target| {
+            self.log_message(
+                log::Level::Warn,
+                format_args!("Log target {target} not supported, keeping current log target"),
+            );
+        })
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        &self.syslog_identifier
+    }
+
+    /// Change the level and the target together.
+    ///
+    /// Leave the level untouched if changing the target fails.
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        self.set_target(target)?;
+        self.set_level(level)
+    }
+
+    /// Whether logging works.
+    ///
+    /// Return `false` if the logger was dropped, if a previous change of the
+    /// logger panicked and thus disabled the logger, or if the journal is
+    /// selected but not reachable.
+    fn is_healthy(&self) -> bool {
+        // Reading fails if the logger is gone or poisoned
+        self.handle.with(|_| {}).is_ok()
+            && (self.target != SupportedLogTarget::Journal || logcontrol::journal_reachable())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "log"
+    }
+
+    fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Mute or unmute logging.
+    ///
+    /// Mute by replacing the controlled logger with a logger which drops all
+    /// records, and unmute by creating a new logger for the current target.
+    /// Level and target changes while muted take effect when unmuted.
+    ///
+    /// Return an error if creating the new logger fails; logging remains muted
+    /// then.
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        if muted == self.muted {
+            return Ok(());
+        }
+        let logger = if muted {
+            Box::new(MutedLog)
+        } else {
+            create_logger(&self.target, &self.factory, &self.syslog_identifier)?
+        };
         self.handle
-            .modify(|l| l.set_inner(new_logger))
+            .modify(|l| l.set_inner(logger))
             .map_err(|error| {
-                LogControl1Error::Failure(format!(
-                    "Failed to change log target to {}: {error}",
-                    target.as_ref()
-                ))
+                let action = if muted { "mute" } else { "unmute" };
+                LogControl1Error::reload_failed(format!("Failed to {action} logging"), error)
             })?;
-        self.target = log_target;
+        self.muted = muted;
         Ok(())
     }
 
-    fn syslog_identifier(&self) -> &str {
-        &self.syslog_identifier
+    /// Reopen the current target.
+    ///
+    /// Create a new logger for the current target, e.g. to reconnect a socket
+    /// target, and replace the controlled logger with it.  If creating the new
+    /// logger fails the current logger remains in place.  While muted this
+    /// does nothing, because unmuting creates a new logger anyway.
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        if self.muted {
+            return Ok(());
+        }
+        let new_logger = create_logger(&self.target, &self.factory, &self.syslog_identifier)?;
+        self.set_inner(new_logger).map_err(|error| {
+            LogControl1Error::reload_failed(
+                format!("Failed to reopen log target {}", self.target.as_str()),
+                error,
+            )
+        })
+    }
+
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities {
+            mute: true,
+            reopen: true,
+            health: true,
+        }
+    }
+}
+
+/// Flush the controlled logger when the controller goes away.
+///
+/// Loggers which buffer output, e.g. console loggers writing to a buffered
+/// writer, would otherwise lose records at shutdown.  Flush through the reload
+/// handle, and do nothing if the logger was dropped already, or if its lock is
+/// poisoned.
+impl<F: LogFactory> Drop for LogController<F> {
+    fn drop(&mut self) {
+        // Drop runs exactly once, so this flushes at most once
+        let _ = self.handle.with(|l| l.flush());
+    }
+}
+
+/// Log a panic with the given `payload` at `location` to `logger`.
+///
+/// Log at [`log::Level::Error`], with the message and the location of the
+/// panic and the name of the current thread, and flush `logger` afterwards, as
+/// the process may abort right after the panic.
+fn log_panic(logger: &dyn Log, payload: &(dyn Any + Send), location: Option<&Location<'_>>) {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+    let at = location.map_or_else(String::new, |location| format!(" at {location}"));
+    logger.log(
+        &log::Record::builder()
+            .args(format_args!("Thread '{thread}' panicked{at}: {message}"))
+            .level(log::Level::Error)
+            .target("panic")
+            .file(location.map(Location::file))
+            .line(location.map(Location::line))
+            .build(),
+    );
+    logger.flush();
+}
+
+/// Log panics through the installed logger.
+///
+/// Install a panic hook which logs every panic to [`log::logger`] at
+/// [`log::Level::Error`], and then calls the previous hook, e.g. the default
+/// hook which prints the panic to stderr.  With a [`LogController`] this puts
+/// panics into the journal, at error priority.
+///
+/// Call this after installing the logger, e.g. with
+/// [`LogController::install_auto`]; the hook uses whatever logger is
+/// installed at the time of the panic.
+pub fn install_panic_logger() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log_panic(log::logger(), info.payload(), info.location());
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::os::unix::net::UnixDatagram;
+    use std::panic::AssertUnwindSafe;
+    use std::panic::Location;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use log::kv::{Key, Value, VisitSource};
+    use log::{Log, Record};
+    use log_reload::LevelFilter;
+    use logcontrol::{
+        KnownLogTarget, LogControl1, LogControl1Error, LogLevel, UnsupportedTargetPolicy,
+    };
+
+    use crate::{
+        BufferingLevelFilter, ChannelLog, CodeFuncFactory, DatagramSocketLog, LogController,
+        LogFactory, WriterLogFactory,
+    };
+
+    /// Record the key-values of all records.
+    #[derive(Default)]
+    struct CollectKeyValues {
+        records: Mutex<Vec<Vec<(String, String)>>>,
+    }
+
+    struct KeyValuesVisitor<'a>(&'a mut Vec<(String, String)>);
+
+    impl<'kvs> VisitSource<'kvs> for KeyValuesVisitor<'_> {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+            self.0.push((key.to_string(), value.to_string()));
+            Ok(())
+        }
+    }
+
+    impl Log for CollectKeyValues {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let mut key_values = Vec::new();
+            record
+                .key_values()
+                .visit(&mut KeyValuesVisitor(&mut key_values))
+                .unwrap();
+            self.records.lock().unwrap().push(key_values);
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn key_values(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Create journal loggers which all collect into the same key-values.
+    struct KeyValuesFactory(Arc<CollectKeyValues>);
+
+    impl LogFactory for KeyValuesFactory {
+        fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            Ok(Box::new(self.0.clone()))
+        }
+
+        fn create_journal_log(
+            &self,
+            _syslog_identifier: String,
+        ) -> Result<Box<dyn Log>, LogControl1Error> {
+            Ok(Box::new(self.0.clone()))
+        }
+    }
+
+    #[test]
+    fn code_func_factory_adds_code_func_to_journal() {
+        let journal = Arc::new(CollectKeyValues::default());
+        let factory = CodeFuncFactory::new(KeyValuesFactory(journal.clone()));
+        let log = factory.create_journal_log("test".to_string()).unwrap();
+        let record_key_values = [("FOO", "bar")];
+        log.log(
+            &Record::builder()
+                .args(format_args!("Hello"))
+                .module_path(Some("foo::bar"))
+                .key_values(&record_key_values)
+                .build(),
+        );
+        log.log(&Record::builder().args(format_args!("No module")).build());
+        assert_eq!(
+            *journal.records.lock().unwrap(),
+            [
+                key_values(&[("FOO", "bar"), ("CODE_FUNC", "foo::bar")]),
+                key_values(&[]),
+            ]
+        );
+
+        // Other targets remain unchanged
+        let console = factory.create_console_log().unwrap();
+        console.log(
+            &Record::builder()
+                .args(format_args!("Hello"))
+                .module_path(Some("foo::bar"))
+                .build(),
+        );
+        assert_eq!(journal.records.lock().unwrap()[2], key_values(&[]));
+    }
+
+    /// Record the level and message of all records, and count flushes.
+    #[derive(Default)]
+    struct CollectMessages {
+        messages: Mutex<Vec<String>>,
+        flushes: AtomicUsize,
+    }
+
+    impl Log for CollectMessages {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let mut message = format!("{} {}", record.level(), record.args());
+            if let Some(line) = record.line() {
+                message.push_str(&format!(" at line {line}"));
+            }
+            self.messages.lock().unwrap().push(message);
+        }
+
+        fn flush(&self) {
+            self.flushes.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn log_at(log: &dyn Log, level: log::Level, message: &str) {
+        log.log(
+            &Record::builder()
+                .level(level)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    /// Create console loggers which all collect into the same messages.
+    #[derive(Default)]
+    struct TestFactory(Arc<CollectMessages>);
+
+    impl LogFactory for TestFactory {
+        fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            Ok(Box::new(self.0.clone()))
+        }
+    }
+
+    #[test]
+    fn log_panic() {
+        let logger = CollectMessages::default();
+        let location = Location::caller();
+        let payloads: [Box<dyn Any + Send>; 3] = [
+            Box::new("static"),
+            Box::new(format!("formatted {}", 42)),
+            Box::new(42),
+        ];
+        for payload in payloads {
+            crate::log_panic(&logger, &*payload, Some(location));
+        }
+        crate::log_panic(&logger, &"nowhere", None);
+        let prefix = format!("ERROR Thread 'tests::log_panic' panicked at {location}");
+        let line = location.line();
+        assert_eq!(
+            *logger.messages.lock().unwrap(),
+            [
+                format!("{prefix}: static at line {line}"),
+                format!("{prefix}: formatted 42 at line {line}"),
+                format!("{prefix}: Box<dyn Any> at line {line}"),
+                "ERROR Thread 'tests::log_panic' panicked: nowhere".to_string(),
+            ]
+        );
+        assert_eq!(logger.flushes.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn level_filter_from_log_level() {
+        let filters = [
+            (LogLevel::Emerg, log::LevelFilter::Error),
+            (LogLevel::Alert, log::LevelFilter::Error),
+            (LogLevel::Crit, log::LevelFilter::Error),
+            (LogLevel::Err, log::LevelFilter::Error),
+            (LogLevel::Warning, log::LevelFilter::Warn),
+            (LogLevel::Notice, log::LevelFilter::Info),
+            (LogLevel::Info, log::LevelFilter::Debug),
+            (LogLevel::Debug, log::LevelFilter::Trace),
+        ];
+        for (level, filter) in filters {
+            assert_eq!(crate::level_filter_from_log_level(level), filter);
+        }
+    }
+
+    #[test]
+    fn log_controller_is_healthy() {
+        let (control, log) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert!(control.is_healthy());
+        drop(log);
+        assert!(!control.is_healthy());
+    }
+
+    #[test]
+    fn log_controller_is_unhealthy_after_panic() {
+        let (control, _log) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        std::panic::catch_unwind(AssertUnwindSafe(|| {
+            control
+                .handle
+                .modify(|_| panic!("Poison the lock"))
+                .unwrap()
+        }))
+        .unwrap_err();
+        assert!(!control.is_healthy());
+    }
+
+    #[test]
+    fn log_controller_startup_message() {
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (mut control, _log) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Auto,
+            log::Level::Info,
+        )
+        .unwrap();
+        control.log_startup_message();
+        control.set_target("console").unwrap();
+        control.log_startup_message();
+        let messages = messages.messages.lock().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with(
+            "INFO logging to console at level notice (auto-detected), syslog identifier \"test\""
+        ));
+        assert!(messages[1].starts_with(
+            "INFO logging to console at level notice (configured), syslog identifier \"test\""
+        ));
+    }
+
+    #[test]
+    fn writer_log_factory_switch_targets() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let (mut control, log) = LogController::new(
+            WriterLogFactory::from_shared(output.clone()),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Auto,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.target(), "console");
+        log_at(&log, log::Level::Info, "first");
+        log_at(&log, log::Level::Debug, "filtered");
+        control.set_target("console").unwrap();
+        control.set_level(LogLevel::Info).unwrap();
+        log_at(&log, log::Level::Debug, "second");
+        assert!(matches!(
+            control.set_target("null"),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+        log_at(&log, log::Level::Warn, "third");
+        assert_eq!(
+            String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+            "INFO : first\nDEBUG : second\nWARN : third\n"
+        );
+    }
+
+    #[test]
+    fn writer_log_factory_priority_prefix() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let (_control, log) = LogController::new(
+            WriterLogFactory::from_shared(output.clone()).with_priority_prefix(true),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Trace,
+        )
+        .unwrap();
+        for level in log::Level::iter() {
+            log_at(&log, level, level.as_str());
+        }
+        log_at(&log, log::Level::Warn, "two\nlines");
+        assert_eq!(
+            String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+            "<3>ERROR : ERROR\n<4>WARN : WARN\n<5>INFO : INFO\n<6>DEBUG : DEBUG\n\
+             <7>TRACE : TRACE\n<4>WARN : two\n<4>lines\n"
+        );
+    }
+
+    #[test]
+    fn unsupported_target_policy() {
+        let policies = [
+            (UnsupportedTargetPolicy::Error, false, ""),
+            (UnsupportedTargetPolicy::Ignore, true, ""),
+            (
+                UnsupportedTargetPolicy::KeepCurrent,
+                true,
+                "WARN logcontrol_log: Log target kmsg not supported, keeping current log target\n",
+            ),
+        ];
+        for (policy, succeeds, expected_output) in policies {
+            let output = Arc::new(Mutex::new(Vec::new()));
+            let (control, _log) = LogController::new(
+                WriterLogFactory::from_shared(output.clone()),
+                false,
+                "test".to_string(),
+                KnownLogTarget::Console,
+                log::Level::Info,
+            )
+            .unwrap();
+            let mut control = control.with_unsupported_target_policy(policy);
+            let result = control.set_target("kmsg");
+            assert_eq!(result.is_ok(), succeeds, "{policy:?}: {result:?}");
+            if !succeeds {
+                assert!(matches!(
+                    result,
+                    Err(LogControl1Error::UnsupportedLogTarget(_))
+                ));
+            }
+            assert_eq!(control.target(), "console");
+            assert_eq!(
+                String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+                expected_output
+            );
+        }
+    }
+
+    /// A journal logger which records messages together with the syslog identifier.
+    struct IdentifiedLog {
+        syslog_identifier: String,
+        messages: Arc<CollectMessages>,
+    }
+
+    impl Log for IdentifiedLog {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.messages.messages.lock().unwrap().push(format!(
+                "{}: {}",
+                self.syslog_identifier,
+                record.args()
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Create console loggers with [`TestFactory`], and journal loggers with [`IdentifiedLog`].
+    #[derive(Default)]
+    struct IdentifiedJournalFactory(TestFactory);
+
+    impl LogFactory for IdentifiedJournalFactory {
+        fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            self.0.create_console_log()
+        }
+
+        fn create_journal_log(
+            &self,
+            syslog_identifier: String,
+        ) -> Result<Box<dyn Log>, LogControl1Error> {
+            Ok(Box::new(IdentifiedLog {
+                syslog_identifier,
+                messages: self.0 .0.clone(),
+            }))
+        }
+    }
+
+    #[test]
+    fn set_syslog_identifier() {
+        let factory = IdentifiedJournalFactory::default();
+        let messages = factory.0 .0.clone();
+        let (mut control, log) = LogController::new(
+            factory,
+            false,
+            "old".to_string(),
+            KnownLogTarget::Journal,
+            log::Level::Info,
+        )
+        .unwrap();
+        log_at(&log, log::Level::Info, "first");
+        control.set_syslog_identifier("new".to_string()).unwrap();
+        assert_eq!(control.syslog_identifier(), "new");
+        log_at(&log, log::Level::Info, "second");
+        control.set_target("console").unwrap();
+        control.set_syslog_identifier("newer".to_string()).unwrap();
+        log_at(&log, log::Level::Info, "third");
+        control.set_target("journal").unwrap();
+        log_at(&log, log::Level::Info, "fourth");
+        assert_eq!(
+            *messages.messages.lock().unwrap(),
+            ["old: first", "new: second", "INFO third", "newer: fourth"]
+        );
+    }
+
+    #[test]
+    fn buffering_level_filter_emits_buffer_on_trigger() {
+        let messages = Arc::new(CollectMessages::default());
+        let log = BufferingLevelFilter::new(
+            LevelFilter::new(log::Level::Warn, messages.clone()),
+            log::Level::Error,
+            2,
+        );
+        assert_eq!(log.trigger(), log::Level::Error);
+        assert_eq!(log.capacity(), 2);
+
+        log_at(&log, log::Level::Info, "dropped");
+        log_at(&log, log::Level::Debug, "first");
+        log_at(&log, log::Level::Warn, "warning");
+        log.log(
+            &Record::builder()
+                .level(log::Level::Trace)
+                .args(format_args!("second"))
+                .line(Some(42))
+                .build(),
+        );
+        log_at(&log, log::Level::Error, "error");
+        log_at(&log, log::Level::Error, "another error");
+        assert_eq!(
+            *messages.messages.lock().unwrap(),
+            [
+                "WARN warning",
+                "DEBUG first",
+                "TRACE second at line 42",
+                "ERROR error",
+                "ERROR another error"
+            ]
+        );
+    }
+
+    #[test]
+    fn buffering_level_filter_discards_buffer_without_trigger() {
+        let messages = Arc::new(CollectMessages::default());
+        let mut log = BufferingLevelFilter::new(
+            LevelFilter::new(log::Level::Warn, messages.clone()),
+            log::Level::Error,
+            10,
+        );
+        log_at(&log, log::Level::Info, "info");
+        log_at(&log, log::Level::Warn, "warning");
+        log.set_level(log::Level::Info);
+        assert_eq!(log.filter().level(), log::Level::Info);
+        log_at(&log, log::Level::Info, "second info");
+        assert_eq!(
+            *messages.messages.lock().unwrap(),
+            ["WARN warning", "INFO second info"]
+        );
+    }
+
+    #[test]
+    fn buffering_level_filter_keeps_key_values() {
+        let journal = Arc::new(CollectKeyValues::default());
+        let log = BufferingLevelFilter::new(
+            LevelFilter::new(log::Level::Warn, journal.clone()),
+            log::Level::Error,
+            1,
+        );
+        let record_key_values = [("FOO", "bar")];
+        log.log(
+            &Record::builder()
+                .level(log::Level::Debug)
+                .args(format_args!("Hello"))
+                .key_values(&record_key_values)
+                .build(),
+        );
+        log_at(&log, log::Level::Error, "error");
+        assert_eq!(
+            *journal.records.lock().unwrap(),
+            [key_values(&[("FOO", "bar")]), key_values(&[])]
+        );
+    }
+
+    #[cfg(feature = "fern")]
+    #[test]
+    fn fern_factory() {
+        use crate::{FernFactory, LogFactory};
+        use log::Record;
+        use std::sync::mpsc::channel;
+
+        let (sender, receiver) = channel();
+        let factory = FernFactory::new(move || {
+            fern::Dispatch::new()
+                // The factory overrides the default level of the dispatch
+                .level(log::LevelFilter::Off)
+                .format(|out, message, record| {
+                    out.finish(format_args!("[{}] {message}", record.level()))
+                })
+                .chain(sender.clone())
+        });
+        let log = factory.create_console_log().unwrap();
+        log.log(
+            &Record::builder()
+                .level(log::Level::Warn)
+                .args(format_args!("Hello fern"))
+                .build(),
+        );
+        assert_eq!(receiver.try_recv().unwrap(), "[WARN] Hello fern\n");
+    }
+
+    #[cfg(feature = "env_logger")]
+    #[test]
+    fn env_logger_log_control() {
+        use crate::{EnvLoggerFactory, EnvLoggerLogControl1};
+        use std::io::Write;
+
+        struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for SharedWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let factory = EnvLoggerFactory::new({
+            let output = output.clone();
+            move || {
+                let mut builder = env_logger::Builder::new();
+                builder
+                    // The factory overrides the default filter of the builder
+                    .parse_filters("error")
+                    .format(|buf, record| writeln!(buf, "{} {}", record.level(), record.args()))
+                    .target(env_logger::Target::Pipe(Box::new(SharedWriter(
+                        output.clone(),
+                    ))));
+                builder
+            }
+        });
+        let (mut control, logger) = EnvLoggerLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Auto,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.target(), "console");
+        log_at(&logger, log::Level::Info, "info");
+        log_at(&logger, log::Level::Debug, "hidden");
+        control.set_level(LogLevel::Debug).unwrap();
+        log_at(&logger, log::Level::Debug, "debug");
+        control.set_level(LogLevel::Warning).unwrap();
+        log_at(&logger, log::Level::Info, "hidden");
+        log_at(&logger, log::Level::Warn, "warn");
+        assert_eq!(
+            String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+            "INFO info\nDEBUG debug\nWARN warn\n"
+        );
+    }
+
+    #[test]
+    fn drop_flushes_logger() {
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(messages.flushes.load(Ordering::SeqCst), 0);
+        drop(control);
+        assert_eq!(messages.flushes.load(Ordering::SeqCst), 1);
+        drop(logger);
+        assert_eq!(messages.flushes.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn drop_after_logger() {
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        drop(logger);
+        drop(control);
+        assert_eq!(messages.flushes.load(Ordering::SeqCst), 0);
+    }
+
+    /// A datagram socket in the temporary directory, removed on drop.
+    struct TempSocket {
+        path: PathBuf,
+        socket: UnixDatagram,
+    }
+
+    impl TempSocket {
+        fn bind(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("logcontrol-log-{}-{name}.sock", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let socket = UnixDatagram::bind(&path).unwrap();
+            Self { path, socket }
+        }
+
+        fn recv(&self) -> String {
+            let mut buf = [0; 1024];
+            let size = self.socket.recv(&mut buf).unwrap();
+            String::from_utf8(buf[..size].to_vec()).unwrap()
+        }
+    }
+
+    impl Drop for TempSocket {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    #[test]
+    fn socket_target() {
+        let socket = TempSocket::bind("socket-target");
+        let (mut control, logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let target = format!("socket:{}", socket.path.display());
+        control.set_target(format!(" {target}\n")).unwrap();
+        assert_eq!(control.target(), target);
+        logger.log(
+            &Record::builder()
+                .level(log::Level::Warn)
+                .target("test")
+                .args(format_args!("Hello socket"))
+                .build(),
+        );
+        assert_eq!(socket.recv(), "WARN test: Hello socket");
+    }
+
+    #[test]
+    fn socket_target_failures() {
+        let (mut control, _logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let result = control.set_target("socket:/nonexistent/logcontrol.sock");
+        assert!(
+            matches!(result, Err(LogControl1Error::InputOutputError(_))),
+            "{result:?}"
+        );
+        let result = control.set_target("socket:");
+        assert!(
+            matches!(result, Err(LogControl1Error::UnsupportedLogTarget(_))),
+            "{result:?}"
+        );
+        assert_eq!(control.target(), "console");
+    }
+
+    /// Create console loggers for tests, and clones of a channel logger.
+    struct ChannelFactory(ChannelLog);
+
+    impl LogFactory for ChannelFactory {
+        fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            Ok(Box::new(CollectMessages::default()))
+        }
+
+        fn create_channel_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            Ok(Box::new(self.0.clone()))
+        }
+    }
+
+    #[test]
+    fn channel_target() {
+        let (log, receiver) = ChannelLog::bounded(2);
+        let (mut control, logger) = LogController::new(
+            ChannelFactory(log.clone()),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        control.set_target(" channel\n").unwrap();
+        assert_eq!(control.target(), "channel");
+        for message in ["first", "second", "third"] {
+            logger.log(
+                &Record::builder()
+                    .level(log::Level::Warn)
+                    .target("test")
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            ["WARN test: first", "WARN test: second"]
+        );
+        assert_eq!(log.dropped(), 1);
+
+        // Dropping the receiver drops all further records
+        drop(receiver);
+        logger.log(&Record::builder().args(format_args!("gone")).build());
+        assert_eq!(log.dropped(), 2);
+    }
+
+    #[test]
+    fn channel_target_unsupported_by_default() {
+        let (mut control, _logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let result = control.set_target("channel");
+        assert!(
+            matches!(result, Err(LogControl1Error::UnsupportedLogTarget(ref target)) if target == "channel"),
+            "{result:?}"
+        );
+        assert_eq!(control.target(), "console");
+    }
+
+    #[test]
+    fn current_priority() {
+        let (mut control, _logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.current_priority(), 5);
+        control.set_level(LogLevel::Debug).unwrap();
+        assert_eq!(control.current_priority(), 7);
+        control.set_level(LogLevel::Warning).unwrap();
+        assert_eq!(control.current_priority(), 4);
+    }
+
+    #[test]
+    fn reload_failure() {
+        let (mut control, logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        drop(logger);
+        let error = control.set_level(LogLevel::Debug).unwrap_err();
+        assert!(
+            matches!(error, LogControl1Error::ReloadFailed { .. }),
+            "{error:?}"
+        );
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(
+            matches!(
+                source.downcast_ref::<log_reload::ReloadError>(),
+                Some(log_reload::ReloadError::Gone)
+            ),
+            "{source:?}"
+        );
+        assert_eq!(control.level(), LogLevel::Notice);
+    }
+
+    #[test]
+    fn backend_name() {
+        let (control, _logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.backend_name(), "log");
+    }
+
+    #[test]
+    fn capabilities() {
+        let (control, _logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.capabilities().names(), ["mute", "reopen", "health"]);
+    }
+
+    #[test]
+    fn target_default_levels() {
+        let socket = TempSocket::bind("target-default-levels");
+        let socket_target = format!("socket:{}", socket.path.display());
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let mut control =
+            control.with_target_default_level(KnownLogTarget::Console, LogLevel::Debug);
+        // The default does not apply to the initial target
+        assert_eq!(control.level(), LogLevel::Notice);
+
+        control.set_level(LogLevel::Warning).unwrap();
+        control.set_target(&socket_target).unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+        control.set_target("console").unwrap();
+        assert_eq!(control.level(), LogLevel::Debug);
+        log_at(&logger, log::Level::Trace, "trace");
+        assert_eq!(*messages.messages.lock().unwrap(), ["TRACE trace"]);
+
+        control.set_level(LogLevel::Err).unwrap();
+        control.set_target("auto").unwrap();
+        assert_eq!(control.level(), LogLevel::Debug);
+
+        // An explicit level wins over the default level
+        control.reconfigure(LogLevel::Warning, "console").unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+    }
+
+    #[test]
+    fn high_target_default_level() {
+        let socket = TempSocket::bind("high-target-default-level");
+        let socket_target = format!("socket:{}", socket.path.display());
+        let (control, _logger) = LogController::new(
+            TestFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let mut control =
+            control.with_target_default_level(KnownLogTarget::Console, LogLevel::Emerg);
+        control.set_target(&socket_target).unwrap();
+        control.set_target("console").unwrap();
+        assert_eq!(control.target(), "console");
+        assert_eq!(control.level(), LogLevel::Emerg);
+    }
+
+    #[test]
+    fn all_levels() {
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (mut control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        for priority in 0..=7u8 {
+            let level = LogLevel::try_from(priority).unwrap();
+            control.set_level(level).unwrap();
+            assert_eq!(control.level(), level);
+        }
+        for level in [LogLevel::Emerg, LogLevel::Alert, LogLevel::Crit] {
+            control.set_level(level).unwrap();
+            assert_eq!(control.level(), level);
+            log_at(&logger, log::Level::Warn, "warn");
+            log_at(&logger, log::Level::Error, "error");
+        }
+        assert_eq!(
+            *messages.messages.lock().unwrap(),
+            ["ERROR error", "ERROR error", "ERROR error"]
+        );
+    }
+
+    #[test]
+    fn mute() {
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (mut control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let error = log::Metadata::builder().level(log::Level::Error).build();
+        log_at(&logger, log::Level::Info, "before");
+        control.set_muted(true).unwrap();
+        assert!(control.is_muted());
+        assert!(!logger.enabled(&error));
+        log_at(&logger, log::Level::Error, "muted");
+
+        // Changes while muted apply when unmuted
+        control.reconfigure(LogLevel::Warning, "auto").unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+        assert_eq!(control.target(), "console");
+        log_at(&logger, log::Level::Error, "still muted");
+        assert_eq!(*messages.messages.lock().unwrap(), ["INFO before"]);
+
+        control.set_muted(false).unwrap();
+        assert!(!control.is_muted());
+        assert!(logger.enabled(&error));
+        log_at(&logger, log::Level::Warn, "after");
+        log_at(&logger, log::Level::Info, "info");
+        assert_eq!(
+            *messages.messages.lock().unwrap(),
+            ["INFO before", "WARN after"]
+        );
+    }
+
+    /// Create console loggers with [`TestFactory`], and fail to create journal loggers.
+    #[derive(Default)]
+    struct NoJournalFactory(TestFactory);
+
+    impl LogFactory for NoJournalFactory {
+        fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            self.0.create_console_log()
+        }
+
+        fn create_journal_log(
+            &self,
+            _syslog_identifier: String,
+        ) -> Result<Box<dyn Log>, LogControl1Error> {
+            Err(LogControl1Error::Failure("journal not running".to_string()))
+        }
+    }
+
+    #[test]
+    fn target_fallback_chain() {
+        let chain = vec![
+            KnownLogTarget::Journal,
+            KnownLogTarget::Syslog,
+            KnownLogTarget::Console,
+        ];
+        let factory = NoJournalFactory::default();
+        let messages = factory.0 .0.clone();
+        // The journal fails, and syslog is not supported, so start on the console
+        let (control, logger) = LogController::new_with_target_fallback_chain(
+            factory,
+            false,
+            "test".to_string(),
+            chain,
+            log::Level::Info,
+        )
+        .unwrap();
+        let mut control =
+            control.with_target_default_level(KnownLogTarget::Console, LogLevel::Info);
+        assert_eq!(control.target(), "console");
+
+        control.set_target("journal").unwrap();
+        assert_eq!(control.target(), "console");
+        assert_eq!(control.level(), LogLevel::Info);
+        log_at(&logger, log::Level::Debug, "fallback");
+        assert_eq!(*messages.messages.lock().unwrap(), ["DEBUG fallback"]);
+
+        // Without a working target in the chain fail with the error of the requested target
+        let mut control = control.with_target_fallback_chain(vec![KnownLogTarget::Syslog]);
+        assert!(matches!(
+            control.set_target("journal"),
+            Err(LogControl1Error::Failure(_))
+        ));
+        assert!(matches!(
+            LogController::new_with_target_fallback_chain(
+                TestFactory::default(),
+                false,
+                "test".to_string(),
+                vec![KnownLogTarget::Syslog, KnownLogTarget::Kmsg],
+                log::Level::Info,
+            ),
+            Err(LogControl1Error::UnsupportedLogTarget(target)) if target == "syslog"
+        ));
+    }
+
+    /// Count console loggers created by [`TestFactory`].
+    #[derive(Default)]
+    struct CountingFactory {
+        factory: TestFactory,
+        created: Arc<AtomicUsize>,
+    }
+
+    impl LogFactory for CountingFactory {
+        fn create_console_log(&self) -> Result<Box<dyn Log>, LogControl1Error> {
+            self.created.fetch_add(1, Ordering::SeqCst);
+            self.factory.create_console_log()
+        }
+    }
+
+    #[test]
+    fn set_factory() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let (mut control, logger) = LogController::new(
+            WriterLogFactory::from_shared(output.clone()),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        log_at(&logger, log::Level::Warn, "old");
+        control
+            .set_factory(WriterLogFactory::from_shared(output.clone()).with_priority_prefix(true))
+            .unwrap();
+        log_at(&logger, log::Level::Debug, "hidden");
+        log_at(&logger, log::Level::Warn, "new");
+        assert_eq!(control.target(), "console");
+
+        // While muted only the factory changes
+        control.set_muted(true).unwrap();
+        control
+            .set_factory(WriterLogFactory::from_shared(output.clone()))
+            .unwrap();
+        log_at(&logger, log::Level::Warn, "muted");
+        control.set_muted(false).unwrap();
+        log_at(&logger, log::Level::Warn, "unmuted");
+        assert_eq!(
+            String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+            "WARN : old\n<4>WARN : new\nWARN : unmuted\n"
+        );
+    }
+
+    #[test]
+    fn reopen() {
+        let factory = CountingFactory::default();
+        let created = factory.created.clone();
+        let messages = factory.factory.0.clone();
+        let (mut control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        control.reopen().unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        assert_eq!(control.target(), "console");
+        log_at(&logger, log::Level::Info, "reopened");
+        assert_eq!(*messages.messages.lock().unwrap(), ["INFO reopened"]);
+
+        // Unmuting creates a new logger anyway, so reopening while muted does nothing
+        control.set_muted(true).unwrap();
+        control.reopen().unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        control.set_muted(false).unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn scoped_level() {
+        let factory = TestFactory::default();
+        let messages = factory.0.clone();
+        let (mut control, logger) = LogController::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        {
+            let guard = control.scoped_level(LogLevel::Debug).unwrap();
+            assert_eq!(guard.level(), LogLevel::Debug);
+            log_at(&logger, log::Level::Debug, "scoped");
+        }
+        assert_eq!(control.level(), LogLevel::Notice);
+        log_at(&logger, log::Level::Debug, "hidden");
+        assert_eq!(*messages.messages.lock().unwrap(), ["DEBUG scoped"]);
+    }
+
+    #[test]
+    fn datagram_socket_log_send_fails_without_receiver() {
+        let socket = TempSocket::bind("send-fails");
+        let log = DatagramSocketLog::connect(&socket.path).unwrap();
+        let record = Record::builder().args(format_args!("gone")).build();
+        log.send(&record).unwrap();
+        assert_eq!(socket.recv(), "INFO : gone");
+        drop(socket);
+        assert!(log.send(&record).is_err());
+        // Logging ignores the error
+        log.log(&record);
     }
 }
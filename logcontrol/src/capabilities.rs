@@ -0,0 +1,55 @@
+//! Optional capabilities of a log control.
+
+use alloc::vec::Vec;
+
+/// The optional capabilities of a log control, as returned by [`crate::LogControl1::capabilities`].
+///
+/// Each field denotes whether a control implements the corresponding optional
+/// method of [`crate::LogControl1`] in a meaningful way, instead of relying on
+/// its default implementation, e.g. so that tooling can hide a mute switch for
+/// controls which cannot mute.
+///
+/// The [`Default`] has no capabilities.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LogControlCapabilities {
+    /// Whether the control supports muting with [`crate::LogControl1::set_muted`].
+    pub mute: bool,
+    /// Whether [`crate::LogControl1::reopen`] actually reopens the current target.
+    pub reopen: bool,
+    /// Whether [`crate::LogControl1::is_healthy`] checks the health of logging.
+    pub health: bool,
+}
+
+impl LogControlCapabilities {
+    /// Get the names of all supported capabilities.
+    ///
+    /// Return `mute`, `reopen`, and `health`, in this order, for all supported
+    /// capabilities, e.g. for the `Capabilities` property of DBus frontends.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            ("mute", self.mute),
+            ("reopen", self.reopen),
+            ("health", self.health),
+        ]
+        .into_iter()
+        .filter_map(|(name, supported)| supported.then_some(name))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogControlCapabilities;
+
+    #[test]
+    fn names() {
+        assert!(LogControlCapabilities::default().names().is_empty());
+        let capabilities = LogControlCapabilities {
+            mute: true,
+            reopen: false,
+            health: true,
+        };
+        assert_eq!(capabilities.names(), ["mute", "health"]);
+    }
+}
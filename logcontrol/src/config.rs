@@ -0,0 +1,157 @@
+//! Load the logging configuration of a service with serde.
+
+use alloc::string::String;
+
+use serde::de::{Error, Unexpected};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+
+/// Deserialize the level from its name, e.g. `"info"`.
+impl<'de> Deserialize<'de> for LogLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        LogLevel::try_from(value.as_str())
+            .map_err(|_| D::Error::invalid_value(Unexpected::Str(&value), &"a log level"))
+    }
+}
+
+/// Serialize the target as its name, e.g. `"journal"`.
+impl Serialize for KnownLogTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Deserialize the target from its name, e.g. `"journal"`.
+impl<'de> Deserialize<'de> for KnownLogTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        KnownLogTarget::try_from(value.as_str())
+            .map_err(|_| D::Error::invalid_value(Unexpected::Str(&value), &"a known log target"))
+    }
+}
+
+/// The logging configuration of a service, e.g. from a `[logging]` section of its configuration file.
+///
+/// Deserialize the configuration, e.g. from TOML:
+///
+/// ```toml
+/// [logging]
+/// level = "info"
+/// target = "journal"
+/// syslog_identifier = "my-service"
+/// ```
+///
+/// Then create a control with the `syslog_identifier` of the configuration, and
+/// apply level and target to the control with [`LoggingConfig::apply`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// The log level.
+    pub level: LogLevel,
+    /// The log target.
+    pub target: KnownLogTarget,
+    /// The syslog identifier, if any.
+    ///
+    /// [`LogControl1`] cannot change the syslog identifier, so pass the
+    /// identifier to the constructor of the control, and use
+    /// [`crate::syslog_identifier`] if the configuration has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub syslog_identifier: Option<String>,
+}
+
+impl LoggingConfig {
+    /// Apply level and target of this configuration to `control`.
+    ///
+    /// Change both with [`LogControl1::reconfigure`], see there for errors.
+    /// Ignore the syslog identifier, see [`LoggingConfig::syslog_identifier`].
+    pub fn apply<C: LogControl1>(&self, control: &mut C) -> Result<(), LogControl1Error> {
+        control.reconfigure(self.level, self.target.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::config::LoggingConfig;
+    use crate::test_util::TestLogControl1;
+    use crate::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+
+    #[derive(Debug, Deserialize)]
+    struct ServiceConfig {
+        logging: LoggingConfig,
+    }
+
+    #[test]
+    fn deserialize_and_apply() {
+        let config: ServiceConfig = toml::from_str(
+            r#"
+[logging]
+level = "debug"
+target = "console"
+syslog_identifier = "my-service"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.logging,
+            LoggingConfig {
+                level: LogLevel::Debug,
+                target: KnownLogTarget::Console,
+                syslog_identifier: Some("my-service".to_string()),
+            }
+        );
+
+        let mut control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+        config.logging.apply(&mut control).unwrap();
+        assert_eq!(control.level(), LogLevel::Debug);
+        assert_eq!(control.target(), "console");
+    }
+
+    #[test]
+    fn apply_unsupported_target() {
+        let config: LoggingConfig =
+            toml::from_str("level = \"warning\"\ntarget = \"kmsg\"").unwrap();
+        assert_eq!(config.syslog_identifier, None);
+        let mut control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+        assert!(matches!(
+            config.apply(&mut control),
+            Err(LogControl1Error::UnsupportedLogTarget(target)) if target == "kmsg"
+        ));
+        assert_eq!(control.target(), "journal");
+    }
+
+    #[test]
+    fn reject_invalid_values() {
+        let error = toml::from_str::<LoggingConfig>("level = \"verbose\"\ntarget = \"journal\"")
+            .unwrap_err();
+        assert!(
+            error
+                .message()
+                .contains("invalid value: string \"verbose\", expected a log level"),
+            "{error}"
+        );
+        let error =
+            toml::from_str::<LoggingConfig>("level = \"info\"\ntarget = \"file\"").unwrap_err();
+        assert!(
+            error
+                .message()
+                .contains("invalid value: string \"file\", expected a known log target"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let config = LoggingConfig {
+            level: LogLevel::Notice,
+            target: KnownLogTarget::Auto,
+            syslog_identifier: None,
+        };
+        assert_eq!(
+            toml::to_string(&config).unwrap(),
+            "level = \"notice\"\ntarget = \"auto\"\n"
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Coalesce rapid level changes.
+
+use std::time::{Duration, Instant};
+
+use crate::{LogControl1, LogControl1Error, LogControlCapabilities, LogLevel};
+
+/// A [`LogControl1`] which coalesces rapid level changes.
+///
+/// The first call to [`LogControl1::set_level`] opens a window of the given
+/// duration; the underlying control keeps its level until the window ends, and
+/// then changes to the level requested last.  Scripts which toggle the level
+/// in a loop thus cause a single change of the underlying control per window,
+/// instead of a reload and audit event for every toggle.
+///
+/// This control has no timer of its own; the host loop calls
+/// [`Debounced::tick`] regularly, or sleeps until [`Debounced::deadline`], to
+/// apply pending levels.
+///
+/// [`LogControl1::level`] returns the pending level if any, so that clients
+/// read back the level they set.
+///
+/// Errors are deferred as well: [`LogControl1::set_level`] returns `Ok` for a
+/// level which the underlying control rejects later, at the end of the window.
+/// [`Debounced::tick`] returns the error of the underlying control then, and
+/// [`Debounced::last_error`] keeps it for clients of this control, e.g. to
+/// surface it over DBus.  Use a zero window to get errors right away.
+///
+/// [`LogControl1::set_target`] changes the
+/// target right away, and [`LogControl1::reconfigure`] changes both level and
+/// target right away, discarding any pending level.
+///
+/// ```
+/// # use logcontrol::*;
+/// use std::time::{Duration, Instant};
+///
+/// # struct Settings { level: LogLevel, target: KnownLogTarget, name: &'static str }
+/// # logcontrol::impl_log_control1!(Settings { level: level, target: target, syslog_identifier: name });
+/// # let control = Settings { level: LogLevel::Info, target: KnownLogTarget::Journal, name: "example" };
+/// let mut control = Debounced::new(control, Duration::from_millis(500));
+/// let start = Instant::now();
+/// control.set_level_at(LogLevel::Debug, start)?;
+/// control.set_level_at(LogLevel::Warning, start + Duration::from_millis(100))?;
+/// assert_eq!(control.deadline(), Some(start + Duration::from_millis(500)));
+/// control.tick(start + Duration::from_millis(500))?;
+/// assert_eq!(control.into_inner().level(), LogLevel::Warning);
+/// # Ok::<(), LogControl1Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Debounced<C> {
+    control: C,
+    window: Duration,
+    /// The level requested last, and the end of the current window.
+    pending: Option<(LogLevel, Instant)>,
+    /// The error of the last failed attempt to apply a pending level.
+    last_error: Option<String>,
+}
+
+impl<C: LogControl1> Debounced<C> {
+    /// Coalesce level changes of `control` within `window`.
+    ///
+    /// With a zero `window` every level change applies right away.
+    pub fn new(control: C, window: Duration) -> Self {
+        Self {
+            control,
+            window,
+            pending: None,
+            last_error: None,
+        }
+    }
+
+    /// Request `level` at the given time `now`.
+    ///
+    /// Open a new window at `now` if no change is pending, and apply the level
+    /// right away if the window is zero.  [`LogControl1::set_level`] calls
+    /// this method with the current time.
+    pub fn set_level_at(&mut self, level: LogLevel, now: Instant) -> Result<(), LogControl1Error> {
+        let deadline = self
+            .pending
+            .map_or(now + self.window, |(_, deadline)| deadline);
+        self.pending = Some((level, deadline));
+        self.tick(now)
+    }
+
+    /// Get the end of the current window, if a level change is pending.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.map(|(_, deadline)| deadline)
+    }
+
+    /// Apply the pending level if its window ended at `now`.
+    ///
+    /// Skip the change if the underlying control already has the pending
+    /// level, e.g. if a client toggled the level back and forth.  Discard the
+    /// pending level if changing the level fails, remember the error for
+    /// [`Self::last_error`], and return it.
+    pub fn tick(&mut self, now: Instant) -> Result<(), LogControl1Error> {
+        match self.pending {
+            Some((level, deadline)) if deadline <= now => {
+                self.pending = None;
+                let result = if self.control.level() == level {
+                    Ok(())
+                } else {
+                    self.control.set_level(level)
+                };
+                self.last_error = result
+                    .as_ref()
+                    .err()
+                    .map(|error| format!("Failed to apply pending level {level}: {error}"));
+                result
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Describe the error of the last pending level which failed to apply.
+    ///
+    /// Return `None` if the last pending level applied successfully, or if
+    /// no pending level was applied yet.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Get the underlying control, discarding any pending level.
+    pub fn into_inner(self) -> C {
+        self.control
+    }
+}
+
+impl<C: LogControl1> LogControl1 for Debounced<C> {
+    fn level(&self) -> LogLevel {
+        self.pending
+            .map_or_else(|| self.control.level(), |(level, _)| level)
+    }
+
+    /// Request `level`, see [`Debounced::set_level_at`].
+    ///
+    /// Return `Ok` while the window is open, even if the underlying control
+    /// rejects `level` later; see [`Debounced::last_error`].
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.set_level_at(level, Instant::now())
+    }
+
+    fn target(&self) -> &str {
+        self.control.target()
+    }
+
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        self.control.set_target(target)
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        self.control.syslog_identifier()
+    }
+
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        self.pending = None;
+        self.control.reconfigure(level, target)
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.control.is_healthy()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.control.backend_name()
+    }
+
+    fn is_muted(&self) -> bool {
+        self.control.is_muted()
+    }
+
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        self.control.set_muted(muted)
+    }
+
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        self.control.reopen()
+    }
+
+    fn capabilities(&self) -> LogControlCapabilities {
+        self.control.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use crate::test_util::TestLogControl1;
+    use crate::{
+        KnownLogTarget, LogControl1, LogControl1Error, LogControl1Operation, LogLevel, Logged,
+    };
+
+    use super::Debounced;
+
+    const WINDOW: Duration = Duration::from_millis(100);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Record all level changes which reach the underlying control.
+    fn record_level_changes(
+        changes: &Mutex<Vec<LogLevel>>,
+    ) -> Logged<TestLogControl1, impl Fn(&LogControl1Operation<'_>) + '_> {
+        Logged::new(
+            TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal),
+            move |operation| {
+                if let LogControl1Operation::SetLevel { level, .. } = operation {
+                    changes.lock().unwrap().push(*level);
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn coalesce_changes_within_window() {
+        let changes = Mutex::new(Vec::new());
+        let mut control = Debounced::new(record_level_changes(&changes), WINDOW);
+        let start = Instant::now();
+        control.set_level_at(LogLevel::Debug, start).unwrap();
+        control.set_level_at(LogLevel::Err, start + ms(30)).unwrap();
+        control
+            .set_level_at(LogLevel::Warning, start + ms(60))
+            .unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+        assert_eq!(control.deadline(), Some(start + WINDOW));
+
+        control.tick(start + ms(99)).unwrap();
+        assert!(changes.lock().unwrap().is_empty());
+        control.tick(start + WINDOW).unwrap();
+        assert_eq!(*changes.lock().unwrap(), [LogLevel::Warning]);
+        assert_eq!(control.deadline(), None);
+        control.tick(start + ms(200)).unwrap();
+        assert_eq!(*changes.lock().unwrap(), [LogLevel::Warning]);
+
+        // A change after the window opens a new window
+        control
+            .set_level_at(LogLevel::Debug, start + ms(250))
+            .unwrap();
+        assert_eq!(control.deadline(), Some(start + ms(350)));
+        control.tick(start + ms(350)).unwrap();
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [LogLevel::Warning, LogLevel::Debug]
+        );
+    }
+
+    #[test]
+    fn skip_toggles_back_to_current_level() {
+        let changes = Mutex::new(Vec::new());
+        let mut control = Debounced::new(record_level_changes(&changes), WINDOW);
+        let start = Instant::now();
+        control.set_level_at(LogLevel::Debug, start).unwrap();
+        control
+            .set_level_at(LogLevel::Info, start + ms(10))
+            .unwrap();
+        control.tick(start + WINDOW).unwrap();
+        assert!(changes.lock().unwrap().is_empty());
+        assert_eq!(control.level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn zero_window_applies_right_away() {
+        let changes = Mutex::new(Vec::new());
+        let mut control = Debounced::new(record_level_changes(&changes), Duration::ZERO);
+        control.set_level(LogLevel::Debug).unwrap();
+        assert_eq!(*changes.lock().unwrap(), [LogLevel::Debug]);
+        assert_eq!(control.deadline(), None);
+    }
+
+    #[test]
+    fn reconfigure_discards_pending_level() {
+        let mut control = Debounced::new(
+            TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal),
+            WINDOW,
+        );
+        let start = Instant::now();
+        control.set_level_at(LogLevel::Debug, start).unwrap();
+        control.set_target("console").unwrap();
+        assert_eq!(control.target(), "console");
+        control.reconfigure(LogLevel::Err, "null").unwrap();
+        assert_eq!(control.deadline(), None);
+        control.tick(start + WINDOW).unwrap();
+        let control = control.into_inner();
+        assert_eq!(control.level(), LogLevel::Err);
+        assert_eq!(control.target(), "null");
+    }
+
+    /// Reject the emerg level.
+    struct RejectEmerg(LogLevel);
+
+    impl LogControl1 for RejectEmerg {
+        fn level(&self) -> LogLevel {
+            self.0
+        }
+
+        fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+            if level == LogLevel::Emerg {
+                Err(LogControl1Error::UnsupportedLogLevel(level))
+            } else {
+                self.0 = level;
+                Ok(())
+            }
+        }
+
+        fn target(&self) -> &str {
+            "console"
+        }
+
+        fn set_target<S: AsRef<str>>(&mut self, _target: S) -> Result<(), LogControl1Error> {
+            Ok(())
+        }
+
+        fn syslog_identifier(&self) -> &str {
+            "test"
+        }
+    }
+
+    #[test]
+    fn defer_errors_until_window_ends() {
+        let mut control = Debounced::new(RejectEmerg(LogLevel::Info), WINDOW);
+        let start = Instant::now();
+        control.set_level_at(LogLevel::Emerg, start).unwrap();
+        assert_eq!(control.last_error(), None);
+        assert!(matches!(
+            control.tick(start + WINDOW),
+            Err(LogControl1Error::UnsupportedLogLevel(LogLevel::Emerg))
+        ));
+        assert_eq!(control.level(), LogLevel::Info);
+        assert_eq!(
+            control.last_error(),
+            Some("Failed to apply pending level emerg: The log level emerg is not supported")
+        );
+
+        // The next successful change clears the error
+        control
+            .set_level_at(LogLevel::Debug, start + ms(200))
+            .unwrap();
+        control.tick(start + ms(300)).unwrap();
+        assert_eq!(control.last_error(), None);
+    }
+}
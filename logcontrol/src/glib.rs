@@ -0,0 +1,112 @@
+//! Map log levels to the log levels of GLib.
+//!
+//! Services which embed GLib-based libraries can use these functions to
+//! follow level changes of their [`crate::LogControl1`] in their GLib log
+//! handler.  The functions return the raw bits of `GLogLevelFlags`, to avoid
+//! a dependency on GLib; with the `glib` crate convert them with
+//! `glib::LogLevelFlags::from_bits_truncate`.
+//!
+//! The mapping mirrors the priorities which GLib itself uses when it writes to
+//! the journal: `G_LOG_LEVEL_CRITICAL` is [`LogLevel::Crit`], and
+//! `G_LOG_LEVEL_ERROR` is [`LogLevel::Err`].
+
+use crate::LogLevel;
+
+/// The raw value of `G_LOG_LEVEL_ERROR`.
+pub const G_LOG_LEVEL_ERROR: u32 = 1 << 2;
+/// The raw value of `G_LOG_LEVEL_CRITICAL`.
+pub const G_LOG_LEVEL_CRITICAL: u32 = 1 << 3;
+/// The raw value of `G_LOG_LEVEL_WARNING`.
+pub const G_LOG_LEVEL_WARNING: u32 = 1 << 4;
+/// The raw value of `G_LOG_LEVEL_MESSAGE`.
+pub const G_LOG_LEVEL_MESSAGE: u32 = 1 << 5;
+/// The raw value of `G_LOG_LEVEL_INFO`.
+pub const G_LOG_LEVEL_INFO: u32 = 1 << 6;
+/// The raw value of `G_LOG_LEVEL_DEBUG`.
+pub const G_LOG_LEVEL_DEBUG: u32 = 1 << 7;
+
+/// Get the GLib log level flag which corresponds to `level`.
+///
+/// GLib has no levels more severe than critical, so [`LogLevel::Emerg`] and
+/// [`LogLevel::Alert`] map to `G_LOG_LEVEL_CRITICAL` as well.
+pub fn log_level_flag(level: LogLevel) -> u32 {
+    match level {
+        LogLevel::Emerg | LogLevel::Alert | LogLevel::Crit => G_LOG_LEVEL_CRITICAL,
+        LogLevel::Err => G_LOG_LEVEL_ERROR,
+        LogLevel::Warning => G_LOG_LEVEL_WARNING,
+        LogLevel::Notice => G_LOG_LEVEL_MESSAGE,
+        LogLevel::Info => G_LOG_LEVEL_INFO,
+        LogLevel::Debug => G_LOG_LEVEL_DEBUG,
+    }
+}
+
+/// Get the GLib log level flags which pass at `level`.
+///
+/// Return the flags of `level` and all more severe levels, e.g. for
+/// [`LogLevel::Notice`] the flags for messages, warnings, errors and critical
+/// messages.  A GLib log handler can drop all messages whose level is not in
+/// this mask, to filter GLib messages at the level of the log control.
+///
+/// ```
+/// use logcontrol::glib::*;
+/// use logcontrol::LogLevel;
+///
+/// assert_eq!(
+///     enabled_log_level_flags(LogLevel::Warning),
+///     G_LOG_LEVEL_CRITICAL | G_LOG_LEVEL_ERROR | G_LOG_LEVEL_WARNING
+/// );
+/// ```
+pub fn enabled_log_level_flags(level: LogLevel) -> u32 {
+    (0..=level.as_priority())
+        .filter_map(|priority| LogLevel::try_from(priority).ok())
+        .fold(0, |flags, level| flags | log_level_flag(level))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::glib::{
+        enabled_log_level_flags, log_level_flag, G_LOG_LEVEL_CRITICAL, G_LOG_LEVEL_DEBUG,
+        G_LOG_LEVEL_ERROR, G_LOG_LEVEL_INFO, G_LOG_LEVEL_MESSAGE, G_LOG_LEVEL_WARNING,
+    };
+    use crate::LogLevel;
+
+    #[test]
+    fn log_level_flag_of_each_level() {
+        let flags = [
+            (LogLevel::Emerg, G_LOG_LEVEL_CRITICAL),
+            (LogLevel::Alert, G_LOG_LEVEL_CRITICAL),
+            (LogLevel::Crit, G_LOG_LEVEL_CRITICAL),
+            (LogLevel::Err, G_LOG_LEVEL_ERROR),
+            (LogLevel::Warning, G_LOG_LEVEL_WARNING),
+            (LogLevel::Notice, G_LOG_LEVEL_MESSAGE),
+            (LogLevel::Info, G_LOG_LEVEL_INFO),
+            (LogLevel::Debug, G_LOG_LEVEL_DEBUG),
+        ];
+        for (level, flag) in flags {
+            assert_eq!(log_level_flag(level), flag, "{level}");
+        }
+    }
+
+    #[test]
+    fn enabled_log_level_flags_of_each_level() {
+        let critical = G_LOG_LEVEL_CRITICAL;
+        let error = critical | G_LOG_LEVEL_ERROR;
+        let warning = error | G_LOG_LEVEL_WARNING;
+        let message = warning | G_LOG_LEVEL_MESSAGE;
+        let info = message | G_LOG_LEVEL_INFO;
+        let debug = info | G_LOG_LEVEL_DEBUG;
+        let flags = [
+            (LogLevel::Emerg, critical),
+            (LogLevel::Alert, critical),
+            (LogLevel::Crit, critical),
+            (LogLevel::Err, error),
+            (LogLevel::Warning, warning),
+            (LogLevel::Notice, message),
+            (LogLevel::Info, info),
+            (LogLevel::Debug, debug),
+        ];
+        for (level, flags) in flags {
+            assert_eq!(enabled_log_level_flags(level), flags, "{level}");
+        }
+    }
+}
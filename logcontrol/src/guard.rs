@@ -0,0 +1,183 @@
+//! Temporarily change the level.
+
+use core::fmt::{Debug, Formatter};
+use core::ops::{Deref, DerefMut};
+
+use crate::{LogControl1, LogControl1Error, LogLevel};
+
+/// Restore the previous level of a [`LogControl1`] on drop.
+///
+/// [`LevelGuard::new`] changes the level of a control, e.g. to enable debug
+/// logging for a single operation, and remembers the level it replaced; when
+/// the guard goes out of scope it sets this level again.  The guard
+/// dereferences to the control, to use the control while the guard exists.
+///
+/// Restoring the level on drop is best-effort: drop cannot fail, so the guard
+/// passes errors to the handler given to [`LevelGuard::with_restore_error_handler`],
+/// and ignores them by default.
+///
+/// ```
+/// # use logcontrol::*;
+/// # struct Settings { level: LogLevel, target: KnownLogTarget, name: &'static str }
+/// # logcontrol::impl_log_control1!(Settings { level: level, target: target, syslog_identifier: name });
+/// let mut control = Settings { level: LogLevel::Info, target: KnownLogTarget::Journal, name: "example" };
+/// {
+///     let guard = LevelGuard::new(&mut control, LogLevel::Debug)?;
+///     assert_eq!(guard.level(), LogLevel::Debug);
+/// }
+/// assert_eq!(control.level(), LogLevel::Info);
+/// # Ok::<(), LogControl1Error>(())
+/// ```
+pub struct LevelGuard<'a, C: LogControl1> {
+    control: &'a mut C,
+    previous: LogLevel,
+    on_restore_error: fn(&LogControl1Error),
+}
+
+impl<'a, C: LogControl1> LevelGuard<'a, C> {
+    /// Change the level of `control` to `level` until the returned guard drops.
+    ///
+    /// Return an error and leave `control` untouched if `control` does not
+    /// accept `level`.
+    pub fn new(control: &'a mut C, level: LogLevel) -> Result<Self, LogControl1Error> {
+        Self::with_restore_error_handler(control, level, |_| {})
+    }
+
+    /// Like [`Self::new`], but pass errors from restoring the level to `on_restore_error`.
+    ///
+    /// Backends use this to log failures to restore the level.
+    pub fn with_restore_error_handler(
+        control: &'a mut C,
+        level: LogLevel,
+        on_restore_error: fn(&LogControl1Error),
+    ) -> Result<Self, LogControl1Error> {
+        let previous = control.level();
+        control.set_level(level)?;
+        Ok(Self {
+            control,
+            previous,
+            on_restore_error,
+        })
+    }
+
+    /// The level to restore when this guard drops.
+    pub fn previous_level(&self) -> LogLevel {
+        self.previous
+    }
+}
+
+impl<C: LogControl1> Deref for LevelGuard<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.control
+    }
+}
+
+impl<C: LogControl1> DerefMut for LevelGuard<'_, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.control
+    }
+}
+
+impl<C: LogControl1> Drop for LevelGuard<'_, C> {
+    fn drop(&mut self) {
+        if let Err(error) = self.control.set_level(self.previous) {
+            (self.on_restore_error)(&error);
+        }
+    }
+}
+
+impl<C: LogControl1> Debug for LevelGuard<'_, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LevelGuard")
+            .field("previous", &self.previous)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::test_util::TestLogControl1;
+    use crate::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+
+    use super::LevelGuard;
+
+    #[test]
+    fn restore_level_on_drop() {
+        let mut control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+        {
+            let mut guard = LevelGuard::new(&mut control, LogLevel::Debug).unwrap();
+            assert_eq!(guard.level(), LogLevel::Debug);
+            assert_eq!(guard.previous_level(), LogLevel::Info);
+            // Changes through the guard get reverted as well
+            guard.set_level(LogLevel::Err).unwrap();
+            guard.set_target("console").unwrap();
+        }
+        assert_eq!(control.level(), LogLevel::Info);
+        assert_eq!(control.target(), "console");
+    }
+
+    /// Fail to set levels once `fail` is set.
+    struct FailingControl {
+        level: LogLevel,
+        fail: bool,
+    }
+
+    impl LogControl1 for FailingControl {
+        fn level(&self) -> LogLevel {
+            self.level
+        }
+
+        fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+            if self.fail {
+                Err(LogControl1Error::UnsupportedLogLevel(level))
+            } else {
+                self.level = level;
+                Ok(())
+            }
+        }
+
+        fn target(&self) -> &str {
+            "console"
+        }
+
+        fn set_target<S: AsRef<str>>(&mut self, _target: S) -> Result<(), LogControl1Error> {
+            Ok(())
+        }
+
+        fn syslog_identifier(&self) -> &str {
+            "test"
+        }
+    }
+
+    #[test]
+    fn report_restore_errors() {
+        static ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut control = FailingControl {
+            level: LogLevel::Info,
+            fail: false,
+        };
+        {
+            let mut guard =
+                LevelGuard::with_restore_error_handler(&mut control, LogLevel::Debug, |error| {
+                    assert!(matches!(
+                        error,
+                        LogControl1Error::UnsupportedLogLevel(LogLevel::Info)
+                    ));
+                    ERRORS.fetch_add(1, Ordering::SeqCst);
+                })
+                .unwrap();
+            guard.fail = true;
+        }
+        assert_eq!(ERRORS.load(Ordering::SeqCst), 1);
+        assert_eq!(control.level(), LogLevel::Debug);
+
+        // Failing to set the level up front returns no guard
+        assert!(LevelGuard::new(&mut control, LogLevel::Err).is_err());
+        assert_eq!(ERRORS.load(Ordering::SeqCst), 1);
+    }
+}
@@ -64,6 +64,46 @@
 //! should default to logging to the [`KnownLogTarget::Journal`] log target.
 //! This function also helps to implement the [`KnownLogTarget::Auto`] target.
 //!
+//! [`running_under_systemd`] determines whether the current process was started
+//! by systemd at all, regardless of where its stderr goes.
+//!
+//! [`boot_id`] reads the ID of the current boot, for sinks other than journald.
+//!
+//! [`log_level_from_systemd_env`] and [`log_target_from_systemd_env`] read the
+//! initial level and target from `$SYSTEMD_LOG_LEVEL` and `$SYSTEMD_LOG_TARGET`,
+//! like systemd's own tools.
+//!
+//! With the `signal` feature the `signal` module allows to change the log
+//! level with `SIGUSR1` and `SIGUSR2`, without going through DBus.
+//!
+//! [`LogControl1::status`] takes a [`LogControlStatus`] snapshot of the
+//! current configuration; with the `serde` feature it serializes to JSON, e.g.
+//! for a status endpoint of a service.  With the same feature
+//! `LoggingConfig` loads level and target from configuration files.
+//!
+//! [`LogControl1::capabilities`] tells which optional methods a control
+//! supports, see [`LogControlCapabilities`].
+//!
+//! [`SharedLogControl1`] shares a [`LogControl1`] between multiple owners, e.g.
+//! a DBus frontend and the application itself.
+//!
+//! [`Debounced`] coalesces rapid level changes, e.g. from scripts which toggle
+//! the level in a loop, into a single change per time window.
+//!
+//! [`LevelGuard`] changes the level temporarily, and restores the previous
+//! level when it goes out of scope.
+//!
+//! [`impl_log_control1!`] implements [`LogControl1`] for plain structs which
+//! just store the level and the target.
+//!
+//! The [`glib`] module maps levels to the log levels of GLib, for services
+//! which embed GLib-based libraries.
+//!
+//! The levels, targets, and the [`LogControl1`] trait do not need the standard
+//! library.  Without the default `std` feature this crate builds with `no_std`
+//! and `alloc`, and leaves out all helpers which inspect the current process,
+//! [`SharedLogControl1`], [`Debounced`], and [`LogControl1Error::InputOutputError`].
+//!
 //! ## Logging framework implementations and DBus frontends
 //!
 //! The following crates provides implementations of the [`LogControl1`] trait
@@ -72,6 +112,8 @@
 //! - [`logcontrol-tracing`](https://docs.rs/logcontrol-tracing) implements
 //!   the log control interface on top of the [`tracing`](https://doc.rs/tracing)
 //!   crate.
+//! - [`logcontrol-slog`](https://docs.rs/logcontrol-slog) implements the log
+//!   control interface on top of the [`slog`](https://docs.rs/slog) crate.
 //!
 //! These crates implement DBus frontends to actually expose an implementation
 //! of the [`LogControl1`] trait over DBus:
@@ -83,12 +125,53 @@
 
 #![deny(warnings, clippy::all, missing_docs, missing_debug_implementations)]
 #![forbid(unsafe_code)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
-use std::fmt::{Display, Formatter};
-use std::os::{fd::AsFd, linux::fs::MetadataExt};
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Formatter};
 
 use thiserror::Error;
 
+mod capabilities;
+#[cfg(feature = "serde")]
+mod config;
+#[cfg(feature = "std")]
+mod debounced;
+pub mod glib;
+mod guard;
+mod logged;
+mod macros;
+#[cfg(feature = "std")]
+mod shared;
+mod status;
+#[cfg(feature = "std")]
+mod system;
+
+#[cfg(feature = "signal")]
+pub mod signal;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
+pub use capabilities::LogControlCapabilities;
+#[cfg(feature = "serde")]
+pub use config::LoggingConfig;
+#[cfg(feature = "std")]
+pub use debounced::Debounced;
+pub use guard::LevelGuard;
+pub use logged::{LogControl1Operation, Logged};
+#[cfg(feature = "std")]
+pub use shared::SharedLogControl1;
+pub use status::LogControlStatus;
+#[cfg(feature = "std")]
+pub use system::{
+    boot_id, journal_reachable, log_level_from_systemd_env, log_target_from_systemd_env,
+    running_under_systemd, stderr_connected_to_journal, syslog_identifier, syslog_identifier_os,
+};
+
 /// A syslog log level as used by the systemd log control interface.
 ///
 /// See [POSIX syslog](https://pubs.opengroup.org/onlinepubs/9699919799.2018edition/functions/syslog.html)
@@ -113,11 +196,97 @@ pub enum LogLevel {
     Debug,
 }
 
+impl LogLevel {
+    /// The string representations of all levels, from most to least severe.
+    ///
+    /// Useful for shell completion or help texts.
+    pub const VARIANTS: &'static [&'static str] = &[
+        "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+    ];
+
+    /// Get the numeric syslog priority of this level.
+    ///
+    /// The priority ranges from `0` for [`LogLevel::Emerg`] to `7` for
+    /// [`LogLevel::Debug`]; see `syslog(3)`.
+    pub fn as_priority(self) -> u8 {
+        match self {
+            LogLevel::Emerg => 0,
+            LogLevel::Alert => 1,
+            LogLevel::Crit => 2,
+            LogLevel::Err => 3,
+            LogLevel::Warning => 4,
+            LogLevel::Notice => 5,
+            LogLevel::Info => 6,
+            LogLevel::Debug => 7,
+        }
+    }
+
+    /// Get the level `count` steps more verbose than `base`.
+    ///
+    /// Saturate at [`LogLevel::Debug`].  Maps repeated `-v` flags on the
+    /// command line to a level, e.g. with `clap`'s `ArgAction::Count`:
+    ///
+    /// ```
+    /// # use logcontrol::LogLevel;
+    /// assert_eq!(LogLevel::from_verbosity(LogLevel::Warning, 2), LogLevel::Info);
+    /// assert_eq!(LogLevel::from_verbosity(LogLevel::Warning, 42), LogLevel::Debug);
+    /// ```
+    pub fn from_verbosity(base: LogLevel, count: u8) -> LogLevel {
+        let priority = base
+            .as_priority()
+            .saturating_add(count)
+            .min(LogLevel::Debug.as_priority());
+        LogLevel::try_from(priority).unwrap_or(LogLevel::Debug)
+    }
+
+    /// Get the number of steps this level is more verbose than `base`.
+    ///
+    /// This is the inverse of [`LogLevel::from_verbosity`]; return `0` if this
+    /// level is not more verbose than `base`.
+    pub fn to_verbosity(self, base: LogLevel) -> u8 {
+        self.as_priority().saturating_sub(base.as_priority())
+    }
+}
+
 /// The log level was invalid.
 #[derive(Debug, Copy, Clone, Error)]
 #[error("Invalid log level")]
 pub struct LogLevelParseError;
 
+/// Convert a numeric syslog priority to a log level.
+///
+/// See [`LogLevel::as_priority`] for the inverse conversion.
+impl TryFrom<u8> for LogLevel {
+    type Error = LogLevelParseError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(LogLevel::Emerg),
+            1 => Ok(LogLevel::Alert),
+            2 => Ok(LogLevel::Crit),
+            3 => Ok(LogLevel::Err),
+            4 => Ok(LogLevel::Warning),
+            5 => Ok(LogLevel::Notice),
+            6 => Ok(LogLevel::Info),
+            7 => Ok(LogLevel::Debug),
+            _ => Err(LogLevelParseError),
+        }
+    }
+}
+
+/// Convert a numeric syslog priority to a log level.
+///
+/// See [`LogLevel::as_priority`] for the inverse conversion.
+impl TryFrom<i32> for LogLevel {
+    type Error = LogLevelParseError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        u8::try_from(value)
+            .map_err(|_| LogLevelParseError)
+            .and_then(LogLevel::try_from)
+    }
+}
+
 impl TryFrom<&str> for LogLevel {
     type Error = LogLevelParseError;
 
@@ -136,8 +305,23 @@ impl TryFrom<&str> for LogLevel {
     }
 }
 
+/// Parse a level from an OS string, e.g. from [`std::env::var_os`].
+///
+/// Fail like [`TryFrom<&str>`] if `value` is not valid UTF-8.
+#[cfg(feature = "std")]
+impl TryFrom<&std::ffi::OsStr> for LogLevel {
+    type Error = LogLevelParseError;
+
+    fn try_from(value: &std::ffi::OsStr) -> Result<Self, Self::Error> {
+        value
+            .to_str()
+            .ok_or(LogLevelParseError)
+            .and_then(LogLevel::try_from)
+    }
+}
+
 impl Display for LogLevel {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let level = match self {
             LogLevel::Emerg => "emerg",
             LogLevel::Alert => "alert",
@@ -163,7 +347,7 @@ impl Display for LogLevel {
 /// Implementations of [`LogControl1`] can use this enum to parse known targets,
 /// or entirely ignore it and handle the target themselves; the latter allows
 /// services to implement their own proprietary log targets.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum KnownLogTarget {
     /// Log to the console or standard output.
     Console,
@@ -206,6 +390,12 @@ pub enum KnownLogTarget {
 }
 
 impl KnownLogTarget {
+    /// The string representations of all known targets.
+    ///
+    /// Useful for shell completion or help texts.
+    pub const VARIANTS: &'static [&'static str] =
+        &["console", "kmsg", "journal", "syslog", "null", "auto"];
+
     /// Convert to the corresponding string representation.
     pub fn as_str(self) -> &'static str {
         match self {
@@ -217,6 +407,19 @@ impl KnownLogTarget {
             KnownLogTarget::Auto => "auto",
         }
     }
+
+    /// Whether logging to this target typically requires privileges.
+    ///
+    /// Return `true` for [`KnownLogTarget::Kmsg`], because only root may
+    /// write to `/dev/kmsg` by default, and `false` for all other targets.
+    /// The syslog socket at `/dev/log` is writable by all users on common
+    /// systems, so [`KnownLogTarget::Syslog`] returns `false`.
+    ///
+    /// This is a heuristic for warnings and error messages ahead of switching
+    /// targets; the actual permissions depend on the system.
+    pub fn requires_privileges(self) -> bool {
+        matches!(self, KnownLogTarget::Kmsg)
+    }
 }
 
 /// The log target was invalid.
@@ -224,6 +427,26 @@ impl KnownLogTarget {
 #[error("Invalid log target: '{0}'")]
 pub struct LogTargetParseError(String);
 
+impl LogTargetParseError {
+    /// Create an error for the invalid `target`.
+    ///
+    /// Useful for parsers of custom targets which report their errors like
+    /// [`KnownLogTarget::try_from`].
+    pub fn new<S: Into<String>>(target: S) -> Self {
+        Self(target.into())
+    }
+
+    /// Get the invalid target, as given to the parser.
+    pub fn target(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert into the invalid target.
+    pub fn into_target(self) -> String {
+        self.0
+    }
+}
+
 impl From<LogTargetParseError> for LogControl1Error {
     fn from(value: LogTargetParseError) -> Self {
         Self::UnsupportedLogTarget(value.0)
@@ -246,12 +469,77 @@ impl TryFrom<&str> for KnownLogTarget {
     }
 }
 
+/// Parse a target from an OS string, e.g. from [`std::env::var_os`].
+///
+/// Fail like [`TryFrom<&str>`] if `value` is not valid UTF-8; the error then
+/// holds `value` with invalid sequences replaced.
+#[cfg(feature = "std")]
+impl TryFrom<&std::ffi::OsStr> for KnownLogTarget {
+    type Error = LogTargetParseError;
+
+    fn try_from(value: &std::ffi::OsStr) -> Result<Self, Self::Error> {
+        value
+            .to_str()
+            .ok_or_else(|| LogTargetParseError(value.to_string_lossy().into_owned()))
+            .and_then(KnownLogTarget::try_from)
+    }
+}
+
+impl AsRef<str> for KnownLogTarget {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
 impl Display for KnownLogTarget {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.as_str())
     }
 }
 
+/// Resolve [`KnownLogTarget::Auto`] to a concrete target.
+///
+/// Return [`KnownLogTarget::Journal`] if `connected_to_journal`, and
+/// [`KnownLogTarget::Console`] otherwise; see [`stderr_connected_to_journal`]
+/// to determine whether the current process is connected to the journal.
+///
+/// This is the target which the log control implementations of this project
+/// select for [`KnownLogTarget::Auto`].
+pub fn resolve_auto(connected_to_journal: bool) -> KnownLogTarget {
+    if connected_to_journal {
+        KnownLogTarget::Journal
+    } else {
+        KnownLogTarget::Console
+    }
+}
+
+/// The result of [`try_parse_any`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParsedInput {
+    /// The input is a [`LogLevel`].
+    Level(LogLevel),
+    /// The input is a [`KnownLogTarget`].
+    Target(KnownLogTarget),
+    /// The input is neither a level nor a known target.
+    Unknown,
+}
+
+/// Parse `input` as a [`LogLevel`] or a [`KnownLogTarget`].
+///
+/// The names of levels and targets do not overlap, so `input` matches at most
+/// one of them.  Like the [`TryFrom`] implementations this function does not
+/// ignore surrounding whitespace.  It never panics, which makes it a single
+/// entry point for fuzzing the parsers.
+pub fn try_parse_any(input: &str) -> ParsedInput {
+    if let Ok(level) = LogLevel::try_from(input) {
+        ParsedInput::Level(level)
+    } else if let Ok(target) = KnownLogTarget::try_from(input) {
+        ParsedInput::Target(target)
+    } else {
+        ParsedInput::Unknown
+    }
+}
+
 /// An error in a [`LogControl1`] operation.
 #[derive(Debug, Error)]
 pub enum LogControl1Error {
@@ -262,13 +550,139 @@ pub enum LogControl1Error {
     #[error("The log target {0} is not supported")]
     UnsupportedLogTarget(String),
     /// An IO error occurred while changing log target or log level.
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
     #[error(transparent)]
     InputOutputError(#[from] std::io::Error),
+    /// Reloading the underlying logger failed.
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    #[error("{message}: {source}")]
+    ReloadFailed {
+        /// The change which failed.
+        message: String,
+        /// The error of the underlying log framework.
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
     /// A generic failure while changing log target or log level.
     #[error("{0}")]
     Failure(String),
 }
 
+#[cfg(feature = "std")]
+impl LogControl1Error {
+    /// Create a [`LogControl1Error::ReloadFailed`] error.
+    ///
+    /// `message` describes the change which failed, and `source` is the error
+    /// of the underlying log framework.
+    pub fn reload_failed<E>(message: impl Into<String>, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::ReloadFailed {
+            message: message.into(),
+            source: Box::new(source),
+        }
+    }
+}
+
+/// Convert to an IO error, for services with IO-centric error types.
+///
+/// Unwrap [`LogControl1Error::InputOutputError`], and wrap all other variants
+/// in an error of [`std::io::ErrorKind::Other`].
+#[cfg(feature = "std")]
+impl From<LogControl1Error> for std::io::Error {
+    fn from(error: LogControl1Error) -> Self {
+        match error {
+            LogControl1Error::InputOutputError(error) => error,
+            other => std::io::Error::new(std::io::ErrorKind::Other, other),
+        }
+    }
+}
+
+/// What a log control does when asked to switch to an unsupported log target.
+///
+/// `systemctl service-log-target` passes any target through to the service;
+/// lenient services can keep their current target instead of failing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UnsupportedTargetPolicy {
+    /// Fail with [`LogControl1Error::UnsupportedLogTarget`].
+    #[default]
+    Error,
+    /// Keep the current target silently, and report success.
+    Ignore,
+    /// Keep the current target, log a warning, and report success.
+    KeepCurrent,
+}
+
+impl UnsupportedTargetPolicy {
+    /// Apply this policy to the `result` of switching the target.
+    ///
+    /// Return `result` unchanged unless it failed with
+    /// [`LogControl1Error::UnsupportedLogTarget`] and this policy is lenient.
+    /// In this case return `Ok(())`; with [`Self::KeepCurrent`] call `warn`
+    /// with the unsupported target first.
+    ///
+    /// The log control must leave its target untouched if switching fails.
+    pub fn apply<F: FnOnce(&str)>(
+        self,
+        result: Result<(), LogControl1Error>,
+        warn: F,
+    ) -> Result<(), LogControl1Error> {
+        match (self, result) {
+            (Self::Ignore, Err(LogControl1Error::UnsupportedLogTarget(_))) => Ok(()),
+            (Self::KeepCurrent, Err(LogControl1Error::UnsupportedLogTarget(target))) => {
+                warn(&target);
+                Ok(())
+            }
+            (_, result) => result,
+        }
+    }
+}
+
+/// Call `f` with each of `targets` in order, until it succeeds.
+///
+/// Return the first target for which `f` succeeded, together with the result
+/// of `f`.  If `f` fails for all targets return the error for the first
+/// target, and [`LogControl1Error::Failure`] if `targets` is empty.
+///
+/// Log controls use this function to walk a chain of fallback targets, e.g.
+/// to prefer the journal, then syslog, and finally the console:
+///
+/// ```
+/// use logcontrol::{first_working_target, KnownLogTarget, LogControl1Error};
+///
+/// let chain = [KnownLogTarget::Journal, KnownLogTarget::Syslog, KnownLogTarget::Console];
+/// let (target, _) = first_working_target(&chain, |target| match target {
+///     KnownLogTarget::Console => Ok(()),
+///     other => Err(LogControl1Error::UnsupportedLogTarget(other.to_string())),
+/// })?;
+/// assert_eq!(target, KnownLogTarget::Console);
+/// # Ok::<(), LogControl1Error>(())
+/// ```
+pub fn first_working_target<T, F>(
+    targets: &[KnownLogTarget],
+    mut f: F,
+) -> Result<(KnownLogTarget, T), LogControl1Error>
+where
+    F: FnMut(KnownLogTarget) -> Result<T, LogControl1Error>,
+{
+    let mut first_error = None;
+    for &target in targets {
+        match f(target) {
+            Ok(value) => return Ok((target, value)),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    Err(first_error
+        .unwrap_or_else(|| LogControl1Error::Failure("No log target to try".to_string())))
+}
+
 /// Abstract representation of the [LogControl1] interface.
 ///
 /// Bridges a DBus frontend to a backend logging framework.
@@ -305,6 +719,107 @@ pub trait LogControl1 {
 
     /// Get the syslog identifier.
     fn syslog_identifier(&self) -> &str;
+
+    /// Change the level and the target together.
+    ///
+    /// The default implementation first sets the `target` and then the `level`;
+    /// if setting the target fails the level remains unchanged, but if setting
+    /// the level fails the new target remains in place.  Implementations
+    /// should override this method to check the `level` beforehand, or to
+    /// apply both changes atomically.
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        self.set_target(target)?;
+        self.set_level(level)
+    }
+
+    /// Whether logging currently works.
+    ///
+    /// Implementations should return `false` if they know that log messages
+    /// get lost, e.g. if the journal is no longer reachable while logging to
+    /// [`KnownLogTarget::Journal`].
+    ///
+    /// The default implementation always returns `true`.
+    fn is_healthy(&self) -> bool {
+        true
+    }
+
+    /// Get the name of the logging framework behind this control.
+    ///
+    /// Diagnostics can report this name, e.g. `tracing` or `log`, to tell
+    /// which implementation a service uses.
+    ///
+    /// The default implementation returns `unknown`.
+    fn backend_name(&self) -> &'static str {
+        "unknown"
+    }
+
+    /// Whether logging is currently muted.
+    ///
+    /// The default implementation always returns `false`.
+    fn is_muted(&self) -> bool {
+        false
+    }
+
+    /// Mute or unmute logging.
+    ///
+    /// While muted, implementations should drop all log records, but keep the
+    /// current level and target, and restore both when unmuted.  Unlike
+    /// switching to [`KnownLogTarget::Null`] this keeps the previous target
+    /// around.  Implementations should still accept level and target changes
+    /// while muted, and apply them when unmuted.
+    ///
+    /// The default implementation does not support muting: it fails with
+    /// [`LogControl1Error::Failure`] when asked to mute, and does nothing when
+    /// asked to unmute.
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        if muted {
+            Err(LogControl1Error::Failure(
+                "Muting is not supported".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reopen the current target.
+    ///
+    /// Implementations should close and recreate the logger for the current
+    /// target, e.g. to reopen a log file after logrotate moved it away, or to
+    /// reconnect to the journal after journald restarted.  Level, target, and
+    /// muting remain unchanged.
+    ///
+    /// The default implementation does nothing.
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        Ok(())
+    }
+
+    /// Get the optional capabilities of this control.
+    ///
+    /// Implementations should override this method to advertise the optional
+    /// methods they implement, e.g. [`LogControl1::set_muted`], so that clients
+    /// can discover them.
+    ///
+    /// The default implementation conservatively returns no capabilities.
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities::default()
+    }
+
+    /// Get a snapshot of the current configuration.
+    ///
+    /// The default implementation takes level, target, and syslog identifier
+    /// from the corresponding getters, and checks whether stderr is connected
+    /// to the journal with [`stderr_connected_to_journal`].
+    fn status(&self) -> LogControlStatus {
+        LogControlStatus {
+            level: self.level(),
+            target: self.target().to_string(),
+            syslog_identifier: self.syslog_identifier().to_string(),
+            #[cfg(feature = "std")]
+            connected_to_journal: stderr_connected_to_journal(),
+            #[cfg(not(feature = "std"))]
+            connected_to_journal: false,
+        }
+    }
 }
 
 /// The DBus object path a log control interface needs to be served on for systemd to find it.
@@ -312,43 +827,320 @@ pub trait LogControl1 {
 /// The path is `/org/freedesktop/LogControl1`, as required by the interface specification.
 pub static DBUS_OBJ_PATH: &str = "/org/freedesktop/LogControl1";
 
-/// Whether the current process is directly connected to the systemd journal.
+/// The journal `MESSAGE_ID` of events which announce a changed log configuration.
 ///
-/// You can use this function to implement [`KnownLogTarget::Auto`].
+/// Backends attach this ID to the event they emit after the level or the
+/// target changed, so that the change can be found with
+/// `journalctl MESSAGE_ID=50ca8f207ba94b788a78d895f7fc7191`.  The ID is
+/// stable and never changes.
+pub static CONFIGURATION_CHANGED_MESSAGE_ID: &str = "50ca8f207ba94b788a78d895f7fc7191";
+
+/// Describe the configuration of `control` in a single line.
 ///
-/// Return `true` if the device and inode numbers of the [`std::io::stderr`]
-/// file descriptor match the value of `$JOURNAL_STREAM` (see `systemd.exec(5)`).
-/// Otherwise, return `false`.
-pub fn stderr_connected_to_journal() -> bool {
-    std::io::stderr()
-        .as_fd()
-        .try_clone_to_owned()
-        .and_then(|fd| std::fs::File::from(fd).metadata())
-        .map(|metadata| format!("{}:{}", metadata.st_dev(), metadata.st_ino()))
-        .ok()
-        .and_then(|stderr| {
-            std::env::var_os("JOURNAL_STREAM").map(|s| s.to_string_lossy() == stderr.as_str())
-        })
-        .unwrap_or(false)
+/// Describe the target, the level, and the syslog identifier of `control`, for
+/// use in a log message at startup, e.g. `logging to journal at level info
+/// (auto-detected), syslog identifier "foo"`.  `auto_detected` denotes whether
+/// the target was chosen with [`KnownLogTarget::Auto`].
+pub fn describe_configuration<C: LogControl1>(control: &C, auto_detected: bool) -> String {
+    format!(
+        "logging to {} at level {} ({}), syslog identifier {:?}",
+        control.target(),
+        control.level(),
+        if auto_detected {
+            "auto-detected"
+        } else {
+            "configured"
+        },
+        control.syslog_identifier()
+    )
 }
 
-/// Determine the syslog identifier for this process.
-///
-/// This function obtains the syslog identifier from the file name of the
-/// current executable, per [`std::env::current_exe`].
-///
-/// As such, it's a comparatively expensive function to call; implementations of
-/// [`LogControl1`] should avoid calling it for every invocation, but instead
-/// determine the identifier once upon construction and store it.
-///
-/// If it fails to determine the syslog identifier, i.e. when `current_exe`
-/// returns an error, this function falls back to the empty string.
-pub fn syslog_identifier() -> String {
-    std::env::current_exe()
-        .ok()
-        .as_ref()
-        .and_then(|p| p.file_name())
-        .map(|n| n.to_string_lossy().into_owned())
-        // If we fail to get the name of the current executable fall back to an empty string.
-        .unwrap_or_default()
+#[cfg(test)]
+mod tests {
+    use crate::test_util::TestLogControl1;
+    use crate::{
+        describe_configuration, try_parse_any, KnownLogTarget, LogControl1, LogControl1Error,
+        LogLevel, LogTargetParseError, ParsedInput,
+    };
+
+    #[test]
+    fn log_level_from_priority() {
+        let levels = [
+            LogLevel::Emerg,
+            LogLevel::Alert,
+            LogLevel::Crit,
+            LogLevel::Err,
+            LogLevel::Warning,
+            LogLevel::Notice,
+            LogLevel::Info,
+            LogLevel::Debug,
+        ];
+        for (priority, level) in levels.into_iter().enumerate() {
+            assert_eq!(LogLevel::try_from(priority as u8).unwrap(), level);
+            assert_eq!(LogLevel::try_from(priority as i32).unwrap(), level);
+            assert_eq!(usize::from(level.as_priority()), priority);
+        }
+    }
+
+    #[test]
+    fn log_level_from_invalid_priority() {
+        assert!(LogLevel::try_from(8u8).is_err());
+        assert!(LogLevel::try_from(255u8).is_err());
+        assert!(LogLevel::try_from(8i32).is_err());
+        assert!(LogLevel::try_from(-1i32).is_err());
+        assert!(LogLevel::try_from(i32::MAX).is_err());
+    }
+
+    #[test]
+    fn log_level_variants_parse() {
+        for variant in LogLevel::VARIANTS {
+            let level = LogLevel::try_from(*variant).unwrap();
+            assert_eq!(level.to_string(), *variant);
+        }
+        assert_eq!(LogLevel::VARIANTS.len(), 8);
+    }
+
+    #[test]
+    fn known_log_target_variants_parse() {
+        for variant in KnownLogTarget::VARIANTS {
+            let target = KnownLogTarget::try_from(*variant).unwrap();
+            assert_eq!(target.as_str(), *variant);
+        }
+        assert_eq!(KnownLogTarget::VARIANTS.len(), 6);
+    }
+
+    #[test]
+    fn log_target_parse_error_target() {
+        let error = KnownLogTarget::try_from(" otlp\n").unwrap_err();
+        assert_eq!(error.target(), " otlp\n");
+        assert_eq!(error.to_string(), "Invalid log target: ' otlp\n'");
+        assert_eq!(error.into_target(), " otlp\n");
+        assert_eq!(LogTargetParseError::new("otlp").target(), "otlp");
+    }
+
+    #[test]
+    fn known_log_target_requires_privileges() {
+        let targets = [
+            (KnownLogTarget::Console, false),
+            (KnownLogTarget::Kmsg, true),
+            (KnownLogTarget::Journal, false),
+            (KnownLogTarget::Syslog, false),
+            (KnownLogTarget::Null, false),
+            (KnownLogTarget::Auto, false),
+        ];
+        for (target, requires_privileges) in targets {
+            assert_eq!(
+                target.requires_privileges(),
+                requires_privileges,
+                "{target}"
+            );
+        }
+    }
+
+    #[test]
+    fn known_log_target_as_ref() {
+        fn name<S: AsRef<str>>(target: S) -> String {
+            target.as_ref().to_string()
+        }
+        assert_eq!(name(KnownLogTarget::Journal), "journal");
+
+        let mut control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Console);
+        control.set_target(KnownLogTarget::Journal).unwrap();
+        assert_eq!(control.target(), "journal");
+    }
+
+    #[test]
+    fn backend_name() {
+        let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Console);
+        assert_eq!(control.backend_name(), "unknown");
+        let logged = crate::Logged::new(control, |_| {});
+        assert_eq!(logged.backend_name(), "unknown");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn try_from_os_str() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert_eq!(
+            LogLevel::try_from(OsStr::new("debug")).unwrap(),
+            LogLevel::Debug
+        );
+        assert_eq!(
+            KnownLogTarget::try_from(OsStr::new("journal")).unwrap(),
+            KnownLogTarget::Journal
+        );
+        assert!(LogLevel::try_from(OsStr::new("verbose")).is_err());
+        assert_eq!(
+            KnownLogTarget::try_from(OsStr::new("file"))
+                .unwrap_err()
+                .to_string(),
+            "Invalid log target: 'file'"
+        );
+
+        let invalid = OsStr::from_bytes(b"jour\xffnal");
+        assert!(LogLevel::try_from(invalid).is_err());
+        assert_eq!(
+            KnownLogTarget::try_from(invalid).unwrap_err().to_string(),
+            "Invalid log target: 'jour\u{fffd}nal'"
+        );
+    }
+
+    #[test]
+    fn resolve_auto() {
+        assert_eq!(super::resolve_auto(true), KnownLogTarget::Journal);
+        assert_eq!(super::resolve_auto(false), KnownLogTarget::Console);
+    }
+
+    #[test]
+    fn first_working_target() {
+        let chain = [
+            KnownLogTarget::Journal,
+            KnownLogTarget::Syslog,
+            KnownLogTarget::Console,
+        ];
+        let mut tried = Vec::new();
+        let (target, value) = super::first_working_target(&chain, |target| {
+            tried.push(target);
+            match target {
+                KnownLogTarget::Console => Ok(42),
+                other => Err(LogControl1Error::UnsupportedLogTarget(other.to_string())),
+            }
+        })
+        .unwrap();
+        assert_eq!(target, KnownLogTarget::Console);
+        assert_eq!(value, 42);
+        assert_eq!(tried, chain);
+
+        let error = super::first_working_target(&chain[..2], |target| {
+            Err::<(), _>(LogControl1Error::UnsupportedLogTarget(target.to_string()))
+        })
+        .unwrap_err();
+        assert!(matches!(error, LogControl1Error::UnsupportedLogTarget(t) if t == "journal"));
+
+        let error = super::first_working_target(&[], |_| Ok(())).unwrap_err();
+        assert!(matches!(error, LogControl1Error::Failure(_)));
+    }
+
+    #[test]
+    fn describe_configuration_of_control() {
+        let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+        assert_eq!(
+            describe_configuration(&control, true),
+            "logging to journal at level info (auto-detected), syslog identifier \"test\""
+        );
+        assert_eq!(
+            describe_configuration(&control, false),
+            "logging to journal at level info (configured), syslog identifier \"test\""
+        );
+    }
+
+    #[test]
+    fn try_parse_any_known_inputs() {
+        for variant in LogLevel::VARIANTS {
+            assert!(matches!(try_parse_any(variant), ParsedInput::Level(_)));
+        }
+        for variant in KnownLogTarget::VARIANTS {
+            assert!(matches!(try_parse_any(variant), ParsedInput::Target(_)));
+        }
+        assert_eq!(try_parse_any("info"), ParsedInput::Level(LogLevel::Info));
+        assert_eq!(
+            try_parse_any("journal"),
+            ParsedInput::Target(KnownLogTarget::Journal)
+        );
+        assert_eq!(try_parse_any(""), ParsedInput::Unknown);
+        assert_eq!(try_parse_any(" info"), ParsedInput::Unknown);
+        assert_eq!(try_parse_any("INFO"), ParsedInput::Unknown);
+    }
+
+    #[test]
+    fn try_parse_any_arbitrary_inputs() {
+        // A simple linear congruential generator, to get reproducible inputs
+        let mut state: u32 = 42;
+        let mut next = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            usize::try_from(state >> 16).unwrap()
+        };
+        let names = [LogLevel::VARIANTS, KnownLogTarget::VARIANTS].concat();
+        for _ in 0..1000 {
+            // Mix random bytes with prefixes of known names
+            let name = names[next() % names.len()].as_bytes();
+            let mut bytes = name[..next() % (name.len() + 1)].to_vec();
+            bytes.extend((0..next() % 3).map(|_| name[next() % name.len()] ^ (next() % 2) as u8));
+            let input = String::from_utf8_lossy(&bytes);
+            let parsed = try_parse_any(&input);
+            assert_eq!(
+                matches!(parsed, ParsedInput::Level(_)),
+                LogLevel::VARIANTS.contains(&input.as_ref()),
+                "Input {input:?}"
+            );
+            assert_eq!(
+                matches!(parsed, ParsedInput::Target(_)),
+                KnownLogTarget::VARIANTS.contains(&input.as_ref()),
+                "Input {input:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn log_control_error_into_io_error() {
+        use crate::LogControl1Error;
+
+        let error = std::io::Error::from(LogControl1Error::InputOutputError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no journal",
+        )));
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(error.to_string(), "no journal");
+
+        let errors = [
+            (
+                LogControl1Error::UnsupportedLogLevel(LogLevel::Emerg),
+                "The log level emerg is not supported",
+            ),
+            (
+                LogControl1Error::UnsupportedLogTarget("kmsg".to_string()),
+                "The log target kmsg is not supported",
+            ),
+            (LogControl1Error::Failure("Oops".to_string()), "Oops"),
+        ];
+        for (error, message) in errors {
+            let error = std::io::Error::from(error);
+            assert_eq!(error.kind(), std::io::ErrorKind::Other);
+            assert_eq!(error.to_string(), message);
+            assert!(error
+                .get_ref()
+                .is_some_and(|inner| inner.is::<LogControl1Error>()));
+        }
+    }
+
+    #[test]
+    fn log_level_verbosity() {
+        assert_eq!(
+            LogLevel::from_verbosity(LogLevel::Notice, 0),
+            LogLevel::Notice
+        );
+        assert_eq!(
+            LogLevel::from_verbosity(LogLevel::Notice, 1),
+            LogLevel::Info
+        );
+        assert_eq!(
+            LogLevel::from_verbosity(LogLevel::Notice, 3),
+            LogLevel::Debug
+        );
+        assert_eq!(
+            LogLevel::from_verbosity(LogLevel::Emerg, u8::MAX),
+            LogLevel::Debug
+        );
+        assert_eq!(LogLevel::Err.to_verbosity(LogLevel::Notice), 0);
+        for base in 0..8 {
+            let base = LogLevel::try_from(base).unwrap();
+            for count in 0..=(7 - base.as_priority()) {
+                let level = LogLevel::from_verbosity(base, count);
+                assert_eq!(level.to_verbosity(base), count);
+            }
+        }
+    }
 }
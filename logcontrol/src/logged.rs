@@ -0,0 +1,264 @@
+//! Trace the operations on a log control.
+
+use core::fmt::{Debug, Display, Formatter};
+
+use crate::{LogControl1, LogControl1Error, LogControlCapabilities, LogLevel};
+
+/// An operation on a [`LogControl1`], as reported by [`Logged`].
+///
+/// The [`Display`] implementation describes the operation and its result in a
+/// single line, e.g. `set_target("journal") -> Ok`.
+#[derive(Debug)]
+pub enum LogControl1Operation<'a> {
+    /// [`LogControl1::level`] returned the given level.
+    Level(LogLevel),
+    /// [`LogControl1::set_level`] was called with `level`.
+    SetLevel {
+        /// The requested level.
+        level: LogLevel,
+        /// The result of the call.
+        result: &'a Result<(), LogControl1Error>,
+    },
+    /// [`LogControl1::target`] returned the given target.
+    Target(&'a str),
+    /// [`LogControl1::set_target`] was called with `target`.
+    SetTarget {
+        /// The requested target.
+        target: &'a str,
+        /// The result of the call.
+        result: &'a Result<(), LogControl1Error>,
+    },
+    /// [`LogControl1::reconfigure`] was called with `level` and `target`.
+    Reconfigure {
+        /// The requested level.
+        level: LogLevel,
+        /// The requested target.
+        target: &'a str,
+        /// The result of the call.
+        result: &'a Result<(), LogControl1Error>,
+    },
+    /// [`LogControl1::set_muted`] was called with `muted`.
+    SetMuted {
+        /// Whether to mute or unmute.
+        muted: bool,
+        /// The result of the call.
+        result: &'a Result<(), LogControl1Error>,
+    },
+    /// [`LogControl1::reopen`] was called.
+    Reopen {
+        /// The result of the call.
+        result: &'a Result<(), LogControl1Error>,
+    },
+}
+
+/// Write `Ok` or the error of `result`.
+fn fmt_result(f: &mut Formatter<'_>, result: &Result<(), LogControl1Error>) -> core::fmt::Result {
+    match result {
+        Ok(()) => write!(f, "Ok"),
+        Err(error) => write!(f, "Err({error})"),
+    }
+}
+
+impl Display for LogControl1Operation<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Level(level) => write!(f, "level() -> {level}"),
+            Self::SetLevel { level, result } => {
+                write!(f, "set_level({level}) -> ")?;
+                fmt_result(f, result)
+            }
+            Self::Target(target) => write!(f, "target() -> {target}"),
+            Self::SetTarget { target, result } => {
+                write!(f, "set_target({target:?}) -> ")?;
+                fmt_result(f, result)
+            }
+            Self::Reconfigure {
+                level,
+                target,
+                result,
+            } => {
+                write!(f, "reconfigure({level}, {target:?}) -> ")?;
+                fmt_result(f, result)
+            }
+            Self::SetMuted { muted, result } => {
+                write!(f, "set_muted({muted}) -> ")?;
+                fmt_result(f, result)
+            }
+            Self::Reopen { result } => {
+                write!(f, "reopen() -> ")?;
+                fmt_result(f, result)
+            }
+        }
+    }
+}
+
+/// A [`LogControl1`] which reports all operations on the underlying control.
+///
+/// Forward all calls to the underlying control, and pass every call to
+/// [`LogControl1::level`], [`LogControl1::set_level`],
+/// [`LogControl1::target`], [`LogControl1::set_target`],
+/// [`LogControl1::reconfigure`], [`LogControl1::set_muted`], and
+/// [`LogControl1::reopen`] together with its result to a callback.  This
+/// helps to find out why changes through `systemctl` do not take effect.
+///
+/// The callback decides where reports go, so this crate need not depend on
+/// any logging framework:
+///
+/// ```
+/// # use logcontrol::*;
+/// fn trace<C: LogControl1>(control: C) -> impl LogControl1 {
+///     Logged::new(control, |operation| eprintln!("LogControl1: {operation}"))
+/// }
+/// ```
+pub struct Logged<C, F> {
+    control: C,
+    callback: F,
+}
+
+impl<C, F> Logged<C, F>
+where
+    C: LogControl1,
+    F: Fn(&LogControl1Operation<'_>),
+{
+    /// Report all operations on `control` to `callback`.
+    pub fn new(control: C, callback: F) -> Self {
+        Self { control, callback }
+    }
+
+    /// Get the underlying control.
+    pub fn into_inner(self) -> C {
+        self.control
+    }
+}
+
+impl<C: Debug, F> Debug for Logged<C, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Logged")
+            .field("control", &self.control)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C, F> LogControl1 for Logged<C, F>
+where
+    C: LogControl1,
+    F: Fn(&LogControl1Operation<'_>),
+{
+    fn level(&self) -> LogLevel {
+        let level = self.control.level();
+        (self.callback)(&LogControl1Operation::Level(level));
+        level
+    }
+
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        let result = self.control.set_level(level);
+        (self.callback)(&LogControl1Operation::SetLevel {
+            level,
+            result: &result,
+        });
+        result
+    }
+
+    fn target(&self) -> &str {
+        let target = self.control.target();
+        (self.callback)(&LogControl1Operation::Target(target));
+        target
+    }
+
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        let result = self.control.set_target(target.as_ref());
+        (self.callback)(&LogControl1Operation::SetTarget {
+            target: target.as_ref(),
+            result: &result,
+        });
+        result
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        self.control.syslog_identifier()
+    }
+
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        let result = self.control.reconfigure(level, target);
+        (self.callback)(&LogControl1Operation::Reconfigure {
+            level,
+            target,
+            result: &result,
+        });
+        result
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.control.is_healthy()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.control.backend_name()
+    }
+
+    fn is_muted(&self) -> bool {
+        self.control.is_muted()
+    }
+
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        let result = self.control.set_muted(muted);
+        (self.callback)(&LogControl1Operation::SetMuted {
+            muted,
+            result: &result,
+        });
+        result
+    }
+
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        let result = self.control.reopen();
+        (self.callback)(&LogControl1Operation::Reopen { result: &result });
+        result
+    }
+
+    fn capabilities(&self) -> LogControlCapabilities {
+        self.control.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::test_util::TestLogControl1;
+    use crate::{KnownLogTarget, LogControl1, LogLevel};
+
+    use super::Logged;
+
+    #[test]
+    fn report_operations() {
+        let reports = Mutex::new(Vec::new());
+        let mut control = Logged::new(
+            TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal),
+            |operation| reports.lock().unwrap().push(operation.to_string()),
+        );
+        assert_eq!(control.level(), LogLevel::Info);
+        control.set_level(LogLevel::Debug).unwrap();
+        assert_eq!(control.target(), "journal");
+        control.set_target("null").unwrap();
+        control.set_target("kmsg").unwrap_err();
+        control.reconfigure(LogLevel::Err, "console").unwrap();
+        control.set_muted(true).unwrap();
+        assert!(control.is_muted());
+        control.reopen().unwrap();
+        assert_eq!(control.syslog_identifier(), "test");
+        assert!(control.is_healthy());
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                "level() -> info",
+                "set_level(debug) -> Ok",
+                "target() -> journal",
+                "set_target(\"null\") -> Ok",
+                "set_target(\"kmsg\") -> Err(The log target kmsg is not supported)",
+                "reconfigure(err, \"console\") -> Ok",
+                "set_muted(true) -> Ok",
+                "reopen() -> Ok",
+            ]
+        );
+    }
+}
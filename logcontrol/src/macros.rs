@@ -0,0 +1,136 @@
+//! Macros to implement [`crate::LogControl1`].
+
+/// Implement [`LogControl1`](crate::LogControl1) for a struct which stores the level and the target.
+///
+/// Name the fields which hold the [`LogLevel`](crate::LogLevel), the
+/// [`KnownLogTarget`](crate::KnownLogTarget), and the syslog identifier, which
+/// may be any type which dereferences to `str`, e.g. `String` or `&'static str`.
+///
+/// The generated implementation accepts all levels and all known targets,
+/// including [`KnownLogTarget::Auto`](crate::KnownLogTarget::Auto), which it
+/// stores as is, like systemd does.  Unknown targets fail with
+/// [`LogControl1Error::UnsupportedLogTarget`](crate::LogControl1Error::UnsupportedLogTarget).
+/// The generated implementation only stores the new level and target; use it
+/// for controls which read their fields elsewhere, e.g. to configure logging
+/// on every request.
+///
+/// ```rust
+/// use logcontrol::{KnownLogTarget, LogControl1, LogLevel};
+///
+/// struct Settings {
+///     level: LogLevel,
+///     target: KnownLogTarget,
+///     name: String,
+/// }
+///
+/// logcontrol::impl_log_control1!(Settings {
+///     level: level,
+///     target: target,
+///     syslog_identifier: name,
+/// });
+///
+/// let mut settings = Settings {
+///     level: LogLevel::Info,
+///     target: KnownLogTarget::Console,
+///     name: "example".to_string(),
+/// };
+/// settings.set_target("journal").unwrap();
+/// assert_eq!(settings.target, KnownLogTarget::Journal);
+/// assert_eq!(settings.syslog_identifier(), "example");
+/// ```
+#[macro_export]
+macro_rules! impl_log_control1 {
+    ($type:ty {
+        level: $level:ident,
+        target: $target:ident,
+        syslog_identifier: $syslog_identifier:ident $(,)?
+    }) => {
+        impl $crate::LogControl1 for $type {
+            fn level(&self) -> $crate::LogLevel {
+                self.$level
+            }
+
+            fn set_level(
+                &mut self,
+                level: $crate::LogLevel,
+            ) -> ::core::result::Result<(), $crate::LogControl1Error> {
+                self.$level = level;
+                ::core::result::Result::Ok(())
+            }
+
+            fn target(&self) -> &str {
+                self.$target.as_str()
+            }
+
+            fn set_target<S: ::core::convert::AsRef<str>>(
+                &mut self,
+                target: S,
+            ) -> ::core::result::Result<(), $crate::LogControl1Error> {
+                self.$target =
+                    <$crate::KnownLogTarget as ::core::convert::TryFrom<&str>>::try_from(
+                        target.as_ref(),
+                    )?;
+                ::core::result::Result::Ok(())
+            }
+
+            fn syslog_identifier(&self) -> &str {
+                &self.$syslog_identifier
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+
+    struct Control {
+        current_level: LogLevel,
+        current_target: KnownLogTarget,
+        identifier: &'static str,
+    }
+
+    crate::impl_log_control1!(Control {
+        level: current_level,
+        target: current_target,
+        syslog_identifier: identifier
+    });
+
+    #[test]
+    fn generated_impl() {
+        let mut control = Control {
+            current_level: LogLevel::Info,
+            current_target: KnownLogTarget::Console,
+            identifier: "test",
+        };
+        assert_eq!(control.level(), LogLevel::Info);
+        assert_eq!(control.target(), "console");
+        assert_eq!(control.syslog_identifier(), "test");
+
+        control.set_level(LogLevel::Emerg).unwrap();
+        assert_eq!(control.current_level, LogLevel::Emerg);
+        control.set_target(KnownLogTarget::Auto).unwrap();
+        assert_eq!(control.target(), "auto");
+        control.reconfigure(LogLevel::Debug, "kmsg").unwrap();
+        assert_eq!(control.level(), LogLevel::Debug);
+        assert_eq!(control.current_target, KnownLogTarget::Kmsg);
+
+        assert!(matches!(
+            control.set_target("file"),
+            Err(LogControl1Error::UnsupportedLogTarget(target)) if target == "file"
+        ));
+        assert_eq!(control.target(), "kmsg");
+
+        // The generated impl does not support muting
+        assert!(matches!(
+            control.set_muted(true),
+            Err(LogControl1Error::Failure(_))
+        ));
+        control.set_muted(false).unwrap();
+        assert!(!control.is_muted());
+
+        // Reopening does nothing by default
+        control.reopen().unwrap();
+        assert_eq!(control.target(), "kmsg");
+    }
+}
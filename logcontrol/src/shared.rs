@@ -0,0 +1,447 @@
+//! Share a log control between multiple owners.
+
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::{LogControl1, LogControl1Error, LogControlCapabilities, LogLevel};
+
+/// The state shared between all owners of a [`SharedLogControl1`].
+struct SharedState<C> {
+    /// The underlying control.
+    control: C,
+    /// The current target of `control`.
+    ///
+    /// We keep a copy of the target to look it up in [`Targets`] without
+    /// asking the control again.
+    target: String,
+}
+
+/// All targets a [`SharedLogControl1`] ever had.
+///
+/// We cannot hand out a `&str` into the locked [`SharedState`], so we keep
+/// every distinct target in this append-only list, and hand out a `&str` into
+/// the list instead.  Entries never change once added, and live as long as
+/// any owner of the shared control, so the list only grows by one entry for
+/// every distinct target.
+struct Targets {
+    target: String,
+    next: OnceLock<Box<Targets>>,
+}
+
+impl Targets {
+    fn new(target: &str) -> Self {
+        Self {
+            target: target.to_string(),
+            next: OnceLock::new(),
+        }
+    }
+
+    /// Find `target` in this list, and append it if it's not yet contained.
+    fn get_or_append(&self, target: &str) -> &str {
+        let mut entry = self;
+        loop {
+            if entry.target == target {
+                return &entry.target;
+            }
+            // If another thread appended a different target concurrently we
+            // just continue with the next entry.
+            entry = entry.next.get_or_init(|| Box::new(Self::new(target)));
+        }
+    }
+}
+
+/// A [`LogControl1`] which can be shared between multiple owners.
+///
+/// DBus frontends take ownership of the underlying [`LogControl1`] and require
+/// `&mut self` to change the level or the target; the application can then no
+/// longer change the level itself, e.g. in response to a signal.
+///
+/// This type wraps a [`LogControl1`] in an [`Arc`] and a [`Mutex`]; all clones
+/// refer to the same underlying control.  The inherent methods change the
+/// level and the target through `&self`, and [`LogControl1`] is implemented
+/// as well, so that a clone can be handed to a DBus frontend while the
+/// application keeps another clone:
+///
+/// ```
+/// # use logcontrol::*;
+/// fn share<C: LogControl1>(control: C) -> Result<(), LogControl1Error> {
+///     let shared = SharedLogControl1::new(control);
+///     let frontend = shared.clone();
+///     // Hand `frontend` to a DBus frontend, and change the level from application code:
+///     shared.set_level(LogLevel::Debug)?;
+///     assert_eq!(frontend.level(), LogLevel::Debug);
+///     Ok(())
+/// }
+/// ```
+///
+/// The shared control passes all targets through to the underlying control
+/// unchanged, including custom targets of the underlying control.
+pub struct SharedLogControl1<C> {
+    state: Arc<Mutex<SharedState<C>>>,
+    /// All targets of the underlying control so far.
+    targets: Arc<Targets>,
+    /// The syslog identifier of the underlying control.
+    ///
+    /// The syslog identifier never changes, so we can just copy it.
+    syslog_identifier: Arc<str>,
+}
+
+impl<C: LogControl1> SharedLogControl1<C> {
+    /// Share the given `control`.
+    pub fn new(control: C) -> Self {
+        let target = control.target().to_string();
+        let syslog_identifier = Arc::from(control.syslog_identifier());
+        Self {
+            targets: Arc::new(Targets::new(&target)),
+            state: Arc::new(Mutex::new(SharedState { control, target })),
+            syslog_identifier,
+        }
+    }
+
+    /// Lock the shared state for reading.
+    ///
+    /// Reading the level or target cannot make matters worse, so we read
+    /// through a poisoned lock.
+    fn read(&self) -> MutexGuard<'_, SharedState<C>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the shared state for changes.
+    ///
+    /// Fail if the lock is poisoned, because a panic in a previous change left
+    /// the underlying control in an unknown state.
+    fn write(&self) -> Result<MutexGuard<'_, SharedState<C>>, LogControl1Error> {
+        self.state.lock().map_err(|_| {
+            LogControl1Error::Failure(
+                "A previous change of the log control panicked; refusing further changes"
+                    .to_string(),
+            )
+        })
+    }
+
+    /// Get the currently configured log level.
+    pub fn level(&self) -> LogLevel {
+        self.read().control.level()
+    }
+
+    /// Set the level of the underlying control.
+    ///
+    /// Return [`LogControl1Error::Failure`] if a previous change panicked.
+    pub fn set_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.write()?.control.set_level(level)
+    }
+
+    /// Get the currently configured log target.
+    pub fn target(&self) -> &str {
+        self.targets.get_or_append(&self.read().target)
+    }
+
+    /// Remember the current target of the underlying control.
+    fn update_target(state: &mut SharedState<C>) {
+        if state.target != state.control.target() {
+            state.target = state.control.target().to_string();
+        }
+    }
+
+    /// Set the target of the underlying control.
+    ///
+    /// Ignore leading and trailing whitespace in `target`, and pass it to the
+    /// underlying control otherwise unchanged.
+    ///
+    /// Return any error of the underlying control, and [`LogControl1Error::Failure`]
+    /// if a previous change panicked.
+    pub fn set_target<S: AsRef<str>>(&self, target: S) -> Result<(), LogControl1Error> {
+        let mut state = self.write()?;
+        let result = state.control.set_target(target.as_ref().trim());
+        Self::update_target(&mut state);
+        result
+    }
+
+    /// Change the level and the target of the underlying control together.
+    ///
+    /// Hold the lock while changing both, so that other owners never observe
+    /// the new target with the old level.  See [`LogControl1::reconfigure`]
+    /// for what happens if either change fails.  Like [`Self::set_target`]
+    /// ignore leading and trailing whitespace in `target`.
+    ///
+    /// Return any error of the underlying control, and [`LogControl1Error::Failure`]
+    /// if a previous change panicked.
+    pub fn reconfigure(&self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        let mut state = self.write()?;
+        let result = state.control.reconfigure(level, target.trim());
+        Self::update_target(&mut state);
+        result
+    }
+
+    /// Get the syslog identifier.
+    pub fn syslog_identifier(&self) -> &str {
+        &self.syslog_identifier
+    }
+
+    /// Whether logging currently works.
+    ///
+    /// Return `false` if a previous change panicked, and otherwise ask the
+    /// underlying control.
+    pub fn is_healthy(&self) -> bool {
+        !self.state.is_poisoned() && self.read().control.is_healthy()
+    }
+
+    /// Whether logging is currently muted.
+    pub fn is_muted(&self) -> bool {
+        self.read().control.is_muted()
+    }
+
+    /// Mute or unmute the underlying control.
+    ///
+    /// Return [`LogControl1Error::Failure`] if a previous change panicked.
+    pub fn set_muted(&self, muted: bool) -> Result<(), LogControl1Error> {
+        self.write()?.control.set_muted(muted)
+    }
+
+    /// Reopen the current target of the underlying control.
+    ///
+    /// Return [`LogControl1Error::Failure`] if a previous change panicked.
+    pub fn reopen(&self) -> Result<(), LogControl1Error> {
+        self.write()?.control.reopen()
+    }
+}
+
+impl<C> Clone for SharedLogControl1<C> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            targets: self.targets.clone(),
+            syslog_identifier: self.syslog_identifier.clone(),
+        }
+    }
+}
+
+impl<C> Debug for SharedLogControl1<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedLogControl1")
+            .field("syslog_identifier", &self.syslog_identifier)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: LogControl1> LogControl1 for SharedLogControl1<C> {
+    fn level(&self) -> LogLevel {
+        SharedLogControl1::level(self)
+    }
+
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        SharedLogControl1::set_level(self, level)
+    }
+
+    fn target(&self) -> &str {
+        SharedLogControl1::target(self)
+    }
+
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        SharedLogControl1::set_target(self, target)
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        SharedLogControl1::syslog_identifier(self)
+    }
+
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        SharedLogControl1::reconfigure(self, level, target)
+    }
+
+    fn is_healthy(&self) -> bool {
+        SharedLogControl1::is_healthy(self)
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.read().control.backend_name()
+    }
+
+    fn is_muted(&self) -> bool {
+        SharedLogControl1::is_muted(self)
+    }
+
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        SharedLogControl1::set_muted(self, muted)
+    }
+
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        SharedLogControl1::reopen(self)
+    }
+
+    fn capabilities(&self) -> LogControlCapabilities {
+        self.read().control.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::test_util::TestLogControl1;
+    use crate::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+
+    use super::SharedLogControl1;
+
+    fn shared() -> SharedLogControl1<TestLogControl1> {
+        SharedLogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        ))
+    }
+
+    #[test]
+    fn change_from_two_owners() {
+        let app = shared();
+        let mut frontend = app.clone();
+        assert_eq!(frontend.syslog_identifier(), "test");
+        assert!(LogControl1::is_healthy(&frontend));
+        assert_eq!(frontend.backend_name(), "unknown");
+
+        app.set_level(LogLevel::Debug).unwrap();
+        assert_eq!(LogControl1::level(&frontend), LogLevel::Debug);
+        LogControl1::set_level(&mut frontend, LogLevel::Warning).unwrap();
+        assert_eq!(app.level(), LogLevel::Warning);
+
+        LogControl1::set_target(&mut frontend, "auto").unwrap();
+        assert_eq!(app.target(), "console");
+        app.set_target("null").unwrap();
+        assert_eq!(LogControl1::target(&frontend), "null");
+
+        LogControl1::set_muted(&mut frontend, true).unwrap();
+        assert!(app.is_muted());
+
+        LogControl1::reopen(&mut frontend).unwrap();
+        assert_eq!(app.read().control.reopen_count(), 1);
+    }
+
+    #[test]
+    fn change_from_two_threads() {
+        let app = shared();
+        let frontend = app.clone();
+        thread::spawn(move || frontend.set_level(LogLevel::Debug).unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(app.level(), LogLevel::Debug);
+    }
+
+    #[test]
+    fn failed_set_target_keeps_target() {
+        let app = shared();
+        assert!(matches!(
+            app.set_target("syslog"),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+        assert!(matches!(
+            app.set_target("foo"),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+        assert_eq!(app.target(), "journal");
+    }
+
+    #[test]
+    fn reconfigure() {
+        let app = shared();
+        let mut frontend = app.clone();
+        LogControl1::reconfigure(&mut frontend, LogLevel::Debug, "null").unwrap();
+        assert_eq!(app.level(), LogLevel::Debug);
+        assert_eq!(app.target(), "null");
+
+        assert!(matches!(
+            app.reconfigure(LogLevel::Err, "kmsg"),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+        assert_eq!(app.level(), LogLevel::Debug);
+        assert_eq!(app.target(), "null");
+    }
+
+    #[test]
+    fn set_target_ignores_whitespace() {
+        let app = shared();
+        app.set_target(" null\n").unwrap();
+        assert_eq!(app.target(), "null");
+        app.reconfigure(LogLevel::Debug, "\tconsole ").unwrap();
+        assert_eq!(app.target(), "console");
+        assert!(matches!(
+            app.set_target("  "),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+    }
+
+    /// Accept any target, and call unknown targets by their upper-case name.
+    struct CustomTargets {
+        target: String,
+    }
+
+    impl LogControl1 for CustomTargets {
+        fn level(&self) -> LogLevel {
+            LogLevel::Info
+        }
+
+        fn set_level(&mut self, _level: LogLevel) -> Result<(), LogControl1Error> {
+            Ok(())
+        }
+
+        fn target(&self) -> &str {
+            &self.target
+        }
+
+        fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+            self.target = KnownLogTarget::try_from(target.as_ref()).map_or_else(
+                |_| target.as_ref().to_uppercase(),
+                |t| t.as_str().to_string(),
+            );
+            Ok(())
+        }
+
+        fn syslog_identifier(&self) -> &str {
+            "test"
+        }
+    }
+
+    #[test]
+    fn custom_targets() {
+        let app = SharedLogControl1::new(CustomTargets {
+            target: "file".to_string(),
+        });
+        let mut frontend = app.clone();
+        assert_eq!(app.target(), "file");
+        app.set_target(" rotating-file ").unwrap();
+        assert_eq!(LogControl1::target(&frontend), "ROTATING-FILE");
+        LogControl1::reconfigure(&mut frontend, LogLevel::Info, "journal").unwrap();
+        assert_eq!(app.target(), "journal");
+        app.set_target("rotating-file").unwrap();
+        assert_eq!(app.target(), "ROTATING-FILE");
+        // Switching back and forth adds no new entries
+        let mut entries = 0;
+        let mut next = Some(&*app.targets);
+        while let Some(entry) = next {
+            entries += 1;
+            next = entry.next.get().map(|e| &**e);
+        }
+        assert_eq!(entries, 3);
+    }
+
+    #[test]
+    fn poisoned_lock_fails_changes() {
+        let app = shared();
+        let other = app.clone();
+        thread::spawn(move || {
+            let _guard = other.state.lock().unwrap();
+            panic!("Poison the lock");
+        })
+        .join()
+        .unwrap_err();
+        assert!(!app.is_healthy());
+        assert_eq!(app.level(), LogLevel::Info);
+        assert_eq!(app.target(), "journal");
+        assert!(matches!(
+            app.set_level(LogLevel::Debug),
+            Err(LogControl1Error::Failure(_))
+        ));
+        assert!(matches!(
+            app.set_target("console"),
+            Err(LogControl1Error::Failure(_))
+        ));
+    }
+}
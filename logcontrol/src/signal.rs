@@ -0,0 +1,244 @@
+//! Change the log level with signals.
+//!
+//! Many daemons allow to change their log level with signals, without going
+//! through DBus: `SIGUSR2` makes logging more verbose, and `SIGUSR1` resets
+//! logging to the initial level.
+//!
+//! [`level_signals`] listens for these signals and forwards them to a channel,
+//! and [`SignalLevelToggle`] applies received signals to a [`LogControl1`]:
+//!
+//! ```no_run
+//! use logcontrol::LogControl1;
+//! use logcontrol::signal::{level_signals, SignalLevelToggle};
+//!
+//! fn handle_signals<C: LogControl1>(mut control: C) -> std::io::Result<()> {
+//!     let toggle = SignalLevelToggle::new(control.level());
+//!     let signals = level_signals()?;
+//!     toggle.run(&mut control, signals.iter(), |signal, error| {
+//!         eprintln!("Failed to handle {signal:?}: {error}");
+//!     });
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Handling signals only needs a [`LogControl1`], and works the same for all
+//! logging frameworks, so this module lives in the core crate instead of
+//! every backend crate.  To hand the control to a DBus frontend and still
+//! change its level on signals, share it with [`crate::SharedLogControl1`].
+
+use std::io;
+use std::sync::mpsc::{channel, Receiver};
+
+use signal_hook::consts::{SIGUSR1, SIGUSR2};
+use signal_hook::iterator::{Handle, Signals};
+
+use crate::{LogControl1, LogControl1Error, LogLevel};
+
+/// A signal to change the log level.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LevelSignal {
+    /// Make logging more verbose, sent with `SIGUSR2`.
+    Increase,
+    /// Reset logging to the initial level, sent with `SIGUSR1`.
+    Reset,
+}
+
+/// Received `SIGUSR1` and `SIGUSR2` signals.
+///
+/// Created by [`level_signals`].  Dropping this value unregisters the signal
+/// handlers and stops the thread which forwards signals.
+#[derive(Debug)]
+pub struct LevelSignals {
+    receiver: Receiver<LevelSignal>,
+    handle: Handle,
+}
+
+impl LevelSignals {
+    /// Iterate over received signals.
+    ///
+    /// The iterator blocks until the next signal arrives, and ends after
+    /// [`Self::close`].
+    pub fn iter(&self) -> impl Iterator<Item = LevelSignal> + '_ {
+        self.receiver.iter()
+    }
+
+    /// Unregister the signal handlers.
+    ///
+    /// Stop the thread which forwards signals; iterators over signals end
+    /// after all signals received so far.
+    pub fn close(&self) {
+        self.handle.close();
+    }
+}
+
+impl Drop for LevelSignals {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Listen for `SIGUSR1` and `SIGUSR2`.
+///
+/// Register handlers for these signals and spawn a thread which forwards
+/// received signals as [`LevelSignal`]s.  The signal handlers themselves only
+/// write to a self-pipe, see [`signal_hook::iterator`].
+///
+/// Return an error if registering the signal handlers fails.
+pub fn level_signals() -> io::Result<LevelSignals> {
+    let mut signals = Signals::new([SIGUSR1, SIGUSR2])?;
+    let handle = signals.handle();
+    let (sender, receiver) = channel();
+    std::thread::Builder::new()
+        .name("logcontrol-signals".to_string())
+        .spawn(move || {
+            // Ends when the handle gets closed
+            for signal in signals.forever() {
+                let signal = if signal == SIGUSR2 {
+                    LevelSignal::Increase
+                } else {
+                    LevelSignal::Reset
+                };
+                if sender.send(signal).is_err() {
+                    // The receiver is gone, so nobody's interested in signals anymore.
+                    break;
+                }
+            }
+        })?;
+    Ok(LevelSignals { receiver, handle })
+}
+
+/// Apply [`LevelSignal`]s to a log control.
+#[derive(Debug, Copy, Clone)]
+pub struct SignalLevelToggle {
+    initial: LogLevel,
+}
+
+impl SignalLevelToggle {
+    /// Create a new toggle which resets to the given `initial` level.
+    pub fn new(initial: LogLevel) -> Self {
+        Self { initial }
+    }
+
+    /// Handle `signal` by changing the level of `control`.
+    ///
+    /// For [`LevelSignal::Increase`] switch to the next more verbose level, or
+    /// cycle back to the initial level if `control` already logs at
+    /// [`LogLevel::Debug`].  For [`LevelSignal::Reset`] switch to the initial
+    /// level.
+    ///
+    /// Return the new level, or any error returned from [`LogControl1::set_level`].
+    pub fn handle<C: LogControl1>(
+        &self,
+        control: &mut C,
+        signal: LevelSignal,
+    ) -> Result<LogLevel, LogControl1Error> {
+        let level = match signal {
+            LevelSignal::Increase => more_verbose(control.level()).unwrap_or(self.initial),
+            LevelSignal::Reset => self.initial,
+        };
+        control.set_level(level)?;
+        Ok(level)
+    }
+
+    /// Handle all `signals` with [`Self::handle`].
+    ///
+    /// Return when `signals` ends.  Pass errors from handling a signal to
+    /// `on_error` and continue with the next signal.
+    pub fn run<C, I, E>(&self, control: &mut C, signals: I, mut on_error: E)
+    where
+        C: LogControl1,
+        I: IntoIterator<Item = LevelSignal>,
+        E: FnMut(LevelSignal, LogControl1Error),
+    {
+        for signal in signals {
+            if let Err(error) = self.handle(control, signal) {
+                on_error(signal, error);
+            }
+        }
+    }
+}
+
+/// Get the next more verbose level than `level`, if any.
+fn more_verbose(level: LogLevel) -> Option<LogLevel> {
+    match level {
+        LogLevel::Emerg => Some(LogLevel::Alert),
+        LogLevel::Alert => Some(LogLevel::Crit),
+        LogLevel::Crit => Some(LogLevel::Err),
+        LogLevel::Err => Some(LogLevel::Warning),
+        LogLevel::Warning => Some(LogLevel::Notice),
+        LogLevel::Notice => Some(LogLevel::Info),
+        LogLevel::Info => Some(LogLevel::Debug),
+        LogLevel::Debug => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::TestLogControl1;
+    use crate::{KnownLogTarget, LogControl1, LogLevel};
+
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    use signal_hook::consts::SIGUSR2;
+
+    use super::{level_signals, LevelSignal, SignalLevelToggle};
+
+    #[test]
+    fn increase_cycles_through_levels() {
+        let mut control = TestLogControl1::new(LogLevel::Notice, KnownLogTarget::Console);
+        let toggle = SignalLevelToggle::new(control.level());
+        let levels = (0..4)
+            .map(|_| toggle.handle(&mut control, LevelSignal::Increase).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            [
+                LogLevel::Info,
+                LogLevel::Debug,
+                LogLevel::Notice,
+                LogLevel::Info
+            ]
+        );
+    }
+
+    #[test]
+    fn reset_to_initial_level() {
+        let mut control = TestLogControl1::new(LogLevel::Warning, KnownLogTarget::Console);
+        let toggle = SignalLevelToggle::new(control.level());
+        toggle.handle(&mut control, LevelSignal::Increase).unwrap();
+        toggle.handle(&mut control, LevelSignal::Increase).unwrap();
+        assert_eq!(control.level(), LogLevel::Info);
+        toggle.handle(&mut control, LevelSignal::Reset).unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+    }
+
+    #[test]
+    fn run_handles_signals_from_channel() {
+        let mut control = TestLogControl1::new(LogLevel::Notice, KnownLogTarget::Console);
+        let toggle = SignalLevelToggle::new(control.level());
+        let (sender, receiver) = channel();
+        sender.send(LevelSignal::Increase).unwrap();
+        sender.send(LevelSignal::Increase).unwrap();
+        sender.send(LevelSignal::Reset).unwrap();
+        sender.send(LevelSignal::Increase).unwrap();
+        drop(sender);
+        toggle.run(&mut control, receiver, |signal, error| {
+            panic!("Unexpected error for {signal:?}: {error}")
+        });
+        assert_eq!(control.level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn level_signals_forwards_until_closed() {
+        let signals = level_signals().unwrap();
+        signal_hook::low_level::raise(SIGUSR2).unwrap();
+        assert_eq!(
+            signals.receiver.recv_timeout(Duration::from_secs(5)),
+            Ok(LevelSignal::Increase)
+        );
+        signals.close();
+        // The iterator ends once the forwarding thread is gone.
+        assert!(signals.iter().all(|signal| signal == LevelSignal::Increase));
+    }
+}
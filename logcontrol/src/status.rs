@@ -0,0 +1,111 @@
+//! A snapshot of the configuration of a log control.
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::LogLevel;
+
+/// The current configuration of a log control, as returned by [`crate::LogControl1::status`].
+///
+/// With the `serde` feature this struct implements `Serialize`, e.g. to render
+/// the logging configuration as JSON in a status endpoint of a service:
+///
+/// ```json
+/// {"level":"info","target":"journal","syslog_identifier":"foo","connected_to_journal":true}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LogControlStatus {
+    /// The current log level.
+    pub level: LogLevel,
+    /// The current log target.
+    pub target: String,
+    /// The syslog identifier.
+    pub syslog_identifier: String,
+    /// Whether stderr of the current process is connected to the systemd journal.
+    ///
+    /// Always `false` without the `std` feature.
+    pub connected_to_journal: bool,
+}
+
+impl LogControlStatus {
+    /// Describe the configuration in `Key=value` lines, like systemd does.
+    ///
+    /// Return one line each for the level, the target, and the syslog
+    /// identifier, keyed by the names of the corresponding properties of the
+    /// `LogControl1` interface, e.g. for a `--dump-config` flag:
+    ///
+    /// ```text
+    /// LogLevel=info
+    /// LogTarget=journal
+    /// SyslogIdentifier=foo
+    /// ```
+    ///
+    /// Every line ends with a newline.  The interface has no property for
+    /// [`Self::connected_to_journal`], so the lines omit it.
+    pub fn to_systemd_lines(&self) -> String {
+        format!(
+            "LogLevel={}\nLogTarget={}\nSyslogIdentifier={}\n",
+            self.level, self.target, self.syslog_identifier
+        )
+    }
+}
+
+/// Serialize the level as its name, e.g. `"info"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for LogLevel {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::TestLogControl1;
+    use crate::{KnownLogTarget, LogControl1, LogLevel};
+
+    #[test]
+    fn compare_snapshots() {
+        let mut control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+        let before = control.status();
+        assert_eq!(before, control.status());
+        assert_eq!(before, before.clone());
+
+        // Setting the current level again is a no-op
+        control.set_level(LogLevel::Info).unwrap();
+        assert_eq!(before, control.status());
+
+        control.set_level(LogLevel::Debug).unwrap();
+        let after = control.status();
+        assert_ne!(before, after);
+        assert_eq!(after.level, LogLevel::Debug);
+        assert_eq!(after.target, before.target);
+    }
+
+    #[test]
+    fn to_systemd_lines() {
+        let control = TestLogControl1::new(LogLevel::Warning, KnownLogTarget::Journal);
+        assert_eq!(
+            control.status().to_systemd_lines(),
+            "LogLevel=warning\nLogTarget=journal\nSyslogIdentifier=test\n"
+        );
+    }
+
+    #[cfg(all(feature = "std", feature = "serde"))]
+    #[test]
+    fn serialize_status() {
+        use crate::stderr_connected_to_journal;
+
+        let control = TestLogControl1::new(LogLevel::Notice, KnownLogTarget::Journal);
+        let json = serde_json::to_value(control.status()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "level": "notice",
+                "target": "journal",
+                "syslog_identifier": "test",
+                "connected_to_journal": stderr_connected_to_journal(),
+            })
+        );
+    }
+}
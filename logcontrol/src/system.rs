@@ -0,0 +1,297 @@
+//! Inspect the current process and its environment.
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::os::{fd::AsFd, linux::fs::MetadataExt, unix::net::UnixDatagram};
+use std::path::Path;
+
+use crate::{KnownLogTarget, LogLevel};
+
+#[cfg(doc)]
+use crate::LogControl1;
+
+/// Whether the current process is directly connected to the systemd journal.
+///
+/// You can use this function to implement [`KnownLogTarget::Auto`].
+///
+/// Return `true` if the device and inode numbers of the [`std::io::stderr`]
+/// file descriptor match the value of `$JOURNAL_STREAM` (see `systemd.exec(5)`).
+/// Otherwise, return `false`.
+pub fn stderr_connected_to_journal() -> bool {
+    std::io::stderr()
+        .as_fd()
+        .try_clone_to_owned()
+        .and_then(|fd| std::fs::File::from(fd).metadata())
+        .map(|metadata| format!("{}:{}", metadata.st_dev(), metadata.st_ino()))
+        .ok()
+        .and_then(|stderr| {
+            std::env::var_os("JOURNAL_STREAM").map(|s| s.to_string_lossy() == stderr.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// Whether the native socket of the systemd journal accepts messages.
+///
+/// You can use this function to implement [`LogControl1::is_healthy`] for
+/// [`KnownLogTarget::Journal`].
+///
+/// Return `true` if an empty datagram, which journald discards, can be sent
+/// to `/run/systemd/journal/socket`.  Otherwise, e.g. if journald is not running,
+/// or inside a container which has no access to the journald socket, return
+/// `false`.
+pub fn journal_reachable() -> bool {
+    UnixDatagram::unbound()
+        .and_then(|socket| socket.send_to(&[], "/run/systemd/journal/socket"))
+        .is_ok()
+}
+
+/// Whether the current process was started by systemd.
+///
+/// Return `true` if any of the following environment variables is set, which
+/// systemd sets for the processes it spawns (see `systemd.exec(5)`):
+///
+/// - `$INVOCATION_ID`, which systemd sets for all processes of a unit,
+/// - `$JOURNAL_STREAM`, if stdout or stderr are connected to the journal,
+/// - `$NOTIFY_SOCKET`, for services of `Type=notify`, or
+/// - `$LISTEN_PID`, for socket-activated services, if it matches the PID of
+///   the current process.
+///
+/// Unlike [`stderr_connected_to_journal`] this function does not check whether
+/// stderr actually goes to the journal, so it also returns `true` for services
+/// whose output systemd redirects elsewhere, e.g. with `StandardError=file:…`.
+///
+/// Note that these variables are inherited by child processes, so this function
+/// also returns `true` for processes spawned by a systemd service.
+pub fn running_under_systemd() -> bool {
+    running_under_systemd_with(|name| std::env::var_os(name), std::process::id())
+}
+
+fn running_under_systemd_with<F>(var_os: F, pid: u32) -> bool
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let is_set = |name| var_os(name).is_some_and(|value| !value.is_empty());
+    is_set("INVOCATION_ID")
+        || is_set("JOURNAL_STREAM")
+        || is_set("NOTIFY_SOCKET")
+        || var_os("LISTEN_PID").is_some_and(|value| value.to_str() == Some(&pid.to_string()))
+}
+
+/// Get the log level from `$SYSTEMD_LOG_LEVEL`.
+///
+/// systemd's own tools take their log level from this variable; services can
+/// use this function to mimic this behaviour.  Like systemd, accept a level
+/// name, e.g. `debug`, or a numeric syslog priority from `0` to `7`.
+///
+/// Return `None` if the variable is unset, or holds no valid level.
+pub fn log_level_from_systemd_env() -> Option<LogLevel> {
+    log_level_from_systemd_env_with(|name| std::env::var_os(name))
+}
+
+fn log_level_from_systemd_env_with<F>(var_os: F) -> Option<LogLevel>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let value = var_os("SYSTEMD_LOG_LEVEL")?;
+    let value = value.to_str()?.trim();
+    match value.parse::<u8>() {
+        Ok(priority) => LogLevel::try_from(priority).ok(),
+        Err(_) => LogLevel::try_from(value).ok(),
+    }
+}
+
+/// Get the log target from `$SYSTEMD_LOG_TARGET`.
+///
+/// systemd's own tools take their log target from this variable; services can
+/// use this function to mimic this behaviour.
+///
+/// Return `None` if the variable is unset, or holds no known log target.
+pub fn log_target_from_systemd_env() -> Option<KnownLogTarget> {
+    log_target_from_systemd_env_with(|name| std::env::var_os(name))
+}
+
+fn log_target_from_systemd_env_with<F>(var_os: F) -> Option<KnownLogTarget>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let value = var_os("SYSTEMD_LOG_TARGET")?;
+    KnownLogTarget::try_from(value.to_str()?.trim()).ok()
+}
+
+/// Determine the syslog identifier for this process.
+///
+/// This function obtains the syslog identifier from the file name of the
+/// current executable, per [`std::env::current_exe`].
+///
+/// As such, it's a comparatively expensive function to call; implementations of
+/// [`LogControl1`] should avoid calling it for every invocation, but instead
+/// determine the identifier once upon construction and store it.
+///
+/// If it fails to determine the syslog identifier, i.e. when `current_exe`
+/// returns an error, this function falls back to the empty string.
+///
+/// This function replaces invalid UTF-8 in the file name with `U+FFFD`;
+/// see [`syslog_identifier_os`] to obtain the original file name.
+pub fn syslog_identifier() -> String {
+    syslog_identifier_os().to_string_lossy().into_owned()
+}
+
+/// Determine the syslog identifier for this process, without lossy conversion.
+///
+/// Like [`syslog_identifier`], but return the file name of the current
+/// executable as is.  The journal accepts arbitrary bytes in fields, so
+/// journal loggers can use this identifier to preserve executable names which
+/// are not valid UTF-8.
+///
+/// If it fails to determine the syslog identifier this function falls back to
+/// the empty string.
+pub fn syslog_identifier_os() -> OsString {
+    std::env::current_exe()
+        .ok()
+        .as_deref()
+        .map(syslog_identifier_from_path)
+        // If we fail to get the name of the current executable fall back to an empty string.
+        .unwrap_or_default()
+}
+
+fn syslog_identifier_from_path(path: &Path) -> OsString {
+    path.file_name()
+        .map(OsStr::to_os_string)
+        .unwrap_or_default()
+}
+
+/// Get the ID of the current boot.
+///
+/// Read the boot ID from `/proc/sys/kernel/random/boot_id`, and return it in
+/// the format of the `_BOOT_ID` journal field, i.e. as 32 lowercase hex digits
+/// without dashes.  journald adds `_BOOT_ID` to all entries on its own, but
+/// other sinks may want to add the boot ID to correlate records across reboots,
+/// e.g. as a static field of a journal layer which sends to a custom sink.
+///
+/// Return `None` if the file cannot be read, e.g. if `/proc` is not mounted,
+/// or does not contain a valid boot ID.
+pub fn boot_id() -> Option<String> {
+    boot_id_with(|path| std::fs::read_to_string(path))
+}
+
+fn boot_id_with<F>(read_to_string: F) -> Option<String>
+where
+    F: FnOnce(&Path) -> io::Result<String>,
+{
+    let contents = read_to_string(Path::new("/proc/sys/kernel/random/boot_id")).ok()?;
+    let id = contents
+        .trim()
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<String>();
+    (id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use crate::{KnownLogTarget, LogLevel};
+
+    use super::{
+        boot_id_with, log_level_from_systemd_env_with, log_target_from_systemd_env_with,
+        running_under_systemd_with, syslog_identifier_from_path,
+    };
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    }
+
+    #[test]
+    fn running_under_systemd_without_markers() {
+        assert!(!running_under_systemd_with(env(&[]), 42));
+        assert!(!running_under_systemd_with(env(&[("HOME", "/root")]), 42));
+        assert!(!running_under_systemd_with(
+            env(&[("INVOCATION_ID", "")]),
+            42
+        ));
+    }
+
+    #[test]
+    fn running_under_systemd_with_markers() {
+        for name in ["INVOCATION_ID", "JOURNAL_STREAM", "NOTIFY_SOCKET"] {
+            assert!(running_under_systemd_with(env(&[(name, "foo")]), 42));
+        }
+    }
+
+    #[test]
+    fn running_under_systemd_listen_pid() {
+        assert!(running_under_systemd_with(env(&[("LISTEN_PID", "42")]), 42));
+        assert!(!running_under_systemd_with(
+            env(&[("LISTEN_PID", "43")]),
+            42
+        ));
+    }
+
+    #[test]
+    fn log_level_from_systemd_env_names_and_numbers() {
+        let level = |value| log_level_from_systemd_env_with(env(&[("SYSTEMD_LOG_LEVEL", value)]));
+        assert_eq!(level("debug"), Some(LogLevel::Debug));
+        assert_eq!(level(" warning\n"), Some(LogLevel::Warning));
+        assert_eq!(level("0"), Some(LogLevel::Emerg));
+        assert_eq!(level("3"), Some(LogLevel::Err));
+        assert_eq!(level("7"), Some(LogLevel::Debug));
+        assert_eq!(level("8"), None);
+        assert_eq!(level("-1"), None);
+        assert_eq!(level("verbose"), None);
+        assert_eq!(level(""), None);
+        assert_eq!(log_level_from_systemd_env_with(env(&[])), None);
+    }
+
+    #[test]
+    fn log_target_from_systemd_env_known_targets() {
+        let target =
+            |value| log_target_from_systemd_env_with(env(&[("SYSTEMD_LOG_TARGET", value)]));
+        assert_eq!(target("journal"), Some(KnownLogTarget::Journal));
+        assert_eq!(target("console"), Some(KnownLogTarget::Console));
+        assert_eq!(target("kmsg"), Some(KnownLogTarget::Kmsg));
+        assert_eq!(target("auto "), Some(KnownLogTarget::Auto));
+        assert_eq!(target("otlp"), None);
+        assert_eq!(log_target_from_systemd_env_with(env(&[])), None);
+    }
+
+    #[test]
+    fn syslog_identifier_from_non_utf8_path() {
+        let path = Path::new(OsStr::from_bytes(b"/usr/bin/foo\xffbar"));
+        let identifier = syslog_identifier_from_path(path);
+        assert_eq!(identifier.as_bytes(), b"foo\xffbar");
+        assert_eq!(identifier.to_string_lossy(), "foo\u{FFFD}bar");
+        assert_eq!(syslog_identifier_from_path(Path::new("/")), OsString::new());
+    }
+
+    #[test]
+    fn boot_id_present() {
+        let boot_id = boot_id_with(|path| {
+            assert_eq!(path, Path::new("/proc/sys/kernel/random/boot_id"));
+            Ok("5D1F4C52-8C69-4A2B-9E4B-0E7F3B2C1A90\n".to_string())
+        });
+        assert_eq!(boot_id.as_deref(), Some("5d1f4c528c694a2b9e4b0e7f3b2c1a90"));
+    }
+
+    #[test]
+    fn boot_id_absent_or_invalid() {
+        assert_eq!(
+            boot_id_with(|_| Err(io::Error::from(io::ErrorKind::NotFound))),
+            None
+        );
+        assert_eq!(boot_id_with(|_| Ok(String::new())), None);
+        assert_eq!(boot_id_with(|_| Ok("not-a-boot-id".to_string())), None);
+        assert_eq!(
+            boot_id_with(|_| Ok("5d1f4c52-8c69-4a2b-9e4b-0e7f3b2c1a9z".to_string())),
+            None
+        );
+    }
+}
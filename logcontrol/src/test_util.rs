@@ -0,0 +1,108 @@
+//! Utilities to test log control frontends and helpers.
+
+use alloc::string::ToString;
+
+use crate::{KnownLogTarget, LogControl1, LogControl1Error, LogControlCapabilities, LogLevel};
+
+/// A [`LogControl1`] which only records the level and target.
+///
+/// Supports all levels, and the [`KnownLogTarget::Console`],
+/// [`KnownLogTarget::Journal`], [`KnownLogTarget::Null`] and
+/// [`KnownLogTarget::Auto`] targets; the latter always selects the console.
+/// The syslog identifier is `test`.  The control is healthy unless changed
+/// with [`TestLogControl1::with_healthy`], and supports muting.  The control
+/// counts calls to [`LogControl1::reopen`], see [`TestLogControl1::reopen_count`],
+/// and advertises all [`LogControl1::capabilities`].
+#[derive(Debug, Clone)]
+pub struct TestLogControl1 {
+    level: LogLevel,
+    target: KnownLogTarget,
+    healthy: bool,
+    muted: bool,
+    reopen_count: usize,
+}
+
+impl TestLogControl1 {
+    /// Create a new log control with the given `level` and `target`.
+    pub fn new(level: LogLevel, target: KnownLogTarget) -> Self {
+        Self {
+            level,
+            target,
+            healthy: true,
+            muted: false,
+            reopen_count: 0,
+        }
+    }
+
+    /// Report the given `healthy` state from [`LogControl1::is_healthy`].
+    pub fn with_healthy(mut self, healthy: bool) -> Self {
+        self.healthy = healthy;
+        self
+    }
+
+    /// Get the number of calls to [`LogControl1::reopen`].
+    pub fn reopen_count(&self) -> usize {
+        self.reopen_count
+    }
+}
+
+impl LogControl1 for TestLogControl1 {
+    fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.level = level;
+        Ok(())
+    }
+
+    fn target(&self) -> &str {
+        self.target.as_str()
+    }
+
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        self.target = match KnownLogTarget::try_from(target.as_ref())? {
+            KnownLogTarget::Auto => KnownLogTarget::Console,
+            target @ (KnownLogTarget::Console | KnownLogTarget::Journal | KnownLogTarget::Null) => {
+                target
+            }
+            other => {
+                return Err(LogControl1Error::UnsupportedLogTarget(
+                    other.as_str().to_string(),
+                ))
+            }
+        };
+        Ok(())
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        "test"
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+
+    fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        self.muted = muted;
+        Ok(())
+    }
+
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        self.reopen_count += 1;
+        Ok(())
+    }
+
+    /// Support all capabilities.
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities {
+            mute: true,
+            reopen: true,
+            health: true,
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! A [`LogControl1`] implementation for OpenTelemetry logs.
+//!
+//! [`OtelLogControl1`] provides a [`LogControl1`] implementation on top of
+//! [`tracing`], which switches between a console layer and the
+//! [`OpenTelemetryTracingBridge`] to send events to an OpenTelemetry logs
+//! pipeline, e.g. an OTLP exporter.
+//!
+//! Besides the standard `console` and `null` targets the control supports a
+//! custom [`OTLP_TARGET`], i.e. `otlp`, which sends events to the logger
+//! provider given to [`OtelLogControl1::new`]:
+//!
+//! ```console
+//! # systemctl service-log-target my-service.service otlp
+//! ```
+//!
+//! The level maps to a tracing level like in [`logcontrol_tracing`]; the
+//! bridge then exports events with the corresponding OpenTelemetry severity,
+//! see [`to_severity`].
+
+#![deny(warnings, clippy::all, missing_docs)]
+#![forbid(unsafe_code)]
+
+use logcontrol::{KnownLogTarget, LogControl1, LogControl1Error, LogControlCapabilities, LogLevel};
+use logcontrol_tracing::{from_log_level, ReloadableLevel};
+use opentelemetry::logs::{LoggerProvider, Severity};
+use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+use tracing::Subscriber;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{fmt, reload, Layer};
+
+pub use logcontrol;
+
+/// The custom log target to send events to OpenTelemetry.
+pub const OTLP_TARGET: &str = "otlp";
+
+/// Log targets supported by [`OtelLogControl1`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtelLogTarget {
+    /// Log to stdout, see [`KnownLogTarget::Console`].
+    Console,
+    /// Send events to OpenTelemetry, see [`OTLP_TARGET`].
+    Otlp,
+    /// Disable logging, see [`KnownLogTarget::Null`].
+    Null,
+}
+
+impl OtelLogTarget {
+    /// Get the name of this target as used in the log control interface.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OtelLogTarget::Console => KnownLogTarget::Console.as_str(),
+            OtelLogTarget::Otlp => OTLP_TARGET,
+            OtelLogTarget::Null => KnownLogTarget::Null.as_str(),
+        }
+    }
+}
+
+fn from_known_log_target(target: KnownLogTarget) -> Result<OtelLogTarget, LogControl1Error> {
+    match target {
+        // OpenTelemetry needs explicit configuration, so never select it automatically
+        KnownLogTarget::Auto | KnownLogTarget::Console => Ok(OtelLogTarget::Console),
+        KnownLogTarget::Null => Ok(OtelLogTarget::Null),
+        other => Err(LogControl1Error::UnsupportedLogTarget(
+            other.as_str().to_string(),
+        )),
+    }
+}
+
+fn parse_log_target(target: &str) -> Result<OtelLogTarget, LogControl1Error> {
+    // systemctl passes targets through verbatim, so ignore surrounding whitespace
+    let target = target.trim();
+    if target == OTLP_TARGET {
+        Ok(OtelLogTarget::Otlp)
+    } else {
+        from_known_log_target(KnownLogTarget::try_from(target)?)
+    }
+}
+
+/// Convert [`logcontrol::LogLevel`] to the OpenTelemetry [`Severity`] of exported events.
+///
+/// Map the level to a [`tracing::Level`] with [`from_log_level`], and then
+/// to the severity which [`OpenTelemetryTracingBridge`] uses for events at
+/// that level.
+///
+/// Return an error if the systemd log level is not supported, i.e. does not map to a
+/// corresponding [`tracing::Level`].
+pub fn to_severity(level: LogLevel) -> Result<Severity, LogControl1Error> {
+    Ok(match from_log_level(level)? {
+        tracing::Level::ERROR => Severity::Error,
+        tracing::Level::WARN => Severity::Warn,
+        tracing::Level::INFO => Severity::Info,
+        tracing::Level::DEBUG => Severity::Debug,
+        tracing::Level::TRACE => Severity::Trace,
+    })
+}
+
+/// Convert [`tracing::Level`] to [`logcontrol::LogLevel`].
+fn to_log_level(level: tracing::Level) -> LogLevel {
+    match level {
+        tracing::Level::ERROR => LogLevel::Err,
+        tracing::Level::WARN => LogLevel::Warning,
+        tracing::Level::INFO => LogLevel::Notice,
+        tracing::Level::DEBUG => LogLevel::Info,
+        tracing::Level::TRACE => LogLevel::Debug,
+    }
+}
+
+/// The type of the layer that implements the log target.
+pub type OtelTargetLayer<P, S> = Layered<
+    Option<fmt::Layer<S>>,
+    Option<OpenTelemetryTracingBridge<P, <P as LoggerProvider>::Logger>>,
+    S,
+>;
+
+/// The final type for the layer that implements the log control interface.
+///
+/// The level filter is the outermost layer, so that it always determines the
+/// maximum level of the subscriber, regardless of the current log target.
+pub type OtelLogControl1Layer<P, S> =
+    Layered<reload::Layer<LevelFilter, S>, reload::Layer<OtelTargetLayer<P, S>, S>, S>;
+
+fn make_target_layer<P, S>(provider: &P, target: OtelLogTarget) -> OtelTargetLayer<P, S>
+where
+    P: LoggerProvider + Send + Sync + 'static,
+    P::Logger: Send + Sync + 'static,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let console = (target == OtelLogTarget::Console).then(fmt::layer);
+    let otlp = (target == OtelLogTarget::Otlp).then(|| OpenTelemetryTracingBridge::new(provider));
+    Layer::and_then(otlp, console)
+}
+
+/// A [`LogControl1`] implementation for OpenTelemetry logs.
+///
+/// This implementation creates a tracing layer which sends events either to
+/// the console, with the default [`fmt::layer`], or to an OpenTelemetry
+/// [`LoggerProvider`], e.g. an `opentelemetry_sdk::logs::SdkLoggerProvider`
+/// with an OTLP exporter.  See [`OtelLogTarget`] for supported targets;
+/// [`KnownLogTarget::Auto`] always selects the console.
+///
+/// Like [`logcontrol_tracing::TracingLogControl1`] the level and the target
+/// are independent of each other, and each change reloads only the
+/// respective layer.
+pub struct OtelLogControl1<P, S>
+where
+    P: LoggerProvider + Send + Sync + 'static,
+    P::Logger: Send + Sync + 'static,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// The logger provider for the OTLP target.
+    provider: P,
+    /// The syslog identifier.
+    syslog_identifier: String,
+    /// The last level set through this control.
+    level: tracing::Level,
+    /// The current target active in the target layer.
+    target: OtelLogTarget,
+    /// A handle to reload the level layer in order to change the level.
+    level_handle: reload::Handle<LevelFilter, S>,
+    /// A handle to reload the target layer in order to change the target.
+    target_handle: reload::Handle<OtelTargetLayer<P, S>, S>,
+}
+
+impl<P, S> OtelLogControl1<P, S>
+where
+    P: LoggerProvider + Send + Sync + 'static,
+    P::Logger: Send + Sync + 'static,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Create a new layer controlled through the log interface.
+    ///
+    /// `provider` creates loggers for [`OtelLogTarget::Otlp`]; the control
+    /// creates a new bridge from `provider` whenever it switches to this
+    /// target.  `target` and `level` denote the initial target and level.
+    ///
+    /// OpenTelemetry identifies services by resource attributes of
+    /// `provider`, so this control only reports `syslog_identifier` through
+    /// [`LogControl1::syslog_identifier`].
+    ///
+    /// ```rust
+    /// use logcontrol_otel::{OtelLogControl1, OtelLogTarget};
+    /// use opentelemetry_sdk::logs::SdkLoggerProvider;
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// // Add an OTLP exporter to the provider
+    /// let provider = SdkLoggerProvider::builder().build();
+    /// let (control, layer) = OtelLogControl1::new(
+    ///     provider,
+    ///     "example".to_string(),
+    ///     OtelLogTarget::Otlp,
+    ///     tracing::Level::INFO,
+    /// );
+    /// let subscriber = tracing_subscriber::Registry::default().with(layer);
+    /// ```
+    pub fn new(
+        provider: P,
+        syslog_identifier: String,
+        target: OtelLogTarget,
+        level: tracing::Level,
+    ) -> (Self, OtelLogControl1Layer<P, S>) {
+        let (target_layer, target_handle) =
+            reload::Layer::new(make_target_layer(&provider, target));
+        let (level_layer, level_handle) = reload::Layer::new(LevelFilter::from_level(level));
+        let control = Self {
+            provider,
+            syslog_identifier,
+            level,
+            target,
+            level_handle,
+            target_handle,
+        };
+        (control, target_layer.and_then(level_layer))
+    }
+
+    /// Get the least severe [`Severity`] of events which currently get exported.
+    pub fn severity_threshold(&self) -> Severity {
+        // Our level is always supported
+        to_severity(self.level()).unwrap_or(Severity::Error)
+    }
+}
+
+impl<P, S> LogControl1 for OtelLogControl1<P, S>
+where
+    P: LoggerProvider + Send + Sync + 'static,
+    P::Logger: Send + Sync + 'static,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Get the level of the level layer.
+    ///
+    /// The level may also change through [`ReloadableLevel::set_reload_level`],
+    /// so ask the level layer for its current level.  If the layer was dropped
+    /// return the last level set through this control.
+    fn level(&self) -> LogLevel {
+        let level = self
+            .level_handle
+            .with_current(|filter| filter.into_level())
+            .ok()
+            .flatten()
+            .unwrap_or(self.level);
+        to_log_level(level)
+    }
+
+    /// Change the level of the level layer.
+    ///
+    /// Check that `level` is supported before reloading the level layer, so
+    /// that an unsupported level leaves the level untouched.
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        let new_level = from_log_level(level)?;
+        self.level_handle.set_reload_level(level)?;
+        self.level = new_level;
+        Ok(())
+    }
+
+    fn target(&self) -> &str {
+        self.target.as_str()
+    }
+
+    fn set_target<T: AsRef<str>>(&mut self, target: T) -> Result<(), LogControl1Error> {
+        let new_target = parse_log_target(target.as_ref())?;
+        self.target_handle
+            .reload(make_target_layer(&self.provider, new_target))
+            .map_err(|error| {
+                LogControl1Error::reload_failed(
+                    format!(
+                        "Failed to reload target layer to switch to log target {}",
+                        target.as_ref()
+                    ),
+                    error,
+                )
+            })?;
+        self.target = new_target;
+        Ok(())
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        &self.syslog_identifier
+    }
+
+    /// Change the level and the target together.
+    ///
+    /// Check that `level` is supported before changing the target, so that
+    /// an unsupported level leaves the target untouched.
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        from_log_level(level)?;
+        self.set_target(target)?;
+        self.set_level(level)
+    }
+
+    /// Whether the layers still exist.
+    fn is_healthy(&self) -> bool {
+        self.level_handle.with_current(|_| ()).is_ok()
+            && self.target_handle.with_current(|_| ()).is_ok()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "opentelemetry"
+    }
+
+    /// Support health checks only.
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities {
+            health: true,
+            ..LogControlCapabilities::default()
+        }
+    }
+}
+
+/// Reload the level of the level layer through a shared reference.
+///
+/// [`LogControl1::level`] reflects levels set through this trait.
+impl<P, S> ReloadableLevel for OtelLogControl1<P, S>
+where
+    P: LoggerProvider + Send + Sync + 'static,
+    P::Logger: Send + Sync + 'static,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn set_reload_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.level_handle.set_reload_level(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logcontrol::{LogControl1, LogControl1Error, LogLevel};
+    use opentelemetry::logs::Severity;
+    use opentelemetry_sdk::logs::{InMemoryLogExporter, SdkLoggerProvider};
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::Registry;
+
+    use crate::{to_severity, OtelLogControl1, OtelLogTarget};
+
+    fn exported_severities(exporter: &InMemoryLogExporter) -> Vec<Severity> {
+        exporter
+            .get_emitted_logs()
+            .unwrap()
+            .into_iter()
+            .filter_map(|log| log.record.severity_number())
+            .collect()
+    }
+
+    #[test]
+    fn level_changes_severity_threshold() {
+        let exporter = InMemoryLogExporter::default();
+        let provider = SdkLoggerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let (mut control, layer) = OtelLogControl1::new(
+            provider,
+            "test".to_string(),
+            OtelLogTarget::Otlp,
+            tracing::Level::WARN,
+        );
+        assert_eq!(control.target(), "otlp");
+        assert_eq!(control.severity_threshold(), Severity::Warn);
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::error!("error");
+            tracing::warn!("warn");
+            tracing::info!("info");
+            control.set_level(LogLevel::Info).unwrap();
+            assert_eq!(control.severity_threshold(), Severity::Debug);
+            tracing::debug!("debug");
+            tracing::trace!("trace");
+        });
+        assert_eq!(
+            exported_severities(&exporter),
+            [Severity::Error, Severity::Warn, Severity::Debug]
+        );
+    }
+
+    #[test]
+    fn unsupported_level_leaves_level_untouched() {
+        let (mut control, layer) = OtelLogControl1::<_, Registry>::new(
+            SdkLoggerProvider::builder().build(),
+            "test".to_string(),
+            OtelLogTarget::Null,
+            tracing::Level::WARN,
+        );
+        let result = control.set_level(LogLevel::Crit);
+        assert!(
+            matches!(
+                result,
+                Err(LogControl1Error::UnsupportedLogLevel(LogLevel::Crit))
+            ),
+            "{result:?}"
+        );
+        assert_eq!(control.level(), LogLevel::Warning);
+        assert_eq!(control.severity_threshold(), Severity::Warn);
+        // Without the layer the control falls back to the level it stored last
+        drop(layer);
+        assert_eq!(control.level(), LogLevel::Warning);
+    }
+
+    #[test]
+    fn backend_name() {
+        let (control, _layer) = OtelLogControl1::<_, Registry>::new(
+            SdkLoggerProvider::builder().build(),
+            "test".to_string(),
+            OtelLogTarget::Null,
+            tracing::Level::INFO,
+        );
+        assert_eq!(control.backend_name(), "opentelemetry");
+    }
+
+    #[test]
+    fn capabilities() {
+        let (control, _layer) = OtelLogControl1::<_, Registry>::new(
+            SdkLoggerProvider::builder().build(),
+            "test".to_string(),
+            OtelLogTarget::Null,
+            tracing::Level::INFO,
+        );
+        assert_eq!(control.capabilities().names(), ["health"]);
+    }
+
+    #[test]
+    fn switch_targets() {
+        let exporter = InMemoryLogExporter::default();
+        let provider = SdkLoggerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let (mut control, layer) = OtelLogControl1::new(
+            provider,
+            "test".to_string(),
+            OtelLogTarget::Null,
+            tracing::Level::INFO,
+        );
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("null");
+            control.set_target(" otlp\n").unwrap();
+            tracing::info!("otlp");
+            control.set_target("auto").unwrap();
+            assert_eq!(control.target(), "console");
+            assert!(matches!(
+                control.set_target("journal"),
+                Err(LogControl1Error::UnsupportedLogTarget(_))
+            ));
+            assert_eq!(control.target(), "console");
+        });
+        assert_eq!(exported_severities(&exporter), [Severity::Info]);
+    }
+
+    #[test]
+    fn severities() {
+        assert!(matches!(
+            to_severity(LogLevel::Crit),
+            Err(LogControl1Error::UnsupportedLogLevel(LogLevel::Crit))
+        ));
+        assert_eq!(to_severity(LogLevel::Err).unwrap(), Severity::Error);
+        assert_eq!(to_severity(LogLevel::Notice).unwrap(), Severity::Info);
+        assert_eq!(to_severity(LogLevel::Debug).unwrap(), Severity::Trace);
+    }
+}
@@ -0,0 +1,597 @@
+//! A [`LogControl1`] implementation for [`slog`].
+//!
+//! [`SlogLogControl1`] provides a [`LogControl1`] implementation on top of
+//! [`slog`] which dynamically switches drains and levels depending on the
+//! target and level selected over the log control interface.
+//!
+//! It uses a [`SlogDrainFactory`] implementation to create the actual drains
+//! each time the log target is changed.  This crates provides _no_ default
+//! implementation of this trait; users have to provide an implementation on
+//! their own.  This avoids a dependency on any specific drain implementation
+//! for the `console` and `journal` targets.
+//!
+//! When created [`SlogLogControl1`] additionally returns a [`ControlledDrain`]
+//! which needs to be used as root drain of the application logger, for log
+//! control to have any effect.
+//!
+//! ```rust
+//! use logcontrol::*;
+//! use logcontrol_slog::*;
+//!
+//! struct Factory;
+//!
+//! impl SlogDrainFactory for Factory {
+//!     fn create_console_drain(&self) -> Result<BoxedDrain, LogControl1Error> {
+//!         Ok(Box::new(slog::Discard))
+//!     }
+//!
+//!     fn create_journal_drain(
+//!         &self,
+//!         _syslog_identifier: String,
+//!     ) -> Result<BoxedDrain, LogControl1Error> {
+//!         Ok(Box::new(slog::Discard))
+//!     }
+//! }
+//!
+//! let (control, drain) = SlogLogControl1::new_auto(Factory, slog::Level::Info).unwrap();
+//! let logger = slog::Logger::root(drain, slog::o!());
+//! slog::info!(logger, "Hello");
+//! // Then register `control` over DBus, e.g. via `logcontrol_zbus::LogControl1`.
+//! ```
+
+#![deny(warnings, clippy::all, missing_docs)]
+#![forbid(unsafe_code)]
+
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, RwLock, Weak};
+
+use logcontrol::{KnownLogTarget, LogControl1, LogControl1Error, LogControlCapabilities, LogLevel};
+use slog::{Drain, Never, OwnedKVList, Record, SendSyncRefUnwindSafeDrain};
+
+pub use logcontrol;
+pub use logcontrol::stderr_connected_to_journal;
+pub use logcontrol::syslog_identifier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlogLogTarget {
+    Console,
+    Journal,
+    Null,
+}
+
+impl From<SlogLogTarget> for KnownLogTarget {
+    fn from(value: SlogLogTarget) -> Self {
+        match value {
+            SlogLogTarget::Console => KnownLogTarget::Console,
+            SlogLogTarget::Journal => KnownLogTarget::Journal,
+            SlogLogTarget::Null => KnownLogTarget::Null,
+        }
+    }
+}
+
+fn from_known_log_target(
+    target: KnownLogTarget,
+    connected_to_journal: bool,
+) -> Result<SlogLogTarget, LogControl1Error> {
+    let target = match target {
+        KnownLogTarget::Auto => logcontrol::resolve_auto(connected_to_journal),
+        other => other,
+    };
+    match target {
+        KnownLogTarget::Console => Ok(SlogLogTarget::Console),
+        KnownLogTarget::Journal => Ok(SlogLogTarget::Journal),
+        KnownLogTarget::Null => Ok(SlogLogTarget::Null),
+        other => Err(LogControl1Error::UnsupportedLogTarget(
+            other.as_str().to_string(),
+        )),
+    }
+}
+
+/// Convert [`logcontrol::LogLevel`] to [`slog::Level`].
+///
+/// Return an error if the systemd log level is not supported, i.e. does not map to a
+/// corresponding [`slog::Level`].
+pub fn from_log_level(level: LogLevel) -> Result<slog::Level, LogControl1Error> {
+    match level {
+        LogLevel::Crit => Ok(slog::Level::Critical),
+        LogLevel::Err => Ok(slog::Level::Error),
+        LogLevel::Warning => Ok(slog::Level::Warning),
+        LogLevel::Notice => Ok(slog::Level::Info),
+        LogLevel::Info => Ok(slog::Level::Debug),
+        LogLevel::Debug => Ok(slog::Level::Trace),
+        unsupported => Err(LogControl1Error::UnsupportedLogLevel(unsupported)),
+    }
+}
+
+/// Convert [`slog::Level`] to [`logcontrol::LogLevel`].
+fn to_log_level(level: slog::Level) -> LogLevel {
+    match level {
+        slog::Level::Critical => LogLevel::Crit,
+        slog::Level::Error => LogLevel::Err,
+        slog::Level::Warning => LogLevel::Warning,
+        slog::Level::Info => LogLevel::Notice,
+        slog::Level::Debug => LogLevel::Info,
+        slog::Level::Trace => LogLevel::Debug,
+    }
+}
+
+/// A boxed drain as created by a [`SlogDrainFactory`].
+pub type BoxedDrain = Box<dyn SendSyncRefUnwindSafeDrain<Ok = (), Err = Never>>;
+
+/// A factory for slog drains.
+pub trait SlogDrainFactory {
+    /// Create a drain for the console log target.
+    fn create_console_drain(&self) -> Result<BoxedDrain, LogControl1Error>;
+
+    /// Create a drain for the journal log target.
+    ///
+    /// The implementation should use `syslog_identifier` for the corresponding journal field.
+    fn create_journal_drain(
+        &self,
+        syslog_identifier: String,
+    ) -> Result<BoxedDrain, LogControl1Error>;
+}
+
+fn create_drain<F: SlogDrainFactory>(
+    target: SlogLogTarget,
+    factory: &F,
+    syslog_identifier: &str,
+) -> Result<BoxedDrain, LogControl1Error> {
+    match target {
+        SlogLogTarget::Console => factory.create_console_drain(),
+        SlogLogTarget::Journal => factory.create_journal_drain(syslog_identifier.to_string()),
+        SlogLogTarget::Null => Ok(Box::new(slog::Discard)),
+    }
+}
+
+/// The level and the drain currently active in a [`ControlledDrain`].
+struct DrainState {
+    level: slog::Level,
+    drain: BoxedDrain,
+}
+
+/// A drain controlled through a [`SlogLogControl1`].
+///
+/// Filters records by the level selected through the log control interface, and
+/// forwards all remaining records to the drain for the current log target.
+pub struct ControlledDrain {
+    state: Arc<RwLock<DrainState>>,
+}
+
+impl Drain for ControlledDrain {
+    type Ok = ();
+    type Err = Never;
+
+    /// Forward a log `record` to the current drain if it passes the level filter.
+    ///
+    /// If the [`RwLock`] protecting the current drain is poisoned do nothing,
+    /// because we can't trust that the drain is valid if a panic occurred
+    /// while it was modified.  The `record` is likely lost in this case.
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<(), Never> {
+        if let Ok(state) = self.state.read() {
+            if record.level().is_at_least(state.level) {
+                state.drain.log(record, values)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the given `level` passes the level filter and the current drain is enabled.
+    fn is_enabled(&self, level: slog::Level) -> bool {
+        self.state
+            .read()
+            .is_ok_and(|state| level.is_at_least(state.level) && state.drain.is_enabled(level))
+    }
+}
+
+/// An error which occurred while changing the state of a [`ControlledDrain`].
+///
+/// [`SlogLogControl1`] reports this error as source of
+/// [`LogControl1Error::ReloadFailed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadError {
+    /// The controlled drain was dropped meanwhile.
+    Gone,
+    /// The lock protecting the state of the controlled drain is poisoned.
+    Poisoned,
+}
+
+impl Display for ReloadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReloadError::Gone => write!(f, "Controlled drain was dropped"),
+            ReloadError::Poisoned => write!(f, "Lock poisoned"),
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {}
+
+/// A [`LogControl1`] implementation for [`slog`].
+///
+/// This implementation creates a [`ControlledDrain`] whose level and
+/// underlying drain can be dynamically reconfigured through the [`LogControl1`]
+/// interface.
+///
+/// Currently, this implementation only supports the following [`KnownLogTarget`]s:
+///
+/// - [`KnownLogTarget::Console`]
+/// - [`KnownLogTarget::Journal`]
+/// - [`KnownLogTarget::Null`]
+/// - [`KnownLogTarget::Auto`]
+///
+/// Any other target fails with [`LogControl1Error::UnsupportedLogTarget`].
+pub struct SlogLogControl1<F: SlogDrainFactory> {
+    /// The state of the controlled drain.
+    state: Weak<RwLock<DrainState>>,
+    /// The factory to create drains with when switching targets.
+    factory: F,
+    /// Whether the current process is connnected to the systemd journal.
+    connected_to_journal: bool,
+    /// The syslog identifier used for logging.
+    syslog_identifier: String,
+    /// The current level active in the controlled drain.
+    level: LogLevel,
+    /// The current target active in the controlled drain.
+    target: SlogLogTarget,
+}
+
+impl<F: SlogDrainFactory> SlogLogControl1<F> {
+    /// Create a new drain which can be controlled through the log control interface.
+    ///
+    /// `factory` creates the inner drain for the selected `target` which denotes
+    /// the initial log target.  The `factory` is invoked whenever the log target
+    /// is changed, to create a new drain for the corresponding target.  See
+    /// [`SlogLogControl1`] for supported log targets.
+    ///
+    /// `connected_to_journal` indicates whether this process is connected to the systemd
+    /// journal. Set to `true` to make [`KnownLogTarget::Auto`] use [`KnownLogTarget::Journal`],
+    /// otherwise it uses [`KnownLogTarget::Console`].
+    ///
+    /// `level` denotes the default log level to start with.
+    ///
+    /// `syslog_identifier` is passed to [`SlogDrainFactory::create_journal_drain`]
+    /// for use as `SYSLOG_IDENTIFIER` journal field.
+    ///
+    /// Returns an error if `target` is not supported, of if creating a drain fails.
+    pub fn new(
+        factory: F,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        target: KnownLogTarget,
+        level: slog::Level,
+    ) -> Result<(Self, ControlledDrain), LogControl1Error> {
+        let slog_target = from_known_log_target(target, connected_to_journal)?;
+        let drain = create_drain(slog_target, &factory, &syslog_identifier)?;
+        let state = Arc::new(RwLock::new(DrainState { level, drain }));
+        let control = Self {
+            state: Arc::downgrade(&state),
+            factory,
+            connected_to_journal,
+            syslog_identifier,
+            level: to_log_level(level),
+            target: slog_target,
+        };
+        Ok((control, ControlledDrain { state }))
+    }
+
+    /// Create a new drain which can be controlled through the log control interface, using automatic defaults.
+    ///
+    /// Use [`logcontrol::syslog_identifier()`] as the syslog identifier, and
+    /// determine the initial log target automatically according to
+    /// [`logcontrol::stderr_connected_to_journal()`].
+    ///
+    /// `level` denotes the initial level; for `factory` and returned errors,
+    ///  see [`Self::new`].
+    ///
+    /// Like systemd's own tools, take the initial level and target from
+    /// `$SYSTEMD_LOG_LEVEL` and `$SYSTEMD_LOG_TARGET` if set, see
+    /// [`logcontrol::log_level_from_systemd_env()`] and
+    /// [`logcontrol::log_target_from_systemd_env()`].  Ignore unsupported
+    /// targets in `$SYSTEMD_LOG_TARGET`, and use the most severe supported
+    /// level for unsupported levels in `$SYSTEMD_LOG_LEVEL`.
+    pub fn new_auto(
+        factory: F,
+        level: slog::Level,
+    ) -> Result<(Self, ControlledDrain), LogControl1Error> {
+        let connected_to_journal = logcontrol::stderr_connected_to_journal();
+        let level = logcontrol::log_level_from_systemd_env().map_or(level, |level| {
+            from_log_level(level).unwrap_or(slog::Level::Critical)
+        });
+        let target = logcontrol::log_target_from_systemd_env()
+            .filter(|target| from_known_log_target(*target, connected_to_journal).is_ok())
+            .unwrap_or(KnownLogTarget::Auto);
+        Self::new(
+            factory,
+            connected_to_journal,
+            logcontrol::syslog_identifier(),
+            target,
+            level,
+        )
+    }
+
+    /// Modify the state of the controlled drain.
+    fn modify<G>(&self, g: G) -> Result<(), ReloadError>
+    where
+        G: FnOnce(&mut DrainState),
+    {
+        let state = self.state.upgrade().ok_or(ReloadError::Gone)?;
+        let mut guard = state.write().map_err(|_| ReloadError::Poisoned)?;
+        g(&mut guard);
+        Ok(())
+    }
+}
+
+impl<F: SlogDrainFactory> LogControl1 for SlogLogControl1<F> {
+    fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        let slog_level = from_log_level(level)?;
+        self.modify(|state| state.level = slog_level)
+            .map_err(|error| {
+                LogControl1Error::reload_failed(format!("Failed to change level to {level}"), error)
+            })?;
+        self.level = level;
+        Ok(())
+    }
+
+    fn target(&self) -> &str {
+        KnownLogTarget::from(self.target).as_str()
+    }
+
+    /// Switch to the given `target`.
+    ///
+    /// Ignore leading and trailing whitespace in `target`, which `systemctl`
+    /// passes through.
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        let target = target.as_ref().trim();
+        let slog_target =
+            from_known_log_target(KnownLogTarget::try_from(target)?, self.connected_to_journal)?;
+        let new_drain = create_drain(slog_target, &self.factory, &self.syslog_identifier)?;
+        self.modify(|state| state.drain = new_drain)
+            .map_err(|error| {
+                LogControl1Error::reload_failed(
+                    format!("Failed to change log target to {target}"),
+                    error,
+                )
+            })?;
+        self.target = slog_target;
+        Ok(())
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        &self.syslog_identifier
+    }
+
+    /// Whether logging works.
+    ///
+    /// Return `false` if the controlled drain was dropped, if a previous
+    /// change of the drain panicked and thus disabled the drain, or if the
+    /// journal is selected but not reachable.
+    fn is_healthy(&self) -> bool {
+        // Modifying fails if the drain is gone or poisoned
+        self.modify(|_| {}).is_ok()
+            && (self.target != SlogLogTarget::Journal || logcontrol::journal_reachable())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "slog"
+    }
+
+    /// Support health checks only.
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities {
+            health: true,
+            ..LogControlCapabilities::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use logcontrol::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+    use slog::{Drain, Never, OwnedKVList, Record};
+    use static_assertions::assert_impl_all;
+
+    use crate::{BoxedDrain, ControlledDrain, ReloadError, SlogDrainFactory, SlogLogControl1};
+
+    // Ensure that the log control is Send and Sync, this is required for zbus.
+    assert_impl_all!(SlogLogControl1<CollectingFactory>: Send, Sync);
+
+    #[derive(Clone, Default)]
+    struct CollectMessages {
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Drain for CollectMessages {
+        type Ok = ();
+        type Err = Never;
+
+        fn log(&self, record: &Record, _values: &OwnedKVList) -> Result<(), Never> {
+            let mut guard = self.messages.try_lock().unwrap();
+            guard.push(format!("{}", record.msg()));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct CollectingFactory {
+        console: CollectMessages,
+        journal: CollectMessages,
+    }
+
+    impl SlogDrainFactory for CollectingFactory {
+        fn create_console_drain(&self) -> Result<BoxedDrain, LogControl1Error> {
+            Ok(Box::new(self.console.clone()))
+        }
+
+        fn create_journal_drain(
+            &self,
+            _syslog_identifier: String,
+        ) -> Result<BoxedDrain, LogControl1Error> {
+            Ok(Box::new(self.journal.clone()))
+        }
+    }
+
+    fn log_all_levels(drain: ControlledDrain) -> slog::Logger {
+        let logger = slog::Logger::root(drain, slog::o!());
+        slog::crit!(logger, "crit");
+        slog::error!(logger, "error");
+        slog::warn!(logger, "warn");
+        slog::info!(logger, "info");
+        slog::debug!(logger, "debug");
+        slog::trace!(logger, "trace");
+        logger
+    }
+
+    #[test]
+    fn set_level() {
+        let factory = CollectingFactory::default();
+        let console = factory.console.clone();
+        let (mut control, drain) = SlogLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            slog::Level::Warning,
+        )
+        .unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+        let logger = log_all_levels(drain);
+        assert_eq!(
+            *console.messages.try_lock().unwrap(),
+            &["crit", "error", "warn"]
+        );
+        console.messages.try_lock().unwrap().clear();
+
+        control.set_level(LogLevel::Info).unwrap();
+        assert_eq!(control.level(), LogLevel::Info);
+        slog::info!(logger, "info");
+        slog::debug!(logger, "debug");
+        slog::trace!(logger, "trace");
+        assert_eq!(*console.messages.try_lock().unwrap(), &["info", "debug"]);
+
+        assert!(matches!(
+            control.set_level(LogLevel::Emerg),
+            Err(LogControl1Error::UnsupportedLogLevel(LogLevel::Emerg))
+        ));
+        assert_eq!(control.level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn backend_name() {
+        let (control, _drain) = SlogLogControl1::new(
+            CollectingFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            slog::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.backend_name(), "slog");
+    }
+
+    #[test]
+    fn capabilities() {
+        let (control, _drain) = SlogLogControl1::new(
+            CollectingFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            slog::Level::Info,
+        )
+        .unwrap();
+        assert_eq!(control.capabilities().names(), ["health"]);
+    }
+
+    #[test]
+    fn is_healthy() {
+        let (control, drain) = SlogLogControl1::new(
+            CollectingFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            slog::Level::Info,
+        )
+        .unwrap();
+        assert!(control.is_healthy());
+
+        let state = control.state.upgrade().unwrap();
+        std::thread::spawn(move || {
+            let _guard = state.write().unwrap();
+            panic!("Poison the lock");
+        })
+        .join()
+        .unwrap_err();
+        assert!(!control.is_healthy());
+
+        drop(drain);
+        assert!(!control.is_healthy());
+    }
+
+    #[test]
+    fn set_target() {
+        let factory = CollectingFactory::default();
+        let console = factory.console.clone();
+        let journal = factory.journal.clone();
+        let (mut control, drain) = SlogLogControl1::new(
+            factory,
+            true,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            slog::Level::Info,
+        )
+        .unwrap();
+        let logger = slog::Logger::root(drain, slog::o!());
+        slog::info!(logger, "Message 1");
+
+        control.set_target("auto").unwrap();
+        assert_eq!(control.target(), "journal");
+        slog::info!(logger, "Message 2");
+
+        control.set_target(" null\n").unwrap();
+        assert_eq!(control.target(), "null");
+        slog::info!(logger, "Message 3");
+
+        assert!(matches!(
+            control.set_target("kmsg"),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+        assert!(matches!(
+            control.set_target("  "),
+            Err(LogControl1Error::UnsupportedLogTarget(_))
+        ));
+        assert_eq!(control.target(), "null");
+
+        assert_eq!(*console.messages.try_lock().unwrap(), &["Message 1"]);
+        assert_eq!(*journal.messages.try_lock().unwrap(), &["Message 2"]);
+    }
+
+    #[test]
+    fn dropped_drain_fails_with_reload_error() {
+        let (mut control, drain) = SlogLogControl1::new(
+            CollectingFactory::default(),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            slog::Level::Info,
+        )
+        .unwrap();
+        drop(drain);
+        for error in [
+            control.set_level(LogLevel::Debug).unwrap_err(),
+            control.set_target("null").unwrap_err(),
+        ] {
+            let source = std::error::Error::source(&error)
+                .and_then(|source| source.downcast_ref::<ReloadError>());
+            assert!(matches!(error, LogControl1Error::ReloadFailed { .. }));
+            assert_eq!(source, Some(&ReloadError::Gone));
+        }
+        assert_eq!(control.level(), LogLevel::Notice);
+        assert_eq!(control.target(), "console");
+    }
+}
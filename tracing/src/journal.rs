@@ -0,0 +1,1243 @@
+//! A configurable journal layer.
+//!
+//! [`JournalLayer`] sends events to the systemd journal, much like
+//! [`tracing_journald::Layer`], but sends its payloads to a pluggable
+//! [`JournalSink`] and offers additional configuration, e.g. a custom mapping
+//! from tracing levels to journal priorities.
+//!
+//! [`JournalLayerFactory`] holds the configuration for journal layers, and
+//! creates new layers whenever [`crate::TracingLogControl1`] switches to the
+//! journal target.
+//!
+//! The serialization of events into the native journal protocol is derived
+//! from [`tracing_journald`], which is Copyright (c) 2019 Tokio Contributors,
+//! and licensed under the MIT license:
+//!
+//! > Permission is hereby granted, free of charge, to any person obtaining a
+//! > copy of this software and associated documentation files (the "Software"),
+//! > to deal in the Software without restriction, including without limitation
+//! > the rights to use, copy, modify, merge, publish, distribute, sublicense,
+//! > and/or sell copies of the Software, and to permit persons to whom the
+//! > Software is furnished to do so, subject to the following conditions:
+//! >
+//! > The above copyright notice and this permission notice shall be included in
+//! > all copies or substantial portions of the Software.
+//! >
+//! > THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+//! > IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+//! > FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+//! > AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+//! > LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+//! > FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+//! > DEALINGS IN THE SOFTWARE.
+
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::AsFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use logcontrol::LogControl1Error;
+use rustix::fs::{fcntl_add_seals, memfd_create, MemfdFlags, SealFlags};
+use rustix::io::Errno;
+use rustix::net::{sendmsg_unix, SendAncillaryBuffer, SendFlags, SocketAddrUnix};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+const JOURNALD_PATH: &str = "/run/systemd/journal/socket";
+
+/// A timer to format timestamps of journal entries.
+pub type Timer = Arc<dyn FormatTime + Send + Sync>;
+
+/// A sink for serialized journal entries.
+pub trait JournalSink: Send + Sync {
+    /// Send the serialized journal entry in `payload`.
+    ///
+    /// `payload` contains all fields of a single journal entry, serialized in
+    /// the [native journal protocol](https://systemd.io/JOURNAL_NATIVE_PROTOCOL/).
+    fn send_payload(&self, payload: &[u8]) -> io::Result<()>;
+}
+
+/// A sink which sends entries to the native socket of journald.
+#[derive(Debug)]
+pub struct JournalSocket {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl JournalSocket {
+    /// Connect to the journald socket.
+    ///
+    /// Fails if the journald socket couldn't be opened, e.g. when journald is
+    /// not running, or inside a container which has no direct access to the
+    /// journald socket.
+    pub fn connect() -> io::Result<Self> {
+        Self::connect_to(Path::new(JOURNALD_PATH))
+    }
+
+    /// Connect to the journald socket at `path`.
+    fn connect_to(path: &Path) -> io::Result<Self> {
+        let socket = Self {
+            socket: UnixDatagram::unbound()?,
+            path: path.to_path_buf(),
+        };
+        // Check that we can talk to journald, by sending empty payload which journald discards.
+        // However if the socket didn't exist or if none listened we'd get an error here.
+        socket.send_payload(&[])?;
+        Ok(socket)
+    }
+
+    /// Send a large payload to journald.
+    ///
+    /// Write payload to a memfd, seal it, and then send the FD to the socket in
+    /// an ancilliary message.
+    ///
+    /// See <https://systemd.io/JOURNAL_NATIVE_PROTOCOL/>.
+    fn send_large_payload(&self, payload: &[u8]) -> io::Result<()> {
+        let mut mem: File = memfd_create(
+            "logcontrol-tracing",
+            MemfdFlags::ALLOW_SEALING | MemfdFlags::CLOEXEC,
+        )?
+        .into();
+        mem.write_all(payload)?;
+        // Fully seal the memfd to signal journald that it is safe to mmap now.
+        fcntl_add_seals(
+            &mem,
+            SealFlags::SEAL | SealFlags::SHRINK | SealFlags::WRITE | SealFlags::GROW,
+        )?;
+        let fds = &[mem.as_fd()];
+        let scm_rights = rustix::net::SendAncillaryMessage::ScmRights(fds);
+        // A single FD easily fits into a static buffer.
+        let mut buffer = [0; 64];
+        let mut buffer = SendAncillaryBuffer::new(&mut buffer);
+        if !buffer.push(scm_rights) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Failed to push ScmRights message",
+            ));
+        }
+        sendmsg_unix(
+            &self.socket,
+            &SocketAddrUnix::new(&self.path)?,
+            &[],
+            &mut buffer,
+            SendFlags::NOSIGNAL,
+        )?;
+        Ok(())
+    }
+}
+
+impl JournalSink for JournalSocket {
+    /// Send `payload` to journald.
+    ///
+    /// Directly send it as datagram, and fall back to a sealed memfd if the
+    /// payload exceeds the maximum datagram size, like [`tracing_journald`].
+    fn send_payload(&self, payload: &[u8]) -> io::Result<()> {
+        match self.socket.send_to(payload, &self.path) {
+            Ok(_) => Ok(()),
+            Err(error) if Some(Errno::MSGSIZE) == Errno::from_io_error(&error) => {
+                self.send_large_payload(payload)
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Map a tracing level to a journal priority.
+///
+/// This is the default mapping of [`JournalLayer`], which is the same as the
+/// mapping of `tracing_journald`:
+///
+/// - `ERROR` → `3` (err)
+/// - `WARN` → `4` (warning)
+/// - `INFO` → `5` (notice)
+/// - `DEBUG` → `6` (info)
+/// - `TRACE` → `7` (debug)
+pub fn default_priority(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 5,
+        Level::DEBUG => 6,
+        Level::TRACE => 7,
+    }
+}
+
+/// Map the name of a span or event field to a journal field name.
+///
+/// This is the default mapping of [`JournalLayer`], which upcases `name`, like
+/// `tracing_journald` does.
+pub fn default_field_name(name: &str) -> String {
+    name.to_ascii_uppercase()
+}
+
+/// Sends events and their fields to journald.
+///
+/// Like [`tracing_journald::Layer`] this layer sanitizes field names by
+/// translating `.`s into `_`s, stripping leading `_`s and non-ascii-alphanumeric
+/// characters other than `_`, and upcasing.  See [`JournalLayer::with_field_name_mapper`]
+/// to rename span and event fields before sanitizing.
+///
+/// The standard journald `PRIORITY`, `CODE_LINE`, `CODE_FILE`, and
+/// `SYSLOG_IDENTIFIER` fields are automatically emitted, as well as a `TARGET`
+/// field with the target of the event.  See [`default_priority`] for the
+/// default mapping of levels to priorities.
+///
+/// For events recorded inside spans, an additional `SPAN_NAME` field is emitted
+/// with the name of each of the event's parent spans.
+///
+/// A `message_id` field of an event maps to the standard `MESSAGE_ID` field,
+/// like the message maps to `MESSAGE`: neither the field prefix nor the field
+/// name mapper apply to it.  Use it to tag well-known events with a stable
+/// 128-bit ID in hex, e.g. `tracing::info!(message_id = "…", "…")`, see
+/// [`logcontrol::CONFIGURATION_CHANGED_MESSAGE_ID`].
+///
+/// The journal records the time of each entry on its own, so by default this
+/// layer emits no timestamp; see [`JournalLayer::with_timer`].
+///
+/// The native journal protocol supports multi-line field values, so each event
+/// makes exactly one journal entry, even if its message spans multiple lines;
+/// journald only splits messages written to stdout or stderr at newlines.
+/// See [`JournalLayer::with_single_line_messages`] to join the lines of
+/// messages nonetheless.
+pub struct JournalLayer {
+    sink: Arc<dyn JournalSink>,
+    syslog_identifier: OsString,
+    field_prefix: Option<String>,
+    priority_mapper: fn(Level) -> u8,
+    field_name_mapper: fn(&str) -> String,
+    timer: Option<Timer>,
+    pid_and_tid: bool,
+    static_fields: Vec<(String, String)>,
+    single_line_messages: bool,
+}
+
+impl JournalLayer {
+    /// Create a new layer which sends events to the given `sink`.
+    ///
+    /// The new layer uses the given `syslog_identifier`, no field prefix, the
+    /// [`default_priority`] mapping, and the [`default_field_name`] mapping.
+    ///
+    /// The journal accepts arbitrary bytes in fields, so `syslog_identifier`
+    /// need not be valid UTF-8.
+    pub fn new(sink: Arc<dyn JournalSink>, syslog_identifier: OsString) -> Self {
+        Self {
+            sink,
+            syslog_identifier,
+            field_prefix: None,
+            priority_mapper: default_priority,
+            field_name_mapper: default_field_name,
+            timer: None,
+            pid_and_tid: false,
+            static_fields: Vec::new(),
+            single_line_messages: false,
+        }
+    }
+
+    /// Set the prefix to apply to names of user-defined fields other than the event `message` field.
+    pub fn with_field_prefix(mut self, prefix: Option<String>) -> Self {
+        self.field_prefix = prefix;
+        self
+    }
+
+    /// Use the given function to map levels of events to journal priorities.
+    pub fn with_priority_mapper(mut self, mapper: fn(Level) -> u8) -> Self {
+        self.priority_mapper = mapper;
+        self
+    }
+
+    /// Use the given function to map names of span and event fields to journal field names.
+    ///
+    /// The layer applies `mapper` to the names of all user-defined fields,
+    /// except for the `message` field of events, which maps to the standard
+    /// `MESSAGE` field.  The field prefix goes in front of the mapped name,
+    /// and the layer still sanitizes the result, because journald drops
+    /// fields with invalid names.
+    pub fn with_field_name_mapper(mut self, mapper: fn(&str) -> String) -> Self {
+        self.field_name_mapper = mapper;
+        self
+    }
+
+    /// Add a `SYSLOG_TIMESTAMP` field formatted with the given `timer` to each event.
+    ///
+    /// The journal adds its own timestamps, so this is only useful if the
+    /// timestamp of the application itself matters.  `None` disables the field.
+    pub fn with_timer(mut self, timer: Option<Timer>) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Whether to add `PID` and `TID` fields with the process and thread ID to each event.
+    ///
+    /// journald records the process ID in the trusted `_PID` field already,
+    /// but not the ID of the thread which emitted the event.
+    pub fn with_pid_and_tid(mut self, pid_and_tid: bool) -> Self {
+        self.pid_and_tid = pid_and_tid;
+        self
+    }
+
+    /// Add the given static `fields` to each event.
+    ///
+    /// Each field is a pair of the journal field name and its value.  The
+    /// layer emits these fields verbatim, without the field prefix, so names
+    /// must be valid journal field names, i.e. consist of uppercase letters,
+    /// digits and underscores.
+    pub fn with_static_fields(mut self, fields: Vec<(String, String)>) -> Self {
+        self.static_fields = fields;
+        self
+    }
+
+    /// Whether to join the lines of event messages with spaces.
+    ///
+    /// The journal stores multi-line messages just fine, but some consumers
+    /// of the journal, e.g. syslog forwarders, expect a single line per
+    /// message.  This only affects the `MESSAGE` field of events; the console
+    /// layer still prints multi-line messages as they are.
+    pub fn with_single_line_messages(mut self, single_line_messages: bool) -> Self {
+        self.single_line_messages = single_line_messages;
+        self
+    }
+
+    /// Get the syslog identifier in use.
+    pub fn syslog_identifier(&self) -> &OsStr {
+        &self.syslog_identifier
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for JournalLayer
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut buf = Vec::with_capacity(256);
+
+        writeln!(buf, "SPAN_NAME").unwrap();
+        put_value(&mut buf, span.name().as_bytes());
+        put_metadata(&mut buf, span.metadata(), Some("SPAN_"));
+
+        attrs.record(&mut FieldVisitor {
+            buf: &mut buf,
+            prefix: self.field_prefix.as_deref(),
+            field_name_mapper: self.field_name_mapper,
+            prefix_message: true,
+            single_line_message: false,
+        });
+
+        span.extensions_mut().insert(SpanFields(buf));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut exts = span.extensions_mut();
+        if let Some(SpanFields(buf)) = exts.get_mut::<SpanFields>() {
+            values.record(&mut FieldVisitor {
+                buf,
+                prefix: self.field_prefix.as_deref(),
+                field_name_mapper: self.field_name_mapper,
+                prefix_message: true,
+                single_line_message: false,
+            });
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut buf = Vec::with_capacity(256);
+
+        // Record span fields
+        for span in ctx
+            .lookup_current()
+            .into_iter()
+            .flat_map(|span| span.scope().from_root())
+        {
+            if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                buf.extend_from_slice(fields);
+            }
+        }
+
+        // Record event fields
+        let priority = (self.priority_mapper)(*event.metadata().level());
+        writeln!(buf, "PRIORITY={priority}").unwrap();
+        put_metadata(&mut buf, event.metadata(), None);
+        put_field_length_encoded(&mut buf, "SYSLOG_IDENTIFIER", |buf| {
+            buf.extend_from_slice(self.syslog_identifier.as_bytes())
+        });
+        if let Some(timer) = &self.timer {
+            let mut timestamp = String::new();
+            if timer.format_time(&mut Writer::new(&mut timestamp)).is_ok() {
+                put_field_length_encoded(&mut buf, "SYSLOG_TIMESTAMP", |buf| {
+                    buf.extend_from_slice(timestamp.as_bytes())
+                });
+            }
+        }
+        if self.pid_and_tid {
+            writeln!(buf, "PID={}", std::process::id()).unwrap();
+            if let Some(tid) = current_thread_id() {
+                writeln!(buf, "TID={tid}").unwrap();
+            }
+        }
+        for (name, value) in &self.static_fields {
+            put_field_length_encoded(&mut buf, name, |buf| {
+                buf.extend_from_slice(value.as_bytes())
+            });
+        }
+
+        event.record(&mut FieldVisitor {
+            buf: &mut buf,
+            prefix: self.field_prefix.as_deref(),
+            field_name_mapper: self.field_name_mapper,
+            prefix_message: false,
+            single_line_message: self.single_line_messages,
+        });
+
+        // At this point we can't handle the error anymore so just ignore it.
+        let _ = self.sink.send_payload(&buf);
+    }
+}
+
+struct SpanFields(Vec<u8>);
+
+/// Get the kernel ID of the current thread.
+///
+/// Read the ID from `/proc/thread-self` once per thread.
+fn current_thread_id() -> Option<u32> {
+    thread_local! {
+        static THREAD_ID: Option<u32> = std::fs::read_link("/proc/thread-self")
+            .ok()
+            .and_then(|path| path.file_name()?.to_str()?.parse().ok());
+    }
+    THREAD_ID.with(|id| *id)
+}
+
+/// Write fields of events and spans in the journal export format.
+struct FieldVisitor<'a> {
+    buf: &'a mut Vec<u8>,
+    prefix: Option<&'a str>,
+    field_name_mapper: fn(&str) -> String,
+    /// Whether to apply the prefix and the field name mapper to the message field as well.
+    ///
+    /// The message and the message ID of events map to the standard `MESSAGE`
+    /// and `MESSAGE_ID` fields, so we must not prefix or rename them.
+    prefix_message: bool,
+    /// Whether to join the lines of the message field.
+    single_line_message: bool,
+}
+
+impl FieldVisitor<'_> {
+    /// Whether `field` is a user-defined field, i.e. not the message or message ID of an event.
+    fn is_user_defined(&self, field: &Field) -> bool {
+        self.prefix_message || !matches!(field.name(), "message" | "message_id")
+    }
+
+    fn put_prefix(&mut self, field: &Field) {
+        if let Some(prefix) = self.prefix {
+            if self.is_user_defined(field) {
+                self.buf.extend_from_slice(prefix.as_bytes());
+                self.buf.push(b'_');
+            }
+        }
+    }
+
+    /// Get the journal field name of `field`, before sanitizing.
+    fn field_name(&self, field: &Field) -> Cow<'static, str> {
+        if self.is_user_defined(field) {
+            Cow::Owned((self.field_name_mapper)(field.name()))
+        } else {
+            Cow::Borrowed(field.name())
+        }
+    }
+
+    fn joins_lines(&self, field: &Field) -> bool {
+        self.single_line_message && field.name() == "message"
+    }
+}
+
+/// Join all lines of `value` with spaces.
+fn join_lines(value: &str) -> String {
+    value.lines().collect::<Vec<_>>().join(" ")
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let joined;
+        let value = if self.joins_lines(field) {
+            joined = join_lines(value);
+            &joined
+        } else {
+            value
+        };
+        self.put_prefix(field);
+        let name = self.field_name(field);
+        put_field_length_encoded(self.buf, &name, |buf| {
+            buf.extend_from_slice(value.as_bytes())
+        });
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if self.joins_lines(field) {
+            return self.record_str(field, &format!("{value:?}"));
+        }
+        self.put_prefix(field);
+        let name = self.field_name(field);
+        put_field_length_encoded(self.buf, &name, |buf| write!(buf, "{value:?}").unwrap());
+    }
+}
+
+fn put_metadata(buf: &mut Vec<u8>, meta: &Metadata<'_>, prefix: Option<&str>) {
+    let prefix = prefix.unwrap_or_default();
+    buf.extend_from_slice(prefix.as_bytes());
+    put_field_wellformed(buf, "TARGET", meta.target().as_bytes());
+    if let Some(file) = meta.file() {
+        buf.extend_from_slice(prefix.as_bytes());
+        put_field_wellformed(buf, "CODE_FILE", file.as_bytes());
+    }
+    if let Some(line) = meta.line() {
+        // Text format is safe as a line number can't possibly contain anything funny
+        writeln!(buf, "{prefix}CODE_LINE={line}").unwrap();
+    }
+}
+
+/// Append a sanitized and length-encoded field into `buf`.
+///
+/// `write_value` is invoked with `buf` as argument to append the value data to
+/// `buf`.  This function then determines the length of the data written and
+/// adds it in the appropriate place in `buf`.
+fn put_field_length_encoded(buf: &mut Vec<u8>, name: &str, write_value: impl FnOnce(&mut Vec<u8>)) {
+    sanitize_name(name, buf);
+    buf.push(b'\n');
+    buf.extend_from_slice(&[0; 8]); // Length tag, to be populated
+    let start = buf.len();
+    write_value(buf);
+    let end = buf.len();
+    buf[start - 8..start].copy_from_slice(&((end - start) as u64).to_le_bytes());
+    buf.push(b'\n');
+}
+
+/// Mangle a name into journald-compliant form
+fn sanitize_name(name: &str, buf: &mut Vec<u8>) {
+    buf.extend(
+        name.bytes()
+            .map(|c| if c == b'.' { b'_' } else { c })
+            .skip_while(|&c| c == b'_')
+            .filter(|&c| c == b'_' || char::from(c).is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_uppercase()),
+    );
+}
+
+/// Append arbitrary data with a well-formed name and value.
+///
+/// `value` must not contain an internal newline.
+fn put_field_wellformed(buf: &mut Vec<u8>, name: &str, value: &[u8]) {
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(b'\n');
+    put_value(buf, value);
+}
+
+/// Write the value portion of a key-value pair, in length-encoded format.
+fn put_value(buf: &mut Vec<u8>, value: &[u8]) {
+    buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    buf.extend_from_slice(value);
+    buf.push(b'\n');
+}
+
+type Connect = Arc<dyn Fn() -> io::Result<Arc<dyn JournalSink>> + Send + Sync>;
+
+/// A sink which connects to its underlying sink on first use.
+///
+/// Try to connect on every payload until connecting succeeds, and drop the
+/// payload if connecting fails.
+struct LazySink {
+    connect: Connect,
+    sink: Mutex<Option<Arc<dyn JournalSink>>>,
+}
+
+impl JournalSink for LazySink {
+    fn send_payload(&self, payload: &[u8]) -> io::Result<()> {
+        let sink = {
+            // Connecting while holding the lock makes concurrent events wait
+            // for a single attempt, instead of all connecting at once.
+            let mut guard = self.sink.lock().unwrap_or_else(PoisonError::into_inner);
+            match &*guard {
+                Some(sink) => sink.clone(),
+                None => guard.insert((self.connect)()?).clone(),
+            }
+        };
+        sink.send_payload(payload)
+    }
+}
+
+/// Configuration for journal layers.
+///
+/// Creates a new [`JournalLayer`] for every switch to the journal target.  By
+/// default the layers send events to journald, use no field prefix, map
+/// levels to priorities with [`default_priority`], and map field names with
+/// [`default_field_name`].
+#[derive(Clone)]
+pub struct JournalLayerFactory {
+    connect: Connect,
+    syslog_identifier: Option<OsString>,
+    field_prefix: Option<String>,
+    priority_mapper: fn(Level) -> u8,
+    field_name_mapper: fn(&str) -> String,
+    timer: Option<Timer>,
+    pid_and_tid: bool,
+    static_fields: Vec<(String, String)>,
+    single_line_messages: bool,
+    lazy: bool,
+}
+
+impl Default for JournalLayerFactory {
+    fn default() -> Self {
+        Self {
+            connect: Arc::new(|| Ok(Arc::new(JournalSocket::connect()?))),
+            syslog_identifier: None,
+            field_prefix: None,
+            priority_mapper: default_priority,
+            field_name_mapper: default_field_name,
+            timer: None,
+            pid_and_tid: false,
+            static_fields: Vec::new(),
+            single_line_messages: false,
+            lazy: false,
+        }
+    }
+}
+
+impl fmt::Debug for JournalLayerFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JournalLayerFactory")
+            .field("syslog_identifier", &self.syslog_identifier)
+            .field("field_prefix", &self.field_prefix)
+            .field("timer", &self.timer.is_some())
+            .field("pid_and_tid", &self.pid_and_tid)
+            .field("static_fields", &self.static_fields)
+            .field("single_line_messages", &self.single_line_messages)
+            .field("lazy", &self.lazy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JournalLayerFactory {
+    /// Send events of all layers to the given `sink` instead of journald.
+    pub fn with_sink(mut self, sink: Arc<dyn JournalSink>) -> Self {
+        self.connect = Arc::new(move || Ok(sink.clone()));
+        self
+    }
+
+    /// Connect to sinks with the given `connect` function instead of journald.
+    ///
+    /// Call `connect` whenever a new layer needs a sink, see
+    /// [`Self::create_layer`] and [`Self::with_lazy_connect`], and whenever
+    /// [`Self::is_reachable`] checks the sink.
+    pub fn with_connect<C>(mut self, connect: C) -> Self
+    where
+        C: Fn() -> io::Result<Arc<dyn JournalSink>> + Send + Sync + 'static,
+    {
+        self.connect = Arc::new(connect);
+        self
+    }
+
+    /// Use the given `syslog_identifier` for all layers.
+    ///
+    /// If set, layers use this identifier instead of the identifier passed to
+    /// [`Self::create_layer`].  Unlike the latter, this identifier need not be
+    /// valid UTF-8, so you can use [`logcontrol::syslog_identifier_os`] to
+    /// preserve the original name of the executable:
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::journal::JournalLayerFactory;
+    ///
+    /// let factory = JournalLayerFactory::default()
+    ///     .with_syslog_identifier(Some(logcontrol_tracing::logcontrol::syslog_identifier_os()));
+    /// ```
+    pub fn with_syslog_identifier(mut self, syslog_identifier: Option<OsString>) -> Self {
+        self.syslog_identifier = syslog_identifier;
+        self
+    }
+
+    /// Set the prefix to apply to names of user-defined fields other than the event `message` field.
+    pub fn with_field_prefix(mut self, prefix: Option<String>) -> Self {
+        self.field_prefix = prefix;
+        self
+    }
+
+    /// Use the given function to map levels of events to journal priorities.
+    ///
+    /// Defaults to [`default_priority`].
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::journal::{default_priority, JournalLayerFactory};
+    ///
+    /// // Log warnings at notice priority
+    /// let factory = JournalLayerFactory::default().with_priority_mapper(|level| match level {
+    ///     tracing::Level::WARN => 5,
+    ///     other => default_priority(other),
+    /// });
+    /// ```
+    pub fn with_priority_mapper(mut self, mapper: fn(Level) -> u8) -> Self {
+        self.priority_mapper = mapper;
+        self
+    }
+
+    /// Use the given function to map names of span and event fields to journal field names.
+    ///
+    /// Defaults to [`default_field_name`].  See [`JournalLayer::with_field_name_mapper`].
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::journal::JournalLayerFactory;
+    ///
+    /// // Record `user` as `APP_USER_ID`, for a schema shared with other services
+    /// let factory = JournalLayerFactory::default().with_field_name_mapper(|name| match name {
+    ///     "user" => "APP_USER_ID".to_string(),
+    ///     other => format!("APP_{}", other.to_ascii_uppercase()),
+    /// });
+    /// ```
+    pub fn with_field_name_mapper(mut self, mapper: fn(&str) -> String) -> Self {
+        self.field_name_mapper = mapper;
+        self
+    }
+
+    /// Add a `SYSLOG_TIMESTAMP` field formatted with the given `timer` to each event.
+    ///
+    /// Defaults to `None`, i.e. no timestamp field, because the journal adds
+    /// its own timestamps.  See [`JournalLayer::with_timer`].
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use logcontrol_tracing::journal::JournalLayerFactory;
+    ///
+    /// let factory = JournalLayerFactory::default()
+    ///     .with_timer(Some(Arc::new(tracing_subscriber::fmt::time::SystemTime)));
+    /// ```
+    pub fn with_timer(mut self, timer: Option<Timer>) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Whether to add `PID` and `TID` fields with the process and thread ID to each event.
+    ///
+    /// Defaults to `false`.  See [`JournalLayer::with_pid_and_tid`].
+    pub fn with_pid_and_tid(mut self, pid_and_tid: bool) -> Self {
+        self.pid_and_tid = pid_and_tid;
+        self
+    }
+
+    /// Add the given static `fields` to each event.
+    ///
+    /// Defaults to no fields.  See [`JournalLayer::with_static_fields`].
+    ///
+    /// Use this to add a `UNIT` field with the name of the systemd unit of the
+    /// service, to correlate its messages with the unit, e.g. with
+    /// `journalctl UNIT=my-service.service`.  systemd does not tell a service
+    /// its unit name, so pass it in the unit file with `Environment=UNIT=%n`:
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::journal::JournalLayerFactory;
+    ///
+    /// let fields = std::env::var("UNIT")
+    ///     .map(|unit| vec![("UNIT".to_string(), unit)])
+    ///     .unwrap_or_default();
+    /// let factory = JournalLayerFactory::default().with_static_fields(fields);
+    /// ```
+    pub fn with_static_fields(mut self, fields: Vec<(String, String)>) -> Self {
+        self.static_fields = fields;
+        self
+    }
+
+    /// Whether to join the lines of event messages with spaces.
+    ///
+    /// Defaults to `false`, i.e. multi-line messages end up as a single
+    /// journal entry with a multi-line message.  See
+    /// [`JournalLayer::with_single_line_messages`].
+    ///
+    /// Combine this with [`crate::ConfigurableLogControl1LayerFactory`] to
+    /// keep multi-line messages on the console, but join lines in the journal:
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::ConfigurableLogControl1LayerFactory;
+    /// use logcontrol_tracing::journal::JournalLayerFactory;
+    ///
+    /// let factory = ConfigurableLogControl1LayerFactory::default()
+    ///     .with_journal(JournalLayerFactory::default().with_single_line_messages(true));
+    /// ```
+    pub fn with_single_line_messages(mut self, single_line_messages: bool) -> Self {
+        self.single_line_messages = single_line_messages;
+        self
+    }
+
+    /// Whether to connect new layers to their sink on the first event.
+    ///
+    /// Defaults to `false`, i.e. [`Self::create_layer`] connects to the sink
+    /// right away, and fails if connecting fails.
+    ///
+    /// If `true`, creating layers always succeeds, and each layer tries to
+    /// connect for every event until connecting succeeds; events are lost
+    /// until then.  This keeps services from failing to start if journald is
+    /// not yet available, e.g. early at boot, but leaves no trace of the lost
+    /// events.
+    pub fn with_lazy_connect(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Whether the sink for new layers is reachable.
+    ///
+    /// With the default sink, check whether journald accepts messages.  Any
+    /// sink given to [`Self::with_sink`] is always reachable.
+    pub fn is_reachable(&self) -> bool {
+        (self.connect)().is_ok()
+    }
+
+    /// Create a new journal layer with the given `syslog_identifier`.
+    ///
+    /// Ignore `syslog_identifier` if the factory has its own identifier, see
+    /// [`Self::with_syslog_identifier`].
+    ///
+    /// Return an error if connecting to the sink fails, unless the factory
+    /// connects lazily, see [`Self::with_lazy_connect`].
+    pub fn create_layer(
+        &self,
+        syslog_identifier: String,
+    ) -> Result<JournalLayer, LogControl1Error> {
+        let sink: Arc<dyn JournalSink> = if self.lazy {
+            Arc::new(LazySink {
+                connect: self.connect.clone(),
+                sink: Mutex::new(None),
+            })
+        } else {
+            (self.connect)()?
+        };
+        let syslog_identifier = self
+            .syslog_identifier
+            .clone()
+            .unwrap_or_else(|| OsString::from(syslog_identifier));
+        Ok(JournalLayer::new(sink, syslog_identifier)
+            .with_field_prefix(self.field_prefix.clone())
+            .with_priority_mapper(self.priority_mapper)
+            .with_field_name_mapper(self.field_name_mapper)
+            .with_timer(self.timer.clone())
+            .with_pid_and_tid(self.pid_and_tid)
+            .with_static_fields(self.static_fields.clone())
+            .with_single_line_messages(self.single_line_messages))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::ffi::OsStr;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use similar_asserts::assert_eq;
+    use tracing::Level;
+    use tracing_subscriber::fmt::format::Writer;
+    use tracing_subscriber::fmt::time::FormatTime;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::Registry;
+
+    use super::{
+        current_thread_id, default_priority, JournalLayerFactory, JournalSink, JournalSocket,
+    };
+
+    /// A journal sink which records all entries.
+    #[derive(Default)]
+    pub(crate) struct TestJournal {
+        entries: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl TestJournal {
+        /// Parse all recorded entries into key-value pairs.
+        pub(crate) fn entries(&self) -> Vec<Vec<(String, String)>> {
+            self.entries
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|payload| parse_entry(payload))
+                .collect()
+        }
+
+        /// Get the values of `field` of all recorded entries.
+        pub(crate) fn field_values(&self, field: &str) -> Vec<String> {
+            self.entries()
+                .into_iter()
+                .flat_map(|entry| entry.into_iter())
+                .filter(|(key, _)| key == field)
+                .map(|(_, value)| value)
+                .collect()
+        }
+    }
+
+    impl JournalSink for TestJournal {
+        fn send_payload(&self, payload: &[u8]) -> io::Result<()> {
+            self.entries.lock().unwrap().push(payload.to_vec());
+            Ok(())
+        }
+    }
+
+    /// Parse a journal entry in the native protocol into key-value pairs.
+    fn parse_entry(mut payload: &[u8]) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        while !payload.is_empty() {
+            let end = payload.iter().position(|&c| c == b'\n').unwrap();
+            let line = &payload[..end];
+            if let Some(equals) = line.iter().position(|&c| c == b'=') {
+                fields.push((
+                    String::from_utf8_lossy(&line[..equals]).into_owned(),
+                    String::from_utf8_lossy(&line[equals + 1..]).into_owned(),
+                ));
+                payload = &payload[end + 1..];
+            } else {
+                let name = String::from_utf8_lossy(line).into_owned();
+                let rest = &payload[end + 1..];
+                let length = u64::from_le_bytes(rest[..8].try_into().unwrap()) as usize;
+                let value = String::from_utf8_lossy(&rest[8..8 + length]).into_owned();
+                fields.push((name, value));
+                payload = &rest[8 + length + 1..];
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn default_priority_mapping() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::error!("error");
+            tracing::warn!("warn");
+            tracing::info!("info");
+            tracing::debug!("debug");
+            tracing::trace!("trace");
+        });
+        assert_eq!(journal.field_values("PRIORITY"), ["3", "4", "5", "6", "7"]);
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            ["error", "warn", "info", "debug", "trace"]
+        );
+        assert_eq!(journal.field_values("SYSLOG_IDENTIFIER")[0], "test");
+    }
+
+    #[test]
+    fn custom_priority_mapping() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .with_priority_mapper(|level| match level {
+                Level::WARN => 5,
+                other => default_priority(other),
+            })
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::error!("error");
+            tracing::warn!("warn");
+            tracing::info!("info");
+        });
+        assert_eq!(journal.field_values("PRIORITY"), ["3", "5", "5"]);
+    }
+
+    #[test]
+    fn non_utf8_syslog_identifier() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .with_syslog_identifier(Some(OsStr::from_bytes(b"foo\xffbar").to_os_string()))
+            .create_layer("ignored".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::error!("error");
+        });
+        let entries = journal.entries.lock().unwrap();
+        let mut expected = b"SYSLOG_IDENTIFIER\n".to_vec();
+        expected.extend_from_slice(&7_u64.to_le_bytes());
+        expected.extend_from_slice(b"foo\xffbar\n");
+        assert!(entries[0]
+            .windows(expected.len())
+            .any(|window| window == expected));
+    }
+
+    #[derive(Clone)]
+    struct FixedTime;
+
+    impl FormatTime for FixedTime {
+        fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+            write!(w, "2024-01-02T03:04:05Z")
+        }
+    }
+
+    #[test]
+    fn no_timestamp_by_default() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("info");
+        });
+        let fields = journal.entries()[0]
+            .iter()
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        assert!(
+            fields.iter().all(|field| !field.contains("TIMESTAMP")),
+            "Unexpected timestamp in {fields:?}"
+        );
+    }
+
+    #[test]
+    fn timestamp_with_timer() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .with_timer(Some(Arc::new(FixedTime)))
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("info");
+        });
+        assert_eq!(
+            journal.field_values("SYSLOG_TIMESTAMP"),
+            ["2024-01-02T03:04:05Z"]
+        );
+    }
+
+    #[test]
+    fn pid_and_tid() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = JournalLayerFactory::default().with_sink(journal.clone());
+        let layer = factory.create_layer("test".to_string()).unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("without");
+        });
+        assert!(journal.field_values("TID").is_empty());
+
+        let layer = factory
+            .with_pid_and_tid(true)
+            .create_layer("test".to_string())
+            .unwrap();
+        let subscriber = Arc::new(Registry::default().with(layer));
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            tracing::info!("main");
+        });
+        thread::spawn(move || {
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!("spawned");
+            });
+        })
+        .join()
+        .unwrap();
+        let pid = std::process::id().to_string();
+        assert_eq!(journal.field_values("PID"), [pid.clone(), pid]);
+        let tids = journal.field_values("TID");
+        assert_eq!(tids.len(), 2);
+        assert_eq!(tids[0], current_thread_id().unwrap().to_string());
+        assert_ne!(tids[0], tids[1]);
+    }
+
+    #[test]
+    fn static_fields() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .with_field_prefix(Some("APP".to_string()))
+            .with_static_fields(vec![
+                ("UNIT".to_string(), "test.service".to_string()),
+                ("DEPLOYMENT".to_string(), "multi\nline".to_string()),
+            ])
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("first");
+            let _guard = tracing::info_span!("span").entered();
+            tracing::warn!("second");
+        });
+        assert_eq!(
+            journal.field_values("UNIT"),
+            ["test.service", "test.service"]
+        );
+        assert_eq!(
+            journal.field_values("DEPLOYMENT"),
+            ["multi\nline", "multi\nline"]
+        );
+    }
+
+    #[test]
+    fn field_name_mapper() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .with_field_name_mapper(|name| format!("MYAPP_{}", name.to_ascii_uppercase()))
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            let _guard = tracing::info_span!("span", request.id = 42).entered();
+            tracing::info!(user = "alice", "hello");
+        });
+        let entries = journal.entries();
+        assert_eq!(entries.len(), 1);
+        let names = entries[0]
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"MYAPP_REQUEST_ID"), "{names:?}");
+        assert!(names.contains(&"MYAPP_USER"), "{names:?}");
+        assert!(!names.contains(&"USER"), "{names:?}");
+        // The message and the standard fields keep their names
+        assert_eq!(journal.field_values("MESSAGE"), ["hello"]);
+        assert_eq!(journal.field_values("SYSLOG_IDENTIFIER"), ["test"]);
+        assert_eq!(journal.field_values("TARGET").len(), 1);
+    }
+
+    #[test]
+    fn message_id() {
+        let journal = Arc::new(TestJournal::default());
+        let layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .with_field_prefix(Some("APP".to_string()))
+            .with_field_name_mapper(|name| format!("MYAPP_{}", name.to_ascii_uppercase()))
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            let _guard = tracing::info_span!("span", message_id = "span").entered();
+            tracing::info!(
+                message_id = logcontrol::CONFIGURATION_CHANGED_MESSAGE_ID,
+                "changed"
+            );
+        });
+        assert_eq!(
+            journal.field_values("MESSAGE_ID"),
+            ["50ca8f207ba94b788a78d895f7fc7191"]
+        );
+        // Span fields are always user-defined
+        assert_eq!(journal.field_values("APP_MYAPP_MESSAGE_ID"), ["span"]);
+    }
+
+    #[test]
+    fn multi_line_messages() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = JournalLayerFactory::default().with_sink(journal.clone());
+        let layer = factory.create_layer("test".to_string()).unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!(detail = "a\nb", "first\nsecond");
+        });
+        let layer = factory
+            .with_single_line_messages(true)
+            .create_layer("test".to_string())
+            .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!(detail = "a\nb", "first\r\nsecond");
+            tracing::info!(message = "third\nfourth");
+        });
+        assert_eq!(journal.entries().len(), 3);
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            ["first\nsecond", "first second", "third fourth"]
+        );
+        assert_eq!(journal.field_values("DETAIL"), ["a\nb", "a\nb"]);
+    }
+
+    #[test]
+    fn lazy_connect() {
+        let journal = Arc::new(TestJournal::default());
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let factory = JournalLayerFactory::default().with_connect({
+            let journal = journal.clone();
+            let attempts = attempts.clone();
+            move || {
+                // Fail the first two attempts
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(io::Error::from(io::ErrorKind::NotFound))
+                } else {
+                    Ok(journal.clone())
+                }
+            }
+        });
+        assert!(factory.create_layer("test".to_string()).is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        let layer = factory
+            .with_lazy_connect(true)
+            .create_layer("test".to_string())
+            .unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("lost");
+            tracing::info!("first");
+            tracing::info!("second");
+        });
+        // Connecting stops once it succeeded
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(journal.field_values("MESSAGE"), ["first", "second"]);
+    }
+
+    #[test]
+    fn journal_socket_sends_large_payloads_through_memfd() {
+        use std::io::{IoSliceMut, Read, Seek, SeekFrom};
+        use std::os::unix::net::UnixDatagram;
+
+        use rustix::net::{recvmsg, RecvAncillaryBuffer, RecvAncillaryMessage, RecvFlags};
+
+        let path = std::env::temp_dir().join(format!(
+            "logcontrol-tracing-{}-large-payload.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let journald = UnixDatagram::bind(&path).unwrap();
+        let socket = JournalSocket::connect_to(&path).unwrap();
+        // The empty payload which checks the connection
+        assert_eq!(journald.recv(&mut [0; 16]).unwrap(), 0);
+
+        // Larger than the maximum datagram size of Unix sockets
+        let payload = (0..300 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        socket.send_payload(&payload).unwrap();
+
+        let mut space = [0; 64];
+        let mut ancillary = RecvAncillaryBuffer::new(&mut space);
+        let mut data = [0; 16];
+        let received = recvmsg(
+            &journald,
+            &mut [IoSliceMut::new(&mut data)],
+            &mut ancillary,
+            RecvFlags::empty(),
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(received.bytes, 0);
+        let mut fds = ancillary
+            .drain()
+            .filter_map(|message| match message {
+                RecvAncillaryMessage::ScmRights(fds) => Some(fds),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(fds.len(), 1);
+        // The memfd shares its offset with the sender, which wrote up to the end
+        let mut memfd = std::fs::File::from(fds.remove(0));
+        memfd.seek(SeekFrom::Start(0)).unwrap();
+        let mut contents = Vec::new();
+        memfd.read_to_end(&mut contents).unwrap();
+        assert!(contents == payload, "memfd holds a different payload");
+    }
+}
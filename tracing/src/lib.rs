@@ -12,6 +12,14 @@
 //! [`tracing_journald`] for the Journal target.  You can provide your own
 //! implementation to customize the layer for each target.
 //!
+//! [`ConfigurableLogControl1LayerFactory`] provides a layer factory which
+//! uses a configurable [`journal::JournalLayer`] for the journal target.  See
+//! [`journal::JournalLayerFactory`] for available options.
+//!
+//! With the `testing` feature `TracingLogControl1::with_captured` captures
+//! the events which pass the level and filters of a control, to check in tests
+//! that level changes take effect.
+//!
 //! When created [`TracingLogControl1`] additionally returns a layer which needs
 //! to be added to the global tracing subscriber, i.e. a [`tracing_subscriber::Registry`],
 //! for log control to have any effect.
@@ -30,45 +38,112 @@
 //! tracing::subscriber::set_global_default(subscriber).unwrap();
 //! // Then register `control` over DBus, e.g. via `logcontrol_zbus::LogControl1`.
 //! ```
+//!
+//! [`build_subscriber`] builds such a subscriber, optionally with an additional
+//! [`EnvFilter`].
+//!
+//! This crate re-exports the [`logcontrol`] crate, as well as the types of
+//! [`logcontrol`] needed to use [`TracingLogControl1`], so a dependency on this
+//! crate suffices:
+//!
+//! ```rust
+//! use logcontrol_tracing::{
+//!     KnownLogTarget, LogControl1, LogControl1Error, LogLevel, PrettyLogControl1LayerFactory,
+//!     TracingLogControl1,
+//! };
+//!
+//! fn setup() -> Result<(), LogControl1Error> {
+//!     let (mut control, _layer) = TracingLogControl1::<_, tracing_subscriber::Registry>::new(
+//!         PrettyLogControl1LayerFactory,
+//!         false,
+//!         "example".to_string(),
+//!         KnownLogTarget::Console,
+//!         tracing::Level::INFO,
+//!     )?;
+//!     control.set_level(LogLevel::Debug)?;
+//!     assert_eq!(control.level(), LogLevel::Debug);
+//!     Ok(())
+//! }
+//! # setup().unwrap();
+//! ```
 
 #![deny(warnings, clippy::all, missing_docs)]
 #![forbid(unsafe_code)]
 
-use logcontrol::{KnownLogTarget, LogControl1, LogControl1Error, LogLevel};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
 use tracing::Subscriber;
 use tracing_subscriber::filter::LevelFilter;
-use tracing_subscriber::layer::Layered;
+use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
 use tracing_subscriber::registry::LookupSpan;
-use tracing_subscriber::{fmt, reload, Layer};
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
 
 pub use logcontrol;
 pub use logcontrol::stderr_connected_to_journal;
 pub use logcontrol::syslog_identifier;
+pub use logcontrol::{
+    KnownLogTarget, LevelGuard, LogControl1, LogControl1Error, LogControlCapabilities, LogLevel,
+    UnsupportedTargetPolicy,
+};
+
+pub mod journal;
+mod testing;
+
+pub use testing::CaptureLayer;
+
+/// A custom log target which logs to the console and the journal at the same time.
+///
+/// This target is no [`KnownLogTarget`]; [`TracingLogControl1`] supports it in
+/// addition to the standard targets, e.g. to migrate a service from console
+/// logging to the journal.
+pub const CONSOLE_AND_JOURNAL_TARGET: &str = "console+journal";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TracingLogTarget {
     Console,
     Journal,
+    ConsoleAndJournal,
     Null,
 }
 
-impl From<TracingLogTarget> for KnownLogTarget {
-    fn from(value: TracingLogTarget) -> Self {
-        match value {
-            TracingLogTarget::Console => KnownLogTarget::Console,
-            TracingLogTarget::Journal => KnownLogTarget::Journal,
-            TracingLogTarget::Null => KnownLogTarget::Null,
+impl TracingLogTarget {
+    fn as_str(self) -> &'static str {
+        match self {
+            TracingLogTarget::Console => KnownLogTarget::Console.as_str(),
+            TracingLogTarget::Journal => KnownLogTarget::Journal.as_str(),
+            TracingLogTarget::ConsoleAndJournal => CONSOLE_AND_JOURNAL_TARGET,
+            TracingLogTarget::Null => KnownLogTarget::Null.as_str(),
         }
     }
+
+    fn has_console(self) -> bool {
+        matches!(
+            self,
+            TracingLogTarget::Console | TracingLogTarget::ConsoleAndJournal
+        )
+    }
+
+    fn has_journal(self) -> bool {
+        matches!(
+            self,
+            TracingLogTarget::Journal | TracingLogTarget::ConsoleAndJournal
+        )
+    }
 }
 
 fn from_known_log_target(
     target: KnownLogTarget,
     connected_to_journal: bool,
 ) -> Result<TracingLogTarget, LogControl1Error> {
+    let target = match target {
+        KnownLogTarget::Auto => logcontrol::resolve_auto(connected_to_journal),
+        other => other,
+    };
     match target {
-        KnownLogTarget::Auto if connected_to_journal => Ok(TracingLogTarget::Journal),
-        KnownLogTarget::Auto => Ok(TracingLogTarget::Console),
         KnownLogTarget::Console => Ok(TracingLogTarget::Console),
         KnownLogTarget::Journal => Ok(TracingLogTarget::Journal),
         KnownLogTarget::Null => Ok(TracingLogTarget::Null),
@@ -78,6 +153,45 @@ fn from_known_log_target(
     }
 }
 
+/// Split a filter directive off a `target` given to [`TracingLogControl1`].
+///
+/// Return the target itself, and the directive if the target has the form
+/// `<target>?filter=<directive>`.  Fail if the target has any other suffix,
+/// or an empty directive.
+fn split_target_filter(target: &str) -> Result<(&str, Option<&str>), LogControl1Error> {
+    let target = target.trim();
+    match target.split_once('?') {
+        None => Ok((target, None)),
+        Some((base, query)) => match query.strip_prefix("filter=") {
+            Some(directive) if !directive.is_empty() => Ok((base, Some(directive))),
+            _ => Err(LogControl1Error::UnsupportedLogTarget(target.to_string())),
+        },
+    }
+}
+
+/// Parse a filter `directive` embedded in a target.
+fn parse_filter_directive(directive: &str) -> Result<EnvFilter, LogControl1Error> {
+    EnvFilter::try_new(directive).map_err(|error| {
+        LogControl1Error::Failure(format!("Invalid filter directive {directive}: {error}"))
+    })
+}
+
+/// Parse a `target` given to [`TracingLogControl1`].
+///
+/// Support the custom [`CONSOLE_AND_JOURNAL_TARGET`] in addition to known targets.
+/// Ignore leading and trailing whitespace, which `systemctl` passes through.
+fn parse_log_target(
+    target: &str,
+    connected_to_journal: bool,
+) -> Result<TracingLogTarget, LogControl1Error> {
+    let target = target.trim();
+    if target == CONSOLE_AND_JOURNAL_TARGET {
+        Ok(TracingLogTarget::ConsoleAndJournal)
+    } else {
+        from_known_log_target(KnownLogTarget::try_from(target)?, connected_to_journal)
+    }
+}
+
 /// Convert [`logcontrol::LogLevel`] to [`tracing::Level`].
 ///
 /// Return an error if the systemd log level is not supported, i.e. does not map to a
@@ -93,6 +207,32 @@ pub fn from_log_level(level: LogLevel) -> Result<tracing::Level, LogControl1Erro
     }
 }
 
+/// Convert [`logcontrol::LogLevel`] to [`LevelFilter`].
+///
+/// Map levels like [`from_log_level`], but saturate the levels above
+/// [`LogLevel::Err`] which have no corresponding [`tracing::Level`]: emerg,
+/// alert, and crit all map to [`LevelFilter::ERROR`], which is the least
+/// verbose filter that still lets events at these levels through.
+pub fn level_filter_from_log_level(level: LogLevel) -> LevelFilter {
+    from_log_level(level).map_or(LevelFilter::ERROR, LevelFilter::from_level)
+}
+
+/// Get the name of the tracing level for `level` in [`EnvFilter`] directives.
+///
+/// Map levels like [`from_log_level`], and return the lowercase name of the
+/// corresponding [`tracing::Level`], e.g. `debug` for [`LogLevel::Info`], to
+/// build directives such as `my_crate=debug`.  Return `None` if `level` is not
+/// supported.
+pub fn directive_level(level: LogLevel) -> Option<&'static str> {
+    match from_log_level(level).ok()? {
+        tracing::Level::ERROR => Some("error"),
+        tracing::Level::WARN => Some("warn"),
+        tracing::Level::INFO => Some("info"),
+        tracing::Level::DEBUG => Some("debug"),
+        tracing::Level::TRACE => Some("trace"),
+    }
+}
+
 /// Convert [`tracing::Level`] to [`logcontrol::LogLevel`].
 fn to_log_level(level: tracing::Level) -> LogLevel {
     match level {
@@ -104,6 +244,65 @@ fn to_log_level(level: tracing::Level) -> LogLevel {
     }
 }
 
+/// Lock the last level set through a control.
+///
+/// Controls never panic while holding the lock, so ignore poisoning.
+fn lock_level(level: &Mutex<LogLevel>) -> MutexGuard<'_, LogLevel> {
+    level.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Get the current level of the level layer behind `handle`.
+///
+/// tracing has no levels above [`tracing::Level::ERROR`], so emerg, alert, and
+/// crit all filter at `ERROR`.  Return the `requested` level, i.e. the last
+/// level set through the control, as long as the layer filters at this level,
+/// so that these levels read back as they were set.  If the layer filters at
+/// another level it was reloaded elsewhere; return its level then.  If the
+/// layer was dropped, or filters everything, return `requested` as well.
+fn current_level<S>(handle: &reload::Handle<LevelFilter, S>, requested: LogLevel) -> LogLevel {
+    match handle.with_current(|filter| *filter) {
+        Ok(filter) if filter == level_filter_from_log_level(requested) => requested,
+        Ok(filter) => filter.into_level().map_or(requested, to_log_level),
+        Err(_) => requested,
+    }
+}
+
+/// Reload the level layer behind `handle` to `level`, and remember `level`.
+///
+/// If `muted` only remember `level`, and leave the layer filtering
+/// everything; [`reload_muted`] applies the level once unmuted.
+fn reload_level<S>(
+    handle: &reload::Handle<LevelFilter, S>,
+    requested: &Mutex<LogLevel>,
+    muted: bool,
+    level: LogLevel,
+) -> Result<(), LogControl1Error> {
+    let mut requested = lock_level(requested);
+    if !muted {
+        handle.set_reload_level(level)?;
+    }
+    *requested = level;
+    Ok(())
+}
+
+/// Mute or unmute the level layer behind `handle`.
+///
+/// Mute by filtering everything, and unmute by reloading the `requested`
+/// level.
+fn reload_muted<S>(
+    handle: &reload::Handle<LevelFilter, S>,
+    requested: &Mutex<LogLevel>,
+    muted: bool,
+) -> Result<(), LogControl1Error> {
+    if muted {
+        handle.reload(LevelFilter::OFF).map_err(|error| {
+            LogControl1Error::reload_failed("Failed to reload level layer to mute logging", error)
+        })
+    } else {
+        handle.set_reload_level(*lock_level(requested))
+    }
+}
+
 /// A factory to create layers for [`TracingLogControl1`].
 pub trait LogControl1LayerFactory {
     /// The type of the layer to use for [`KnownLogTarget::Journal`].
@@ -124,6 +323,14 @@ pub trait LogControl1LayerFactory {
     fn create_console_layer<S: Subscriber + for<'span> LookupSpan<'span>>(
         &self,
     ) -> Result<Self::ConsoleLayer<S>, LogControl1Error>;
+
+    /// Whether journal layers created by this factory can reach the journal.
+    ///
+    /// The default implementation checks the journald socket with
+    /// [`logcontrol::journal_reachable`].
+    fn journal_reachable(&self) -> bool {
+        logcontrol::journal_reachable()
+    }
 }
 
 /// A layer factory which uses pretty printing on stdout for the console target.
@@ -133,6 +340,7 @@ pub trait LogControl1LayerFactory {
 ///
 /// For [`KnownLogTarget::Journal`] this layer factory creates a [`tracing_journald`]
 /// layer without field prefixes and no further customization.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct PrettyLogControl1LayerFactory;
 
 impl LogControl1LayerFactory for PrettyLogControl1LayerFactory {
@@ -157,16 +365,300 @@ impl LogControl1LayerFactory for PrettyLogControl1LayerFactory {
     }
 }
 
+/// Prefix every line of formatted events with the journal priority of the event.
+///
+/// When stdout or stderr of a service goes to the journal, journald reads a
+/// `<N>` prefix at the start of each line as the syslog priority `N` of the
+/// line, see [`sd-daemon(3)`](https://www.freedesktop.org/software/systemd/man/sd-daemon.html).
+/// This event format writes this prefix in front of every line of the output
+/// of the inner format `F`, with the priority from [`journal::default_priority`],
+/// so that console output ends up in the journal with the right priorities.
+///
+/// Prefixed output has no ANSI colours.  If disabled, this format just uses
+/// the inner format.
+#[derive(Debug, Clone)]
+pub struct PriorityPrefix<F> {
+    inner: F,
+    enabled: bool,
+}
+
+impl<F> PriorityPrefix<F> {
+    /// Prefix lines formatted by `inner` if `enabled`.
+    pub fn new(inner: F, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for PriorityPrefix<F>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    N: for<'writer> FormatFields<'writer> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        if !self.enabled {
+            return self.inner.format_event(ctx, writer, event);
+        }
+        let mut buffer = String::new();
+        self.inner
+            .format_event(ctx, Writer::new(&mut buffer), event)?;
+        let priority = journal::default_priority(*event.metadata().level());
+        for line in buffer.lines() {
+            writeln!(writer, "<{priority}>{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A configurable layer factory.
+///
+/// For [`KnownLogTarget::Console`] this layer factory creates a [`mod@tracing_subscriber::fmt`]
+/// layer which logs to stdout with the built-in pretty format, just like
+/// [`PrettyLogControl1LayerFactory`].  The console layer formats timestamps
+/// with the timer `T`, see [`Self::with_timer`], and optionally prefixes lines
+/// with journal priorities, see [`Self::with_console_priority_prefix`].
+///
+/// For [`KnownLogTarget::Journal`] this layer factory creates a [`journal::JournalLayer`]
+/// configured by the given [`journal::JournalLayerFactory`].
+#[derive(Debug, Clone)]
+pub struct ConfigurableLogControl1LayerFactory<T = fmt::time::SystemTime> {
+    journal: journal::JournalLayerFactory,
+    timer: T,
+    console_priority_prefix: bool,
+}
+
+impl Default for ConfigurableLogControl1LayerFactory {
+    fn default() -> Self {
+        Self {
+            journal: journal::JournalLayerFactory::default(),
+            timer: fmt::time::SystemTime,
+            console_priority_prefix: false,
+        }
+    }
+}
+
+impl<T> ConfigurableLogControl1LayerFactory<T> {
+    /// Use the given factory to create layers for the journal target.
+    pub fn with_journal(mut self, journal: journal::JournalLayerFactory) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Use the given `timer` to format timestamps on the console.
+    ///
+    /// Defaults to [`fmt::time::SystemTime`], which formats the current time
+    /// in RFC 3339 format in UTC.
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::ConfigurableLogControl1LayerFactory;
+    ///
+    /// // Log the time since the process started instead of the current time.
+    /// let factory = ConfigurableLogControl1LayerFactory::default()
+    ///     .with_timer(tracing_subscriber::fmt::time::Uptime::default());
+    /// ```
+    pub fn with_timer<T2: FormatTime + Clone + Send + Sync + 'static>(
+        self,
+        timer: T2,
+    ) -> ConfigurableLogControl1LayerFactory<T2> {
+        ConfigurableLogControl1LayerFactory {
+            journal: self.journal,
+            timer,
+            console_priority_prefix: self.console_priority_prefix,
+        }
+    }
+
+    /// Whether to prefix lines on the console with journal priorities.
+    ///
+    /// Defaults to `false`.  See [`PriorityPrefix`].  Enable this if the
+    /// console goes to the journal, so that the journal gets the priorities of
+    /// events even with the console target:
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::ConfigurableLogControl1LayerFactory;
+    ///
+    /// let factory = ConfigurableLogControl1LayerFactory::default()
+    ///     .with_console_priority_prefix(logcontrol_tracing::stderr_connected_to_journal());
+    /// ```
+    pub fn with_console_priority_prefix(mut self, console_priority_prefix: bool) -> Self {
+        self.console_priority_prefix = console_priority_prefix;
+        self
+    }
+}
+
+impl<T> LogControl1LayerFactory for ConfigurableLogControl1LayerFactory<T>
+where
+    T: FormatTime + Clone + Send + Sync + 'static,
+{
+    type JournalLayer<S: Subscriber + for<'span> LookupSpan<'span>> = journal::JournalLayer;
+
+    type ConsoleLayer<S: Subscriber + for<'span> LookupSpan<'span>> = fmt::Layer<
+        S,
+        fmt::format::Pretty,
+        PriorityPrefix<fmt::format::Format<fmt::format::Pretty, T>>,
+    >;
+
+    fn create_journal_layer<S: Subscriber + for<'span> LookupSpan<'span>>(
+        &self,
+        syslog_identifier: String,
+    ) -> Result<Self::JournalLayer<S>, LogControl1Error> {
+        self.journal.create_layer(syslog_identifier)
+    }
+
+    fn create_console_layer<S: Subscriber + for<'span> LookupSpan<'span>>(
+        &self,
+    ) -> Result<Self::ConsoleLayer<S>, LogControl1Error> {
+        let prefix = self.console_priority_prefix;
+        Ok(tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_timer(self.timer.clone())
+            .map_event_format(|format| PriorityPrefix::new(format, prefix)))
+    }
+
+    fn journal_reachable(&self) -> bool {
+        self.journal.is_reachable()
+    }
+}
+
+/// A level which can be reloaded through a shared reference.
+///
+/// This trait lets code which coordinates multiple reloadable components,
+/// e.g. different filters, change the level of all of them uniformly.  It is
+/// implemented for the [`reload::Handle`] of a [`LevelFilter`], for
+/// [`TracingLogControl1`], and for [`LevelOnlyTracingLogControl1`].
+pub trait ReloadableLevel {
+    /// Reload the level to the given `level`.
+    ///
+    /// Return [`LogControl1Error::UnsupportedLogLevel`] if `level` is not
+    /// supported, and [`LogControl1Error::ReloadFailed`] if reloading fails.
+    fn set_reload_level(&self, level: LogLevel) -> Result<(), LogControl1Error>;
+}
+
+/// Reload the level filter of a reload layer.
+///
+/// Accept all levels, and map levels like [`level_filter_from_log_level`], i.e.
+/// emerg, alert, and crit all filter at [`tracing::Level::ERROR`].
+impl<S> ReloadableLevel for reload::Handle<LevelFilter, S> {
+    fn set_reload_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.reload(level_filter_from_log_level(level))
+            .map_err(|error| {
+                LogControl1Error::reload_failed(
+                    format!("Failed to reload level layer to switch to log level {level}"),
+                    error,
+                )
+            })
+    }
+}
+
 /// The type of the layer that implements the log target.
+///
+/// The outermost layer is the optional [`EnvFilter`] of a target with a
+/// filter directive, see [`TracingLogControl1`], on top of the [`CaptureLayer`]
+/// of the control.
 pub type LogTargetLayer<F, S> = Layered<
-    Option<<F as LogControl1LayerFactory>::ConsoleLayer<S>>,
-    Option<<F as LogControl1LayerFactory>::JournalLayer<S>>,
+    Option<EnvFilter>,
+    Layered<
+        CaptureLayer,
+        Layered<
+            Option<<F as LogControl1LayerFactory>::ConsoleLayer<S>>,
+            Option<<F as LogControl1LayerFactory>::JournalLayer<S>>,
+            S,
+        >,
+        S,
+    >,
     S,
 >;
 
+/// Create an empty target layer, which discards all events.
+///
+/// Use this layer as initial layer for [`TracingLogControl1::from_handles`].
+pub fn empty_target_layer<F, S>() -> LogTargetLayer<F, S>
+where
+    F: LogControl1LayerFactory,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    Layer::and_then(
+        Layer::and_then(Layer::and_then(None, None), CaptureLayer::default()),
+        None,
+    )
+}
+
 /// The final type for the layer that implements the log control interface.
+///
+/// The level filter is the outermost layer, so that it always determines the
+/// maximum level of the subscriber, regardless of the current log target.
 pub type LogControl1Layer<F, S> =
-    Layered<reload::Layer<LogTargetLayer<F, S>, S>, reload::Layer<LevelFilter, S>, S>;
+    Layered<reload::Layer<LevelFilter, S>, reload::Layer<LogTargetLayer<F, S>, S>, S>;
+
+/// The type of the layer returned from [`TracingLogControl1::new_with_env_filter`].
+///
+/// The [`EnvFilter`] is the outermost layer, on top of the [`LogControl1Layer`].
+pub type EnvFilterLogControl1Layer<F, S> =
+    Layered<reload::Layer<EnvFilter, S>, LogControl1Layer<F, S>, S>;
+
+/// An [`EnvFilter`] whose default directive follows the level of a control.
+struct SyncedEnvFilter<S> {
+    /// The directives of the original filter.
+    directives: String,
+    /// A handle to reload the filter.
+    handle: reload::Handle<EnvFilter, S>,
+}
+
+impl<S> SyncedEnvFilter<S> {
+    /// Reload the filter with `level` as default directive.
+    ///
+    /// Keep all directives of the original filter, but add `level` as the last
+    /// directive, which overrides the default directive of the original
+    /// filter, if any.
+    fn set_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+        let default = level_filter_from_log_level(level);
+        let directives = if self.directives.is_empty() {
+            default.to_string()
+        } else {
+            format!("{},{default}", self.directives)
+        };
+        let filter = parse_filter_directive(&directives)?;
+        self.handle.reload(filter).map_err(|error| {
+            LogControl1Error::reload_failed(
+                format!("Failed to reload environment filter to switch to log level {level}"),
+                error,
+            )
+        })
+    }
+}
+
+/// Build a complete subscriber around the `layer` of a [`TracingLogControl1`].
+///
+/// `layer` is the [`LogControl1Layer`] returned from [`TracingLogControl1::new`].
+/// Add `layer` to a default [`Registry`], and add the optional `env_filter` on
+/// top.  The `env_filter` applies in addition to the level of the log control,
+/// i.e. an event must pass both to get logged.
+///
+/// The returned subscriber is ready to be installed as global default:
+///
+/// ```rust
+/// use logcontrol_tracing::*;
+///
+/// let (control, layer) = TracingLogControl1::new_auto(
+///     PrettyLogControl1LayerFactory,
+///     tracing::Level::INFO,
+/// ).unwrap();
+/// let env_filter = tracing_subscriber::EnvFilter::new("info,hyper=warn");
+/// tracing::subscriber::set_global_default(build_subscriber(layer, Some(env_filter))).unwrap();
+/// ```
+pub fn build_subscriber<L>(
+    layer: L,
+    env_filter: Option<EnvFilter>,
+) -> impl Subscriber + for<'span> LookupSpan<'span> + Send + Sync
+where
+    L: Layer<Registry> + Send + Sync,
+{
+    Registry::default().with(layer).with(env_filter)
+}
 
 /// Create a new tracing layer for the given `target`, using the given `factory`.
 ///
@@ -174,26 +666,43 @@ pub type LogControl1Layer<F, S> =
 /// simply because it matches none of the other targets, so we automatically
 /// create an empty layer here.
 ///
-/// Return any error returned from the factory methods.
+/// Add an [`EnvFilter`] for the given filter `directive` on top.
+///
+/// Return any error returned from the factory methods, or if `directive` is
+/// invalid.
 fn make_target_layer<F: LogControl1LayerFactory, S>(
     factory: &F,
+    capture: &CaptureLayer,
     target: TracingLogTarget,
     syslog_identifier: &str,
+    directive: Option<&str>,
 ) -> Result<LogTargetLayer<F, S>, LogControl1Error>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
 {
-    let stdout = if let TracingLogTarget::Console = target {
+    let stdout = if target.has_console() {
         Some(factory.create_console_layer::<S>()?)
     } else {
         None
     };
-    let journal = if let TracingLogTarget::Journal = target {
+    let journal = if target.has_journal() {
         Some(factory.create_journal_layer::<S>(syslog_identifier.to_string())?)
     } else {
         None
     };
-    Ok(tracing_subscriber::Layer::and_then(journal, stdout))
+    let filter = directive.map(parse_filter_directive).transpose()?;
+    Ok(Layer::and_then(
+        Layer::and_then(Layer::and_then(journal, stdout), capture.clone()),
+        filter,
+    ))
+}
+
+/// A filter directive appended to the current target.
+struct TargetFilter {
+    /// The complete target, as reported by [`LogControl1::target`].
+    target: String,
+    /// The [`EnvFilter`] directive.
+    directive: String,
 }
 
 /// A [`LogControl1`] implementation for [`tracing`].
@@ -210,7 +719,36 @@ where
 /// - [`KnownLogTarget::Null`]
 /// - [`KnownLogTarget::Auto`]
 ///
+/// Additionally, it supports the custom [`CONSOLE_AND_JOURNAL_TARGET`] which
+/// combines the console and the journal layer.
+///
 /// Any other target fails with [`LogControl1Error::UnsupportedLogTarget`].
+///
+/// Each target can carry an additional [`EnvFilter`] directive, in the form
+/// `<target>?filter=<directive>`, e.g. to log debug messages only for requests
+/// of a specific tenant:
+///
+/// ```console
+/// # systemctl service-log-level my-service.service info
+/// # systemctl service-log-target my-service.service 'journal?filter=info,[{tenant=foo}]=debug'
+/// ```
+///
+/// The filter applies on top of the level, i.e. an event must pass both to get
+/// logged; the level `info` above translates to the tracing level `DEBUG`.
+/// The filter filters globally, like the level; it only sees spans created
+/// after it was set.  An invalid directive fails with
+/// [`LogControl1Error::Failure`] and leaves the current target in place.
+///
+/// The level and the target are independent of each other:
+/// [`LogControl1::set_level`] only reloads the level filter, and
+/// [`LogControl1::set_target`] only reloads the target layer, so changing the
+/// target never disturbs the level, and vice versa.  The only exception are
+/// targets with a default level, see [`Self::with_target_default_level`].
+/// Each reload swaps the respective layer atomically; events emitted concurrently see either the old
+/// or the new layer, but never a partially configured one.  If a change fails
+/// the previous level or target remains in place.  Since both setters take
+/// `&mut self`, changes are applied in the order of calls; wrap the control in
+/// a [`std::sync::Mutex`] to change it from multiple threads.
 pub struct TracingLogControl1<F, S>
 where
     F: LogControl1LayerFactory,
@@ -220,16 +758,34 @@ where
     connected_to_journal: bool,
     /// The syslog identifier used for logging.
     syslog_identifier: String,
-    /// The current level active in the level layer.
-    level: tracing::Level,
+    /// The last level set through this control, see [`current_level`].
+    level: Mutex<LogLevel>,
+    /// Whether the level layer filters everything, see [`reload_muted`].
+    muted: bool,
     /// The current target active in the target layer.
     target: TracingLogTarget,
+    /// The filter directive of the current target, if any.
+    filter: Option<TargetFilter>,
+    /// Whether [`KnownLogTarget::Auto`] selected the current target.
+    auto_detected: bool,
+    /// What to do if an unsupported target is requested.
+    unsupported_target_policy: UnsupportedTargetPolicy,
+    /// Levels to apply when switching to a target.
+    target_default_levels: HashMap<KnownLogTarget, LogLevel>,
+    /// Targets to try in order if switching to a target fails.
+    target_fallback_chain: Vec<KnownLogTarget>,
+    /// Whether to emit an event after the configuration changed.
+    change_events: bool,
     /// Factory for layers.
     layer_factory: F,
     // /// A handle to reload the level layer in order to change the level.
     level_handle: reload::Handle<LevelFilter, S>,
     // /// A handle to reload the target layer in order to change the target.
     target_handle: reload::Handle<LogTargetLayer<F, S>, S>,
+    /// The capture layer to add to every target layer.
+    capture: CaptureLayer,
+    /// The environment filter to keep in sync with the level, if any.
+    env_filter: Option<SyncedEnvFilter<S>>,
 }
 
 impl<F, S> TracingLogControl1<F, S>
@@ -265,24 +821,241 @@ where
         level: tracing::Level,
     ) -> Result<(Self, LogControl1Layer<F, S>), LogControl1Error> {
         let tracing_target = from_known_log_target(target, connected_to_journal)?;
-        let (target_layer, target_handle) = reload::Layer::new(make_target_layer(
-            &factory,
+        let capture = CaptureLayer::default();
+        let target_layer =
+            make_target_layer(&factory, &capture, tracing_target, &syslog_identifier, None)?;
+        Ok(Self::from_target_layer(
+            factory,
+            connected_to_journal,
+            syslog_identifier,
+            target,
+            tracing_target,
+            (target_layer, capture),
+            level,
+        ))
+    }
+
+    /// Create a new layer controlled through the log interface, with the first working target of `chain`.
+    ///
+    /// Try each target of `chain` in order as initial target, and start with
+    /// the first target for which creating a layer succeeds, e.g. to prefer
+    /// the journal, then syslog, and finally fall back to the console.  The
+    /// control then uses `chain` as fallback chain for [`LogControl1::set_target`],
+    /// see [`Self::with_target_fallback_chain`].
+    ///
+    /// Return the error for the first target if all targets in `chain` fail,
+    /// and [`LogControl1Error::Failure`] if `chain` is empty.  For all other
+    /// arguments see [`Self::new`].
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::*;
+    ///
+    /// let (control, _) = TracingLogControl1::<_, tracing_subscriber::Registry>::new_with_target_fallback_chain(
+    ///     PrettyLogControl1LayerFactory,
+    ///     false,
+    ///     "example".to_string(),
+    ///     vec![KnownLogTarget::Syslog, KnownLogTarget::Console],
+    ///     tracing::Level::INFO,
+    /// )
+    /// .unwrap();
+    /// // This crate does not support syslog, so the control falls back to the console
+    /// assert_eq!(control.target(), "console");
+    /// ```
+    pub fn new_with_target_fallback_chain(
+        factory: F,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        chain: Vec<KnownLogTarget>,
+        level: tracing::Level,
+    ) -> Result<(Self, LogControl1Layer<F, S>), LogControl1Error> {
+        let capture = CaptureLayer::default();
+        let (target, (tracing_target, target_layer)) =
+            logcontrol::first_working_target(&chain, |target| {
+                let tracing_target = from_known_log_target(target, connected_to_journal)?;
+                let target_layer = make_target_layer(
+                    &factory,
+                    &capture,
+                    tracing_target,
+                    &syslog_identifier,
+                    None,
+                )?;
+                Ok((tracing_target, target_layer))
+            })?;
+        let (control, control_layer) = Self::from_target_layer(
+            factory,
+            connected_to_journal,
+            syslog_identifier,
+            target,
             tracing_target,
-            &syslog_identifier,
-        )?);
+            (target_layer, capture),
+            level,
+        );
+        Ok((control.with_target_fallback_chain(chain), control_layer))
+    }
+
+    /// Create a new control around the given initial `target_layer`.
+    fn from_target_layer(
+        factory: F,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        target: KnownLogTarget,
+        tracing_target: TracingLogTarget,
+        (target_layer, capture): (LogTargetLayer<F, S>, CaptureLayer),
+        level: tracing::Level,
+    ) -> (Self, LogControl1Layer<F, S>) {
+        let (target_layer, target_handle) = reload::Layer::new(target_layer);
         let (level_layer, level_handle) = reload::Layer::new(LevelFilter::from_level(level));
-        let control_layer = Layer::and_then(level_layer, target_layer);
+        let control_layer = Layer::and_then(target_layer, level_layer);
         let control = Self {
             connected_to_journal,
             layer_factory: factory,
             syslog_identifier,
-            level,
+            level: Mutex::new(to_log_level(level)),
+            muted: false,
             target: tracing_target,
+            filter: None,
+            auto_detected: target == KnownLogTarget::Auto,
+            unsupported_target_policy: UnsupportedTargetPolicy::default(),
+            target_default_levels: HashMap::new(),
+            target_fallback_chain: Vec::new(),
+            change_events: false,
             level_handle,
             target_handle,
+            capture,
+            env_filter: None,
         };
+        (control, control_layer)
+    }
+
+    /// Create a new layer controlled through the log interface, on top of an existing `env_filter`.
+    ///
+    /// An [`EnvFilter`] added with [`build_subscriber`] applies in addition
+    /// to the level of this control, so a filter from `$RUST_LOG=warn` keeps
+    /// debug events from showing, even after changing the level of this
+    /// control to debug.  Instead, this function puts `env_filter` under the
+    /// control of this control: whenever the level changes, replace the
+    /// default directive of `env_filter` with the new level, and keep all
+    /// other directives of `env_filter`.  Targeted directives, e.g.
+    /// `hyper=warn`, thus still apply, but the level of this control decides
+    /// about all other targets.
+    ///
+    /// Return the control and a layer with `env_filter` on top of the
+    /// [`LogControl1Layer`].  For all other arguments and for errors see
+    /// [`Self::new`].
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::*;
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// let env_filter = tracing_subscriber::EnvFilter::new("warn,hyper=error");
+    /// let (mut control, layer) = TracingLogControl1::new_with_env_filter(
+    ///     PrettyLogControl1LayerFactory,
+    ///     env_filter,
+    ///     false,
+    ///     "example".to_string(),
+    ///     KnownLogTarget::Console,
+    ///     tracing::Level::INFO,
+    /// )
+    /// .unwrap();
+    /// let subscriber = tracing_subscriber::Registry::default().with(layer);
+    /// // Now debug events show, except for hyper
+    /// control.set_level(LogLevel::Info).unwrap();
+    /// ```
+    pub fn new_with_env_filter(
+        factory: F,
+        env_filter: EnvFilter,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        target: KnownLogTarget,
+        level: tracing::Level,
+    ) -> Result<(Self, EnvFilterLogControl1Layer<F, S>), LogControl1Error> {
+        let (mut control, control_layer) = Self::new(
+            factory,
+            connected_to_journal,
+            syslog_identifier,
+            target,
+            level,
+        )?;
+        let directives = env_filter.to_string();
+        let (env_filter_layer, handle) = reload::Layer::new(env_filter);
+        let env_filter = SyncedEnvFilter { directives, handle };
+        env_filter.set_level(to_log_level(level))?;
+        control.env_filter = Some(env_filter);
+        Ok((control, Layer::and_then(control_layer, env_filter_layer)))
+    }
 
-        Ok((control, control_layer))
+    /// Create a new control which drives existing reload layers.
+    ///
+    /// Unlike [`Self::new`] this function does not create layers; instead it
+    /// adopts the given `level_handle` and `target_handle` of reload layers
+    /// which the application created and added to its subscriber itself.
+    /// Combine both layers with [`Layer::and_then`] such that the level layer
+    /// is the outer layer, see [`LogControl1Layer`].
+    ///
+    /// The target layer can initially be empty: this function immediately
+    /// reloads both layers according to the given `target` and `level`.  For
+    /// all other arguments and for errors see [`Self::new`].
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::*;
+    /// use tracing_subscriber::filter::LevelFilter;
+    /// use tracing_subscriber::prelude::*;
+    /// use tracing_subscriber::{reload, Registry};
+    ///
+    /// let (target_layer, target_handle) =
+    ///     reload::Layer::new(empty_target_layer::<PrettyLogControl1LayerFactory, Registry>());
+    /// let (level_layer, level_handle) = reload::Layer::new(LevelFilter::OFF);
+    /// let subscriber = Registry::default().with(target_layer.and_then(level_layer));
+    ///
+    /// let control = TracingLogControl1::from_handles(
+    ///     PrettyLogControl1LayerFactory,
+    ///     false,
+    ///     "example".to_string(),
+    ///     KnownLogTarget::Console,
+    ///     tracing::Level::INFO,
+    ///     level_handle,
+    ///     target_handle,
+    /// ).unwrap();
+    /// ```
+    pub fn from_handles(
+        factory: F,
+        connected_to_journal: bool,
+        syslog_identifier: String,
+        target: KnownLogTarget,
+        level: tracing::Level,
+        level_handle: reload::Handle<LevelFilter, S>,
+        target_handle: reload::Handle<LogTargetLayer<F, S>, S>,
+    ) -> Result<Self, LogControl1Error> {
+        let tracing_target = from_known_log_target(target, connected_to_journal)?;
+        let capture = CaptureLayer::default();
+        let target_layer =
+            make_target_layer(&factory, &capture, tracing_target, &syslog_identifier, None)?;
+        target_handle.reload(target_layer).map_err(|error| {
+            LogControl1Error::reload_failed("Failed to reload target layer", error)
+        })?;
+        level_handle
+            .reload(LevelFilter::from_level(level))
+            .map_err(|error| {
+                LogControl1Error::reload_failed("Failed to reload level layer", error)
+            })?;
+        Ok(Self {
+            connected_to_journal,
+            layer_factory: factory,
+            syslog_identifier,
+            level: Mutex::new(to_log_level(level)),
+            muted: false,
+            target: tracing_target,
+            filter: None,
+            auto_detected: target == KnownLogTarget::Auto,
+            unsupported_target_policy: UnsupportedTargetPolicy::default(),
+            target_default_levels: HashMap::new(),
+            target_fallback_chain: Vec::new(),
+            change_events: false,
+            level_handle,
+            target_handle,
+            capture,
+            env_filter: None,
+        })
     }
 
     /// Create a new layer controlled through the log interface, with automatic defaults.
@@ -293,78 +1066,1743 @@ where
     ///
     /// `level` denotes the initial level; for `factory` and returned errors,
     ///  see [`Self::new`].
+    ///
+    /// Like systemd's own tools, take the initial level and target from
+    /// `$SYSTEMD_LOG_LEVEL` and `$SYSTEMD_LOG_TARGET` if set, see
+    /// [`logcontrol::log_level_from_systemd_env()`] and
+    /// [`logcontrol::log_target_from_systemd_env()`].  Ignore unsupported
+    /// targets in `$SYSTEMD_LOG_TARGET`, and use the most severe supported
+    /// level for unsupported levels in `$SYSTEMD_LOG_LEVEL`.
     pub fn new_auto(
         factory: F,
         level: tracing::Level,
     ) -> Result<(Self, LogControl1Layer<F, S>), LogControl1Error> {
+        let connected_to_journal = logcontrol::stderr_connected_to_journal();
+        let level = logcontrol::log_level_from_systemd_env().map_or(level, |level| {
+            from_log_level(level).unwrap_or(tracing::Level::ERROR)
+        });
+        let target = logcontrol::log_target_from_systemd_env()
+            .filter(|target| from_known_log_target(*target, connected_to_journal).is_ok())
+            .unwrap_or(KnownLogTarget::Auto);
         Self::new(
             factory,
-            logcontrol::stderr_connected_to_journal(),
+            connected_to_journal,
             logcontrol::syslog_identifier(),
-            KnownLogTarget::Auto,
+            target,
             level,
         )
     }
-}
 
-impl<F, S> LogControl1 for TracingLogControl1<F, S>
-where
-    F: LogControl1LayerFactory,
-    S: Subscriber + for<'span> LookupSpan<'span>,
-{
-    fn level(&self) -> LogLevel {
-        to_log_level(self.level)
+    /// Emit an event which describes the current configuration.
+    ///
+    /// Describe the target, the level, the syslog identifier, and whether the
+    /// target was automatically detected, at the current level, to help
+    /// debugging the logging setup itself.  See
+    /// [`logcontrol::describe_configuration`] for the message.
+    ///
+    /// Services typically call this once after installing the subscriber with
+    /// the control layer; the event goes to the current default subscriber.
+    pub fn log_startup_message(&self) {
+        let message = logcontrol::describe_configuration(self, self.auto_detected);
+        self.log_at_current_level(&message, None);
     }
 
-    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
-        let tracing_level = from_log_level(level)?;
+    /// Emit `message` at the current level, with an optional journal `message_id`.
+    fn log_at_current_level(&self, message: &str, message_id: Option<&str>) {
+        match from_log_level(self.level()).unwrap_or(tracing::Level::ERROR) {
+            tracing::Level::ERROR => tracing::error!(message_id, "{message}"),
+            tracing::Level::WARN => tracing::warn!(message_id, "{message}"),
+            tracing::Level::INFO => tracing::info!(message_id, "{message}"),
+            tracing::Level::DEBUG => tracing::debug!(message_id, "{message}"),
+            tracing::Level::TRACE => tracing::trace!(message_id, "{message}"),
+        }
+    }
+
+    /// Run `change` and emit a change event if it succeeds, see [`Self::with_change_events`].
+    ///
+    /// Nested changes, e.g. the default level applied when switching targets,
+    /// emit no event of their own.
+    fn with_change_event(
+        &mut self,
+        change: impl FnOnce(&mut Self) -> Result<(), LogControl1Error>,
+    ) -> Result<(), LogControl1Error> {
+        let change_events = std::mem::replace(&mut self.change_events, false);
+        let result = change(self);
+        self.change_events = change_events;
+        if change_events && result.is_ok() {
+            let message = logcontrol::describe_configuration(self, self.auto_detected);
+            self.log_at_current_level(
+                &format!("Log configuration changed, {message}"),
+                Some(logcontrol::CONFIGURATION_CHANGED_MESSAGE_ID),
+            );
+        }
+        result
+    }
+
+    /// Get the current filter of the level layer as a directive.
+    ///
+    /// Return the level filter as string, e.g. `debug`, in the syntax of
+    /// [`EnvFilter`] directives, for display in status pages and the like.
+    /// Like [`LogControl1::level`] this reflects levels changed through
+    /// [`ReloadableLevel::set_reload_level`].
+    ///
+    /// This control only has a single level for all targets; an [`EnvFilter`]
+    /// passed to [`build_subscriber`] applies on top of this level, and is not
+    /// included in the directive.
+    pub fn level_directive(&self) -> String {
         self.level_handle
-            .reload(LevelFilter::from_level(tracing_level))
-            .map_err(|error| {
-                LogControl1Error::Failure(format!(
-                    "Failed to reload target layer to switch to log target {level}: {error}"
-                ))
-            })?;
-        self.level = tracing_level;
-        Ok(())
+            .with_current(ToString::to_string)
+            .unwrap_or_else(|_| level_filter_from_log_level(*lock_level(&self.level)).to_string())
     }
 
-    fn target(&self) -> &str {
-        KnownLogTarget::from(self.target).as_str()
+    /// Get the numeric syslog priority of the current level.
+    ///
+    /// Return [`LogLevel::as_priority`] of [`LogControl1::level`], i.e. the
+    /// largest journal priority which currently passes the level layer, for
+    /// services which also write to the journal by other means.  Like
+    /// [`LogControl1::level`] this reflects levels changed through
+    /// [`ReloadableLevel::set_reload_level`].
+    pub fn current_priority(&self) -> u8 {
+        self.level().as_priority()
     }
 
-    fn set_target<T: AsRef<str>>(&mut self, target: T) -> Result<(), LogControl1Error> {
-        let new_tracing_target = from_known_log_target(
-            KnownLogTarget::try_from(target.as_ref())?,
-            self.connected_to_journal,
-        )?;
+    /// Change the syslog identifier.
+    ///
+    /// If the current target includes the journal, create a new target layer
+    /// with the new `syslog_identifier`, so that all subsequent events carry
+    /// the new identifier.  Otherwise just remember the identifier for the
+    /// next switch to a target with the journal.
+    ///
+    /// Return an error if creating or reloading the new layer fails; the
+    /// previous identifier remains in place then.
+    pub fn set_syslog_identifier(
+        &mut self,
+        syslog_identifier: String,
+    ) -> Result<(), LogControl1Error> {
+        if self.target.has_journal() {
+            let new_layer = make_target_layer(
+                &self.layer_factory,
+                &self.capture,
+                self.target,
+                &syslog_identifier,
+                self.filter.as_ref().map(|filter| filter.directive.as_str()),
+            )?;
+            self.target_handle.reload(new_layer).map_err(|error| {
+                LogControl1Error::reload_failed(
+                    format!(
+                        "Failed to reload target layer to change syslog identifier to {syslog_identifier}"
+                    ),
+                    error,
+                )
+            })?;
+        }
+        self.syslog_identifier = syslog_identifier;
+        Ok(())
+    }
+
+    /// Change the policy for unsupported log targets.
+    ///
+    /// By default [`LogControl1::set_target`] fails for unsupported targets;
+    /// see [`UnsupportedTargetPolicy`] for alternatives.  With
+    /// [`UnsupportedTargetPolicy::KeepCurrent`] the warning goes to the current
+    /// default subscriber.
+    pub fn with_unsupported_target_policy(mut self, policy: UnsupportedTargetPolicy) -> Self {
+        self.unsupported_target_policy = policy;
+        self
+    }
+
+    /// Set the default level of `target`.
+    ///
+    /// Whenever [`LogControl1::set_target`] switches to `target`, change the
+    /// level to `level` as well, e.g. to log more verbosely on the console than
+    /// to the journal.  Targets without a default level keep the current level.
+    ///
+    /// The default applies to the target which `set_target` actually selects,
+    /// so [`KnownLogTarget::Auto`] applies the default of the journal or the
+    /// console, and a default for `Auto` itself never applies.  It also
+    /// applies to targets with a filter directive, but not to the initial
+    /// target of this control.
+    ///
+    /// An explicit [`LogControl1::set_level`] overrides the default until the
+    /// next switch to a target with a default level; [`LogControl1::reconfigure`]
+    /// always uses its explicit level.
+    pub fn with_target_default_level(mut self, target: KnownLogTarget, level: LogLevel) -> Self {
+        self.target_default_levels.insert(target, level);
+        self
+    }
+
+    /// Set the chain of targets to fall back to.
+    ///
+    /// If [`LogControl1::set_target`] fails to switch to the requested target,
+    /// try the targets in `chain` in order, skipping the requested target, and
+    /// stay with the first target which works, e.g. to fall back from the
+    /// journal to syslog, and finally to the console.  [`LogControl1::target`]
+    /// then returns the fallback target.
+    ///
+    /// If all targets of the chain fail as well, `set_target` returns the
+    /// error for the requested target, subject to the [`UnsupportedTargetPolicy`]
+    /// of this control.  An empty chain, the default, disables fallbacks.
+    pub fn with_target_fallback_chain(mut self, chain: Vec<KnownLogTarget>) -> Self {
+        self.target_fallback_chain = chain;
+        self
+    }
+
+    /// Emit an event whenever the level or the target changes.
+    ///
+    /// After [`LogControl1::set_level`], [`LogControl1::set_target`], or
+    /// [`LogControl1::reconfigure`] succeeded, emit a single event which
+    /// describes the new configuration, at the new level, with the journal
+    /// `MESSAGE_ID` [`logcontrol::CONFIGURATION_CHANGED_MESSAGE_ID`], to audit
+    /// changes of the logging setup.  Changes through
+    /// [`ReloadableLevel::set_reload_level`] emit no event.
+    ///
+    /// Disabled by default.
+    pub fn with_change_events(mut self, enabled: bool) -> Self {
+        self.change_events = enabled;
+        self
+    }
+
+    /// Change the level until the returned guard drops.
+    ///
+    /// Set `level` now, and restore the current level when the returned
+    /// [`LevelGuard`] goes out of scope, e.g. for temporary debug logging
+    /// around a single operation.  Emit a warning if restoring the level fails.
+    pub fn scoped_level(
+        &mut self,
+        level: LogLevel,
+    ) -> Result<LevelGuard<'_, Self>, LogControl1Error> {
+        LevelGuard::with_restore_error_handler(self, level, |error| {
+            tracing::warn!("Failed to restore log level: {error}");
+        })
+    }
+
+    /// Replace the layer factory, and rebuild the target layer with it.
+    ///
+    /// Create a new target layer with `factory` for the current target and
+    /// filter directive, e.g. to switch output formats at runtime, and use
+    /// `factory` for all further target changes.  If creating the new layer
+    /// fails keep the current factory and layer, and return the error.
+    ///
+    /// Reloading waits for events being recorded by the current target layer
+    /// on other threads, so no event gets lost or split between both layers;
+    /// events after this call go to the new layer.  The old layer gets dropped,
+    /// so layers which buffer output should flush on drop.
+    pub fn set_factory(&mut self, factory: F) -> Result<(), LogControl1Error> {
+        let new_layer = make_target_layer(
+            &factory,
+            &self.capture,
+            self.target,
+            &self.syslog_identifier,
+            self.filter.as_ref().map(|filter| filter.directive.as_str()),
+        )?;
+        self.target_handle.reload(new_layer).map_err(|error| {
+            LogControl1Error::reload_failed(
+                format!(
+                    "Failed to reload target layer to replace the factory of log target {}",
+                    self.target()
+                ),
+                error,
+            )
+        })?;
+        self.layer_factory = factory;
+        Ok(())
+    }
+
+    /// Switch to `target`, or to the first working target of the fallback chain.
+    fn switch_target_with_fallback(&mut self, target: &str) -> Result<(), LogControl1Error> {
+        self.switch_target(target).or_else(|error| {
+            let chain = self
+                .target_fallback_chain
+                .iter()
+                .copied()
+                .filter(|fallback| fallback.as_str() != target.trim())
+                .collect::<Vec<_>>();
+            logcontrol::first_working_target(&chain, |fallback| {
+                self.switch_target(fallback.as_str())
+            })
+            .map(|_| ())
+            .map_err(|_| error)
+        })
+    }
+
+    /// Get the default level of the given target, if any.
+    fn target_default_level(&self, target: TracingLogTarget) -> Option<LogLevel> {
+        let target = KnownLogTarget::try_from(target.as_str()).ok()?;
+        self.target_default_levels.get(&target).copied()
+    }
+
+    /// Switch to the given `target`.
+    fn switch_target(&mut self, target: &str) -> Result<(), LogControl1Error> {
+        let (base_target, directive) = split_target_filter(target)?;
+        let new_tracing_target = parse_log_target(base_target, self.connected_to_journal)?;
+        let default_level = self.target_default_level(new_tracing_target);
         let new_layer = make_target_layer(
             &self.layer_factory,
+            &self.capture,
             new_tracing_target,
             &self.syslog_identifier,
+            directive,
         )?;
         self.target_handle.reload(new_layer).map_err(|error| {
-            LogControl1Error::Failure(format!(
-                "Failed to reload target layer to switch to log target {}: {error}",
-                target.as_ref()
-            ))
+            LogControl1Error::reload_failed(
+                format!("Failed to reload target layer to switch to log target {target}"),
+                error,
+            )
         })?;
         self.target = new_tracing_target;
+        self.filter = directive.map(|directive| TargetFilter {
+            target: format!("{}?filter={directive}", new_tracing_target.as_str()),
+            directive: directive.to_string(),
+        });
+        self.auto_detected = base_target == KnownLogTarget::Auto.as_str();
+        match default_level {
+            Some(level) => self.set_level(level),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<F> TracingLogControl1<F, Registry>
+where
+    F: LogControl1LayerFactory,
+    LogControl1Layer<F, Registry>: Send + Sync + 'static,
+{
+    /// Create a control and install its layer as global default subscriber, with automatic defaults.
+    ///
+    /// Add the layer to a default [`Registry`], and install the registry with
+    /// [`tracing::subscriber::set_global_default`].  Use [`build_subscriber`]
+    /// and install the subscriber yourself to add further layers, e.g. an
+    /// [`EnvFilter`].
+    ///
+    /// See [`Self::new_auto`] for arguments and errors.  Additionally, this
+    /// function fails with [`LogControl1Error::Failure`] if a global default
+    /// subscriber is already set.
+    ///
+    /// ```no_run
+    /// use logcontrol_tracing::*;
+    ///
+    /// let control = TracingLogControl1::install_auto(
+    ///     PrettyLogControl1LayerFactory,
+    ///     tracing::Level::INFO,
+    /// )?;
+    /// control.log_startup_message();
+    /// # Ok::<(), LogControl1Error>(())
+    /// ```
+    pub fn install_auto(factory: F, level: tracing::Level) -> Result<Self, LogControl1Error> {
+        let (control, layer) = Self::new_auto(factory, level)?;
+        tracing::subscriber::set_global_default(Registry::default().with(layer))
+            .map_err(|error| LogControl1Error::Failure(format!("{error}")))?;
+        Ok(control)
+    }
+}
+
+impl<F, S> LogControl1 for TracingLogControl1<F, S>
+where
+    F: LogControl1LayerFactory,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Get the level of the level layer.
+    ///
+    /// The level may also change through [`ReloadableLevel::set_reload_level`],
+    /// so ask the level layer for its current level.  If the layer was dropped
+    /// return the last level set through this control.
+    ///
+    /// emerg, alert, and crit read back as they were set, even though they
+    /// all filter at [`tracing::Level::ERROR`].
+    fn level(&self) -> LogLevel {
+        let requested = *lock_level(&self.level);
+        current_level(&self.level_handle, requested)
+    }
+
+    /// Change the level of the level layer.
+    ///
+    /// Accept all levels; emerg, alert, and crit filter at
+    /// [`tracing::Level::ERROR`], the most severe level of tracing.
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.with_change_event(|control| control.set_reload_level(level))
+    }
+
+    fn target(&self) -> &str {
+        self.filter
+            .as_ref()
+            .map_or(self.target.as_str(), |filter| &filter.target)
+    }
+
+    /// Switch to the given `target`.
+    ///
+    /// If switching fails try the fallback chain of this control, see
+    /// [`TracingLogControl1::with_target_fallback_chain`].  Apply the
+    /// [`UnsupportedTargetPolicy`] of this control if `target` is not
+    /// supported.
+    fn set_target<T: AsRef<str>>(&mut self, target: T) -> Result<(), LogControl1Error> {
+        self.with_change_event(|control| {
+            let result = control.switch_target_with_fallback(target.as_ref());
+            control.unsupported_target_policy.apply(result, |target| {
+                tracing::warn!("Log target {target} not supported, keeping current log target");
+            })
+        })
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        &self.syslog_identifier
+    }
+
+    /// Change the level and the target together.
+    ///
+    /// This control supports all levels, so only the target can fail; a
+    /// failed change of the target leaves the level untouched.
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        self.with_change_event(|control| {
+            control.set_target(target)?;
+            control.set_level(level)
+        })
+    }
+
+    /// Whether logging works.
+    ///
+    /// Return `false` if the layer was dropped, or if the journal is selected
+    /// but not reachable according to [`LogControl1LayerFactory::journal_reachable`].
+    ///
+    /// The reload locks never get poisoned, because this control only replaces
+    /// layers, and never runs other code while holding a reload lock.
+    fn is_healthy(&self) -> bool {
+        self.level_handle.with_current(|_| ()).is_ok()
+            && self.target_handle.with_current(|_| ()).is_ok()
+            && (!self.target.has_journal() || self.layer_factory.journal_reachable())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "tracing"
+    }
+
+    fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Mute or unmute logging.
+    ///
+    /// Mute by reloading the level layer to filter everything, and unmute by
+    /// reloading the last level set through this control; level and target
+    /// changes while muted take effect when unmuted.  While muted
+    /// [`LogControl1::level`] returns the last level set through this control.
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        reload_muted(&self.level_handle, &self.level, muted)?;
+        self.muted = muted;
         Ok(())
     }
 
+    /// Reopen the current target.
+    ///
+    /// Create a new target layer for the current target and filter directive,
+    /// e.g. to reconnect to the journal, and reload the target layer with it.
+    /// If creating the new layer fails the current layer remains in place.
+    fn reopen(&mut self) -> Result<(), LogControl1Error> {
+        let new_layer = make_target_layer(
+            &self.layer_factory,
+            &self.capture,
+            self.target,
+            &self.syslog_identifier,
+            self.filter.as_ref().map(|filter| filter.directive.as_str()),
+        )?;
+        self.target_handle.reload(new_layer).map_err(|error| {
+            LogControl1Error::reload_failed(
+                format!(
+                    "Failed to reload target layer to reopen log target {}",
+                    self.target()
+                ),
+                error,
+            )
+        })
+    }
+
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities {
+            mute: true,
+            reopen: true,
+            health: true,
+        }
+    }
+}
+
+/// Reload the level of the level layer through a shared reference.
+///
+/// [`LogControl1::level`] reflects levels set through this trait.
+impl<F, S> ReloadableLevel for TracingLogControl1<F, S>
+where
+    F: LogControl1LayerFactory,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Reload the level, and the default directive of the environment filter, if any.
+    ///
+    /// See [`TracingLogControl1::new_with_env_filter`].
+    fn set_reload_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+        if let Some(env_filter) = &self.env_filter {
+            env_filter.set_level(level)?;
+        }
+        reload_level(&self.level_handle, &self.level, self.muted, level)
+    }
+}
+
+/// A [`LogControl1`] which only changes the level.
+///
+/// For services which always log to the same target with fixed layers, and
+/// only need to change the level.  This control only reloads a level filter,
+/// and rejects changes to any target other than the target it was created
+/// with, with [`LogControl1Error::UnsupportedLogTarget`].
+///
+/// ```rust
+/// use logcontrol_tracing::*;
+/// use tracing_subscriber::prelude::*;
+///
+/// let (control, level_layer) = LevelOnlyTracingLogControl1::new(
+///     "example".to_string(),
+///     KnownLogTarget::Console,
+///     tracing::Level::INFO,
+/// );
+/// let subscriber = tracing_subscriber::registry()
+///     .with(tracing_subscriber::fmt::layer())
+///     .with(level_layer);
+/// ```
+pub struct LevelOnlyTracingLogControl1<S> {
+    /// The syslog identifier used for logging.
+    syslog_identifier: String,
+    /// The fixed target.
+    target: KnownLogTarget,
+    /// The last level set through this control, see [`current_level`].
+    level: Mutex<LogLevel>,
+    /// Whether the level layer filters everything, see [`reload_muted`].
+    muted: bool,
+    /// A handle to reload the level layer in order to change the level.
+    level_handle: reload::Handle<LevelFilter, S>,
+}
+
+impl<S: Subscriber> LevelOnlyTracingLogControl1<S> {
+    /// Create a new control for a level layer.
+    ///
+    /// `syslog_identifier` and `target` describe the fixed layers of the
+    /// service; `level` denotes the initial level.  Return the control and
+    /// the level layer to add to the subscriber on top of the fixed layers.
+    pub fn new(
+        syslog_identifier: String,
+        target: KnownLogTarget,
+        level: tracing::Level,
+    ) -> (Self, reload::Layer<LevelFilter, S>) {
+        let (level_layer, level_handle) = reload::Layer::new(LevelFilter::from_level(level));
+        let control = Self {
+            syslog_identifier,
+            target,
+            level: Mutex::new(to_log_level(level)),
+            muted: false,
+            level_handle,
+        };
+        (control, level_layer)
+    }
+}
+
+impl<S: Subscriber> LogControl1 for LevelOnlyTracingLogControl1<S> {
+    /// Get the level of the level layer.
+    ///
+    /// See [`TracingLogControl1`] for details.
+    fn level(&self) -> LogLevel {
+        let requested = *lock_level(&self.level);
+        current_level(&self.level_handle, requested)
+    }
+
+    /// Change the level of the level layer.
+    ///
+    /// See [`TracingLogControl1`] for details.
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.set_reload_level(level)
+    }
+
+    fn target(&self) -> &str {
+        self.target.as_str()
+    }
+
+    /// Accept the fixed target only.
+    ///
+    /// Ignore leading and trailing whitespace in `target`, and fail with
+    /// [`LogControl1Error::UnsupportedLogTarget`] for any other target.
+    fn set_target<T: AsRef<str>>(&mut self, target: T) -> Result<(), LogControl1Error> {
+        let target = target.as_ref().trim();
+        if target == self.target.as_str() {
+            Ok(())
+        } else {
+            Err(LogControl1Error::UnsupportedLogTarget(target.to_string()))
+        }
+    }
+
     fn syslog_identifier(&self) -> &str {
         &self.syslog_identifier
     }
+
+    /// Change the level, if `target` is the fixed target.
+    ///
+    /// Check the target first, so that an unsupported target leaves the level
+    /// untouched.
+    fn reconfigure(&mut self, level: LogLevel, target: &str) -> Result<(), LogControl1Error> {
+        self.set_target(target)?;
+        self.set_level(level)
+    }
+
+    /// Whether the level layer still exists.
+    fn is_healthy(&self) -> bool {
+        self.level_handle.with_current(|_| ()).is_ok()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "tracing"
+    }
+
+    fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Mute or unmute logging.
+    ///
+    /// See [`TracingLogControl1`] for details.
+    fn set_muted(&mut self, muted: bool) -> Result<(), LogControl1Error> {
+        reload_muted(&self.level_handle, &self.level, muted)?;
+        self.muted = muted;
+        Ok(())
+    }
+
+    /// Support muting and health checks; there is no target to reopen.
+    fn capabilities(&self) -> LogControlCapabilities {
+        LogControlCapabilities {
+            mute: true,
+            reopen: false,
+            health: true,
+        }
+    }
+}
+
+impl<S: Subscriber> ReloadableLevel for LevelOnlyTracingLogControl1<S> {
+    fn set_reload_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+        reload_level(&self.level_handle, &self.level, self.muted, level)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use logcontrol::{
+        KnownLogTarget, LogControl1, LogControl1Error, LogLevel, UnsupportedTargetPolicy,
+    };
+    use similar_asserts::assert_eq;
     use static_assertions::assert_impl_all;
-    use tracing_subscriber::Registry;
+    use tracing::Subscriber;
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::fmt::format::Writer;
+    use tracing_subscriber::fmt::time::FormatTime;
+    use tracing_subscriber::fmt::{self, MakeWriter};
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::{reload, Registry};
 
-    use crate::{PrettyLogControl1LayerFactory, TracingLogControl1};
+    use crate::journal::tests::TestJournal;
+    use crate::journal::{JournalLayer, JournalLayerFactory};
+    use crate::{
+        build_subscriber, empty_target_layer, ConfigurableLogControl1LayerFactory,
+        LevelOnlyTracingLogControl1, LogControl1LayerFactory, PrettyLogControl1LayerFactory,
+        ReloadableLevel, TracingLogControl1, CONSOLE_AND_JOURNAL_TARGET,
+    };
+    use tracing_subscriber::EnvFilter;
 
     // Ensure that the our default log control layers are Send and Sync, this is required for zbus.
     assert_impl_all!(TracingLogControl1<PrettyLogControl1LayerFactory, Registry>: Send, Sync);
+    assert_impl_all!(TracingLogControl1<ConfigurableLogControl1LayerFactory, Registry>: Send, Sync);
+
+    /// Change level and target from multiple threads.
+    ///
+    /// All changes go through a single mutex, so this test only checks
+    /// serialized calls: the final level and target match the last calls, and
+    /// the level still applies after the last target change.
+    #[test]
+    fn interleaved_set_level_and_set_target() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (control, layer) = TracingLogControl1::<_, Registry>::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+
+        // The control together with the last level and target set on it.
+        let state = Arc::new(Mutex::new((control, LogLevel::Info, KnownLogTarget::Null)));
+        let levels = [LogLevel::Err, LogLevel::Notice, LogLevel::Debug];
+        let targets = [
+            KnownLogTarget::Journal,
+            KnownLogTarget::Null,
+            KnownLogTarget::Console,
+        ];
+        let threads = (0..4)
+            .map(|n| {
+                let state = state.clone();
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        let mut guard = state.lock().unwrap();
+                        let (control, level, target) = &mut *guard;
+                        if (i + n) % 2 == 0 {
+                            *level = levels[(i + n) % levels.len()];
+                            control.set_level(*level).unwrap();
+                        } else {
+                            *target = targets[(i * n) % targets.len()];
+                            control.set_target(target.as_str()).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut guard = state.lock().unwrap();
+        let (control, level, target) = &mut *guard;
+        assert_eq!(control.level(), *level);
+        assert_eq!(control.target(), target.as_str());
+
+        control.set_level(LogLevel::Warning).unwrap();
+        control.set_target("journal").unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+        assert_eq!(control.target(), "journal");
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!("error");
+            tracing::warn!("warn");
+            tracing::info!("info");
+            tracing::debug!("debug");
+        });
+        assert_eq!(journal.field_values("MESSAGE"), vec!["error", "warn"]);
+    }
+
+    #[test]
+    fn level_filter_from_log_level() {
+        let filters = [
+            (LogLevel::Emerg, LevelFilter::ERROR),
+            (LogLevel::Alert, LevelFilter::ERROR),
+            (LogLevel::Crit, LevelFilter::ERROR),
+            (LogLevel::Err, LevelFilter::ERROR),
+            (LogLevel::Warning, LevelFilter::WARN),
+            (LogLevel::Notice, LevelFilter::INFO),
+            (LogLevel::Info, LevelFilter::DEBUG),
+            (LogLevel::Debug, LevelFilter::TRACE),
+        ];
+        for (level, filter) in filters {
+            assert_eq!(crate::level_filter_from_log_level(level), filter);
+        }
+    }
+
+    #[test]
+    fn directive_level() {
+        let levels = [
+            (LogLevel::Emerg, None),
+            (LogLevel::Alert, None),
+            (LogLevel::Crit, None),
+            (LogLevel::Err, Some("error")),
+            (LogLevel::Warning, Some("warn")),
+            (LogLevel::Notice, Some("info")),
+            (LogLevel::Info, Some("debug")),
+            (LogLevel::Debug, Some("trace")),
+        ];
+        for (level, directive) in levels {
+            assert_eq!(crate::directive_level(level), directive);
+            if let Some(directive) = directive {
+                EnvFilter::try_new(format!("my_crate={directive}")).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn reconfigure() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control.reconfigure(LogLevel::Warning, "journal").unwrap();
+            tracing::warn!("warn");
+            tracing::info!("info");
+
+            // An unsupported target changes nothing
+            assert!(matches!(
+                control.reconfigure(LogLevel::Debug, "kmsg"),
+                Err(LogControl1Error::UnsupportedLogTarget(_))
+            ));
+            assert_eq!(control.level(), LogLevel::Warning);
+            assert_eq!(control.target(), "journal");
+            tracing::warn!("still warn");
+            tracing::info!("still info");
+        });
+        assert_eq!(journal.field_values("MESSAGE"), ["warn", "still warn"]);
+    }
+
+    #[test]
+    fn new_with_env_filter() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        // As if from RUST_LOG
+        let env_filter = EnvFilter::new("warn,noisy=error");
+        let (mut control, layer) = TracingLogControl1::new_with_env_filter(
+            factory,
+            env_filter,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::WARN,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "app", "warn");
+            tracing::debug!(target: "app", "filtered debug");
+
+            control.set_level(LogLevel::Info).unwrap();
+            tracing::debug!(target: "app", "debug");
+            tracing::trace!(target: "app", "filtered trace");
+            // Targeted directives still apply
+            tracing::warn!(target: "noisy", "filtered noisy warn");
+            tracing::error!(target: "noisy", "noisy error");
+
+            control.set_level(LogLevel::Err).unwrap();
+            tracing::warn!(target: "app", "filtered warn");
+            tracing::error!(target: "app", "error");
+        });
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            ["warn", "debug", "noisy error", "error"]
+        );
+    }
+
+    #[test]
+    fn mute() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!("before");
+            control.set_muted(true).unwrap();
+            assert!(control.is_muted());
+            tracing::error!("muted");
+
+            // Changes while muted apply when unmuted
+            control.set_level(LogLevel::Warning).unwrap();
+            assert_eq!(control.level(), LogLevel::Warning);
+            control.set_target("null").unwrap();
+            control.set_target("journal").unwrap();
+            tracing::error!("still muted");
+
+            control.set_muted(false).unwrap();
+            assert!(!control.is_muted());
+            assert_eq!(control.level_directive(), "warn");
+            tracing::warn!("after");
+            tracing::info!("info");
+        });
+        assert_eq!(journal.field_values("MESSAGE"), ["before", "after"]);
+    }
+
+    #[test]
+    fn target_default_levels() {
+        let factory = ConfigurableLogControl1LayerFactory::default().with_journal(
+            JournalLayerFactory::default().with_sink(Arc::new(TestJournal::default())),
+        );
+        let (control, _layer) = TracingLogControl1::<_, Registry>::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let mut control = control
+            .with_target_default_level(KnownLogTarget::Console, LogLevel::Debug)
+            .with_target_default_level(KnownLogTarget::Journal, LogLevel::Warning)
+            .with_target_default_level(KnownLogTarget::Null, LogLevel::Emerg);
+        // The default does not apply to the initial target
+        assert_eq!(control.level(), LogLevel::Notice);
+
+        control.set_target("console").unwrap();
+        assert_eq!(control.level(), LogLevel::Debug);
+        control.set_level(LogLevel::Err).unwrap();
+        control.set_target(CONSOLE_AND_JOURNAL_TARGET).unwrap();
+        assert_eq!(control.level(), LogLevel::Err);
+        control.set_target("journal").unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+        control.set_target("auto").unwrap();
+        assert_eq!(control.target(), "console");
+        assert_eq!(control.level(), LogLevel::Debug);
+        control.set_target("journal?filter=info").unwrap();
+        assert_eq!(control.level(), LogLevel::Warning);
+
+        // An explicit level wins over the default level
+        control.reconfigure(LogLevel::Info, "console").unwrap();
+        assert_eq!(control.level(), LogLevel::Info);
+
+        // Default levels above error read back as they were set
+        control.set_target("null").unwrap();
+        assert_eq!(control.level(), LogLevel::Emerg);
+    }
+
+    /// A mock reloadable level which records all levels.
+    #[derive(Default)]
+    struct RecordLevels(Mutex<Vec<LogLevel>>);
+
+    impl ReloadableLevel for RecordLevels {
+        fn set_reload_level(&self, level: LogLevel) -> Result<(), LogControl1Error> {
+            self.0.lock().unwrap().push(level);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn level_directive() {
+        let (mut control, _layer) = TracingLogControl1::<_, Registry>::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        assert_eq!(control.level_directive(), "info");
+        control.set_level(LogLevel::Debug).unwrap();
+        assert_eq!(control.level_directive(), "trace");
+    }
+
+    #[test]
+    fn levels_above_error() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            for level in [LogLevel::Emerg, LogLevel::Alert, LogLevel::Crit] {
+                control.set_level(level).unwrap();
+                assert_eq!(control.level(), level);
+                assert_eq!(control.current_priority(), level.as_priority());
+                tracing::warn!("warn");
+                tracing::error!("error");
+            }
+            control.reconfigure(LogLevel::Crit, "journal").unwrap();
+            assert_eq!(control.level(), LogLevel::Crit);
+        });
+        assert_eq!(journal.field_values("MESSAGE"), ["error", "error", "error"]);
+
+        let (mut control, _layer) = LevelOnlyTracingLogControl1::<Registry>::new(
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        );
+        control.set_level(LogLevel::Crit).unwrap();
+        assert_eq!(control.level(), LogLevel::Crit);
+        // A level changed elsewhere reads back as is
+        control.level_handle.reload(LevelFilter::DEBUG).unwrap();
+        assert_eq!(control.level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn current_priority() {
+        let (mut control, _layer) = TracingLogControl1::<_, Registry>::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        assert_eq!(control.current_priority(), 5);
+        control.set_level(LogLevel::Debug).unwrap();
+        assert_eq!(control.current_priority(), 7);
+        control.set_reload_level(LogLevel::Err).unwrap();
+        assert_eq!(control.current_priority(), 3);
+    }
+
+    #[test]
+    fn backend_name() {
+        let (control, _layer) = TracingLogControl1::<_, Registry>::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        assert_eq!(control.backend_name(), "tracing");
+        let (control, _layer) = LevelOnlyTracingLogControl1::<Registry>::new(
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        );
+        assert_eq!(control.backend_name(), "tracing");
+    }
+
+    #[test]
+    fn capabilities() {
+        let (control, _layer) = TracingLogControl1::<_, Registry>::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        assert_eq!(control.capabilities().names(), ["mute", "reopen", "health"]);
+        let (control, _layer) = LevelOnlyTracingLogControl1::<Registry>::new(
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        );
+        assert_eq!(control.capabilities().names(), ["mute", "health"]);
+    }
+
+    #[test]
+    fn level_only_control() {
+        let journal = Arc::new(TestJournal::default());
+        let journal_layer = JournalLayerFactory::default()
+            .with_sink(journal.clone())
+            .create_layer("test".to_string())
+            .unwrap();
+        let (mut control, level_layer) = LevelOnlyTracingLogControl1::new(
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::WARN,
+        );
+        let subscriber = Registry::default().with(journal_layer).with(level_layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("before");
+            control.set_level(LogLevel::Notice).unwrap();
+            assert_eq!(control.level(), LogLevel::Notice);
+            tracing::info!("after");
+
+            control.set_target(" journal ").unwrap();
+            assert!(matches!(
+                control.set_target("console"),
+                Err(LogControl1Error::UnsupportedLogTarget(_))
+            ));
+            assert!(matches!(
+                control.reconfigure(LogLevel::Debug, "null"),
+                Err(LogControl1Error::UnsupportedLogTarget(_))
+            ));
+            assert_eq!(control.target(), "journal");
+            assert_eq!(control.level(), LogLevel::Notice);
+            assert!(control.is_healthy());
+
+            control.set_muted(true).unwrap();
+            tracing::warn!("muted");
+            control.set_muted(false).unwrap();
+            tracing::warn!("unmuted");
+        });
+        assert!(!control.is_healthy());
+        assert_eq!(journal.field_values("MESSAGE"), ["after", "unmuted"]);
+    }
+
+    #[test]
+    fn reloadable_level() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let mock = RecordLevels::default();
+        let reloadables: [&dyn ReloadableLevel; 2] = [&control, &mock];
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            for reloadable in reloadables {
+                reloadable.set_reload_level(LogLevel::Warning).unwrap();
+            }
+            tracing::info!("info");
+            tracing::warn!("warn");
+            assert_eq!(control.level(), LogLevel::Warning);
+            assert_eq!(control.level_directive(), "warn");
+            control.set_reload_level(LogLevel::Crit).unwrap();
+            tracing::warn!("crit");
+            tracing::error!("error");
+            assert_eq!(control.level(), LogLevel::Crit);
+            assert_eq!(control.level_directive(), "error");
+        });
+        assert_eq!(*mock.0.lock().unwrap(), [LogLevel::Warning]);
+        assert_eq!(journal.field_values("MESSAGE"), ["warn", "error"]);
+    }
+
+    #[test]
+    fn from_handles() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (target_layer, target_handle) = reload::Layer::new(empty_target_layer::<
+            ConfigurableLogControl1LayerFactory,
+            Registry,
+        >());
+        let (level_layer, level_handle) = reload::Layer::new(LevelFilter::OFF);
+        let subscriber = Registry::default().with(target_layer.and_then(level_layer));
+
+        let mut control = TracingLogControl1::from_handles(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::WARN,
+            level_handle,
+            target_handle,
+        )
+        .unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!("warn");
+            tracing::info!("info");
+            control.set_level(LogLevel::Notice).unwrap();
+            tracing::info!("info after set_level");
+            control.set_target("null").unwrap();
+            tracing::warn!("warn after set_target");
+        });
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            ["warn", "info after set_level"]
+        );
+    }
+
+    #[test]
+    fn is_healthy() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (control, layer) = TracingLogControl1::<_, Registry>::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        assert!(control.is_healthy());
+        drop(subscriber);
+        assert!(!control.is_healthy());
+    }
+
+    #[test]
+    fn reload_failure() {
+        let (mut control, layer) = TracingLogControl1::<_, Registry>::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        drop(Registry::default().with(layer));
+        for result in [
+            control.set_level(LogLevel::Debug),
+            control.set_target("console"),
+        ] {
+            let error = result.unwrap_err();
+            assert!(
+                matches!(error, LogControl1Error::ReloadFailed { .. }),
+                "{error:?}"
+            );
+            let source = std::error::Error::source(&error).unwrap();
+            assert!(source.is::<reload::Error>(), "{source:?}");
+        }
+        assert_eq!(control.level(), LogLevel::Notice);
+        assert_eq!(control.target(), "null");
+    }
+
+    /// Write into a shared buffer.
+    #[derive(Clone, Default)]
+    struct CapturedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl CapturedWriter {
+        fn output(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedWriter {
+        type Writer = CapturedWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    impl io::Write for CapturedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Clone)]
+    struct FixedTime;
+
+    impl FormatTime for FixedTime {
+        fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+            write!(w, "2024-01-02T03:04:05Z")
+        }
+    }
+
+    #[test]
+    fn console_priority_prefix() {
+        let writer = CapturedWriter::default();
+        let layer = ConfigurableLogControl1LayerFactory::default()
+            .with_timer(FixedTime)
+            .with_console_priority_prefix(true)
+            .create_console_layer::<Registry>()
+            .unwrap()
+            .with_writer(writer.clone());
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::error!("error");
+            tracing::warn!("warn");
+            tracing::info!("info");
+            tracing::debug!("debug");
+            tracing::trace!("trace");
+        });
+        let output = writer.output();
+        let mut prefixes = output.lines().map(|line| &line[..3]).collect::<Vec<_>>();
+        prefixes.dedup();
+        assert_eq!(prefixes, ["<3>", "<4>", "<5>", "<6>", "<7>"], "{output}");
+        assert!(
+            output.starts_with("<3>  2024-01-02T03:04:05Z ERROR"),
+            "Unexpected output: {output:?}"
+        );
+    }
+
+    #[test]
+    fn console_layer_with_timer() {
+        let writer = CapturedWriter::default();
+        let layer = ConfigurableLogControl1LayerFactory::default()
+            .with_timer(FixedTime)
+            .create_console_layer::<Registry>()
+            .unwrap()
+            .with_ansi(false)
+            .with_writer(writer.clone());
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            tracing::info!("Hello");
+        });
+        let output = writer.output();
+        assert!(
+            output.starts_with("  2024-01-02T03:04:05Z  INFO"),
+            "Unexpected output: {output:?}"
+        );
+    }
+
+    /// Create console layers which write to a captured writer, and journal layers with a test journal.
+    struct CapturingLayerFactory {
+        console: CapturedWriter,
+        journal: JournalLayerFactory,
+    }
+
+    impl LogControl1LayerFactory for CapturingLayerFactory {
+        type JournalLayer<S: Subscriber + for<'span> LookupSpan<'span>> = JournalLayer;
+
+        type ConsoleLayer<S: Subscriber + for<'span> LookupSpan<'span>> = fmt::Layer<
+            S,
+            fmt::format::DefaultFields,
+            fmt::format::Format<fmt::format::Full, ()>,
+            CapturedWriter,
+        >;
+
+        fn create_journal_layer<S: Subscriber + for<'span> LookupSpan<'span>>(
+            &self,
+            syslog_identifier: String,
+        ) -> Result<Self::JournalLayer<S>, LogControl1Error> {
+            self.journal.create_layer(syslog_identifier)
+        }
+
+        fn create_console_layer<S: Subscriber + for<'span> LookupSpan<'span>>(
+            &self,
+        ) -> Result<Self::ConsoleLayer<S>, LogControl1Error> {
+            Ok(fmt::layer()
+                .with_ansi(false)
+                .without_time()
+                .with_writer(self.console.clone()))
+        }
+    }
+
+    #[test]
+    fn console_and_journal_target() {
+        let console = CapturedWriter::default();
+        let journal = Arc::new(TestJournal::default());
+        let factory = CapturingLayerFactory {
+            console: console.clone(),
+            journal: JournalLayerFactory::default().with_sink(journal.clone()),
+        };
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("console");
+            control.set_target(CONSOLE_AND_JOURNAL_TARGET).unwrap();
+            assert_eq!(control.target(), "console+journal");
+            tracing::info!("both");
+            control.set_target("journal").unwrap();
+            tracing::info!("journal");
+        });
+        assert_eq!(
+            console.output(),
+            " INFO logcontrol_tracing::tests: console\n INFO logcontrol_tracing::tests: both\n"
+        );
+        assert_eq!(journal.field_values("MESSAGE"), ["both", "journal"]);
+    }
+
+    #[test]
+    fn target_with_filter_directive() {
+        let console = CapturedWriter::default();
+        let factory = CapturingLayerFactory {
+            console: console.clone(),
+            journal: JournalLayerFactory::default(),
+        };
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            tracing::Level::DEBUG,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control
+                .set_target(" console?filter=info,[{tenant=foo}]=debug\n")
+                .unwrap();
+            assert_eq!(control.target(), "console?filter=info,[{tenant=foo}]=debug");
+            tracing::debug!("no span");
+            tracing::info_span!("request", tenant = "bar").in_scope(|| {
+                tracing::debug!("other tenant");
+            });
+            tracing::info_span!("request", tenant = "foo").in_scope(|| {
+                tracing::debug!("tenant");
+            });
+            tracing::info!("info");
+            control.set_target("console").unwrap();
+            assert_eq!(control.target(), "console");
+            tracing::debug!("unfiltered");
+        });
+        assert_eq!(
+            console.output(),
+            "DEBUG request{tenant=\"foo\"}: logcontrol_tracing::tests: tenant\n \
+             INFO logcontrol_tracing::tests: info\n\
+             DEBUG logcontrol_tracing::tests: unfiltered\n"
+        );
+    }
+
+    #[test]
+    fn target_with_invalid_filter_directive() {
+        let (mut control, _layer) = TracingLogControl1::<_, Registry>::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let result = control.set_target("console?filter=info,[{tenant=foo");
+        assert!(
+            matches!(result, Err(LogControl1Error::Failure(_))),
+            "{result:?}"
+        );
+        for target in ["console?filter=", "console?level=debug", "kmsg?filter=info"] {
+            let result = control.set_target(target);
+            assert!(
+                matches!(result, Err(LogControl1Error::UnsupportedLogTarget(_))),
+                "{target}: {result:?}"
+            );
+        }
+        assert_eq!(control.target(), "null");
+    }
+
+    #[test]
+    fn startup_message() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            true,
+            "test".to_string(),
+            KnownLogTarget::Auto,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control.log_startup_message();
+            control.set_target("journal").unwrap();
+            control.log_startup_message();
+        });
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            [
+                "logging to journal at level notice (auto-detected), syslog identifier \"test\"",
+                "logging to journal at level notice (configured), syslog identifier \"test\""
+            ]
+        );
+        assert_eq!(journal.field_values("PRIORITY"), ["5", "5"]);
+        assert!(journal.field_values("MESSAGE_ID").is_empty());
+    }
+
+    #[test]
+    fn change_events() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (control, layer) = TracingLogControl1::new(
+            factory,
+            true,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let mut control = control
+            .with_target_default_level(KnownLogTarget::Journal, LogLevel::Info)
+            .with_change_events(true);
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control.set_level(LogLevel::Debug).unwrap();
+            control.set_reload_level(LogLevel::Warning).unwrap();
+            control.reconfigure(LogLevel::Err, "journal").unwrap();
+        });
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            [
+                "Log configuration changed, logging to journal at level debug (configured), syslog identifier \"test\"",
+                "Log configuration changed, logging to journal at level err (configured), syslog identifier \"test\""
+            ]
+        );
+        assert_eq!(
+            journal.field_values("MESSAGE_ID"),
+            [
+                logcontrol::CONFIGURATION_CHANGED_MESSAGE_ID,
+                logcontrol::CONFIGURATION_CHANGED_MESSAGE_ID
+            ]
+        );
+        assert_eq!(journal.field_values("PRIORITY"), ["7", "3"]);
+    }
+
+    #[test]
+    fn unsupported_target_policy() {
+        let policies = [
+            (UnsupportedTargetPolicy::Error, false, ""),
+            (UnsupportedTargetPolicy::Ignore, true, ""),
+            (
+                UnsupportedTargetPolicy::KeepCurrent,
+                true,
+                " WARN logcontrol_tracing: Log target kmsg not supported, keeping current log target\n",
+            ),
+        ];
+        for (policy, succeeds, expected_output) in policies {
+            let console = CapturedWriter::default();
+            let factory = CapturingLayerFactory {
+                console: console.clone(),
+                journal: JournalLayerFactory::default(),
+            };
+            let (control, layer) = TracingLogControl1::new(
+                factory,
+                false,
+                "test".to_string(),
+                KnownLogTarget::Console,
+                tracing::Level::INFO,
+            )
+            .unwrap();
+            let mut control = control.with_unsupported_target_policy(policy);
+            let subscriber = Registry::default().with(layer);
+            let result =
+                tracing::subscriber::with_default(subscriber, || control.set_target("kmsg"));
+            assert_eq!(result.is_ok(), succeeds, "{policy:?}: {result:?}");
+            if !succeeds {
+                assert!(matches!(
+                    result,
+                    Err(LogControl1Error::UnsupportedLogTarget(_))
+                ));
+            }
+            assert_eq!(control.target(), "console");
+            assert_eq!(console.output(), expected_output);
+        }
+    }
+
+    #[test]
+    fn target_fallback_chain() {
+        let chain = vec![
+            KnownLogTarget::Journal,
+            KnownLogTarget::Syslog,
+            KnownLogTarget::Console,
+        ];
+        let console = CapturedWriter::default();
+        let factory = CapturingLayerFactory {
+            console: console.clone(),
+            journal: JournalLayerFactory::default()
+                .with_connect(|| Err(io::Error::other("journal not running"))),
+        };
+        // The journal fails, and syslog is not supported, so start on the console
+        let (control, layer) = TracingLogControl1::new_with_target_fallback_chain(
+            factory,
+            false,
+            "test".to_string(),
+            chain.clone(),
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let mut control =
+            control.with_target_default_level(KnownLogTarget::Console, LogLevel::Info);
+        assert_eq!(control.target(), "console");
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control.set_target("journal").unwrap();
+            tracing::debug!("fallback");
+        });
+        assert_eq!(control.target(), "console");
+        assert_eq!(control.level(), LogLevel::Info);
+        assert_eq!(
+            console.output(),
+            "DEBUG logcontrol_tracing::tests: fallback\n"
+        );
+
+        // Without a working target in the chain fail with the error of the requested target
+        let mut control = control.with_target_fallback_chain(vec![KnownLogTarget::Syslog]);
+        assert!(matches!(
+            control.set_target("journal"),
+            Err(LogControl1Error::InputOutputError(_))
+        ));
+        assert!(matches!(
+            TracingLogControl1::<_, Registry>::new_with_target_fallback_chain(
+                PrettyLogControl1LayerFactory,
+                false,
+                "test".to_string(),
+                vec![KnownLogTarget::Syslog, KnownLogTarget::Kmsg],
+                tracing::Level::INFO,
+            ),
+            Err(LogControl1Error::UnsupportedLogTarget(target)) if target == "syslog"
+        ));
+    }
+
+    #[test]
+    fn reopen() {
+        let journal = Arc::new(TestJournal::default());
+        let connects = Arc::new(AtomicUsize::new(0));
+        let factory = CapturingLayerFactory {
+            console: CapturedWriter::default(),
+            journal: JournalLayerFactory::default().with_connect({
+                let journal = journal.clone();
+                let connects = connects.clone();
+                move || {
+                    connects.fetch_add(1, Ordering::SeqCst);
+                    Ok(journal.clone())
+                }
+            }),
+        };
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control.set_target("journal?filter=warn").unwrap();
+            assert_eq!(connects.load(Ordering::SeqCst), 2);
+            control.reopen().unwrap();
+            assert_eq!(connects.load(Ordering::SeqCst), 3);
+            // Keep the filter directive of the current target
+            tracing::info!("info");
+            tracing::warn!("warn");
+        });
+        assert_eq!(control.target(), "journal?filter=warn");
+        assert_eq!(journal.field_values("MESSAGE"), ["warn"]);
+    }
+
+    #[test]
+    fn scoped_level() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = CapturingLayerFactory {
+            console: CapturedWriter::default(),
+            journal: JournalLayerFactory::default().with_sink(journal.clone()),
+        };
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            {
+                let guard = control.scoped_level(LogLevel::Debug).unwrap();
+                assert_eq!(guard.level(), LogLevel::Debug);
+                tracing::debug!("scoped");
+            }
+            tracing::debug!("hidden");
+        });
+        assert_eq!(control.level(), LogLevel::Notice);
+        assert_eq!(journal.field_values("MESSAGE"), ["scoped"]);
+    }
+
+    #[test]
+    fn set_factory() {
+        let journal = Arc::new(TestJournal::default());
+        let console = CapturedWriter::default();
+        let factory = CapturingLayerFactory {
+            console: console.clone(),
+            journal: JournalLayerFactory::default().with_sink(journal.clone()),
+        };
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            control.set_target("journal?filter=warn").unwrap();
+            tracing::warn!("old\nformat");
+            control
+                .set_factory(CapturingLayerFactory {
+                    console: console.clone(),
+                    journal: JournalLayerFactory::default()
+                        .with_sink(journal.clone())
+                        .with_single_line_messages(true),
+                })
+                .unwrap();
+            // Keep the filter directive of the current target
+            tracing::info!("info");
+            tracing::warn!("new\nformat");
+        });
+        assert_eq!(control.target(), "journal?filter=warn");
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            ["old\nformat", "new format"]
+        );
+        assert_eq!(console.output(), "");
+    }
+
+    #[test]
+    fn default_factories() {
+        fn console_control<F: LogControl1LayerFactory + Default>() {
+            let (control, _layer) = TracingLogControl1::<_, Registry>::new(
+                F::default(),
+                false,
+                "test".to_string(),
+                KnownLogTarget::Console,
+                tracing::Level::INFO,
+            )
+            .unwrap();
+            assert_eq!(control.target(), "console");
+        }
+
+        console_control::<PrettyLogControl1LayerFactory>();
+        console_control::<ConfigurableLogControl1LayerFactory>();
+    }
+
+    #[test]
+    fn set_syslog_identifier() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "old".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("first");
+            control.set_syslog_identifier("new".to_string()).unwrap();
+            assert_eq!(control.syslog_identifier(), "new");
+            tracing::info!("second");
+            control.set_target("null").unwrap();
+            control.set_syslog_identifier("newer".to_string()).unwrap();
+            tracing::info!("third");
+            control.set_target("journal").unwrap();
+            tracing::info!("fourth");
+        });
+        assert_eq!(
+            journal.field_values("MESSAGE"),
+            ["first", "second", "fourth"]
+        );
+        assert_eq!(
+            journal.field_values("SYSLOG_IDENTIFIER"),
+            ["old", "new", "newer"]
+        );
+    }
+
+    #[test]
+    fn build_subscriber_as_global_default() {
+        let journal = Arc::new(TestJournal::default());
+        let factory = ConfigurableLogControl1LayerFactory::default()
+            .with_journal(JournalLayerFactory::default().with_sink(journal.clone()));
+        let (mut control, layer) = TracingLogControl1::new(
+            factory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Journal,
+            tracing::Level::WARN,
+        )
+        .unwrap();
+        // Other tests emit events as well, so filter by our target
+        let env_filter = EnvFilter::new("global_default=trace");
+        tracing::subscriber::set_global_default(build_subscriber(layer, Some(env_filter))).unwrap();
+
+        tracing::info!(target: "global_default", "before");
+        tracing::warn!(target: "global_default", "warn");
+        control.set_level(LogLevel::Info).unwrap();
+        tracing::info!(target: "global_default", "after");
+        tracing::info!(target: "other", "filtered");
+        assert_eq!(journal.field_values("MESSAGE"), vec!["warn", "after"]);
+    }
 }
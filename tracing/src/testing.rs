@@ -0,0 +1,225 @@
+//! Capture events emitted at the level of a [`TracingLogControl1`] in tests.
+
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+#[cfg(feature = "testing")]
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+#[cfg(feature = "testing")]
+use crate::{LogControl1LayerFactory, TracingLogControl1};
+
+#[cfg(feature = "testing")]
+impl<F, S> TracingLogControl1<F, S>
+where
+    F: LogControl1LayerFactory,
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Run `f` and capture all events it emits through the layer of this control.
+    ///
+    /// Capture events at the output of the target layer of this control while
+    /// `f` runs, i.e. after the level layer, the filter directive of the current
+    /// target, and the [`tracing_subscriber::EnvFilter`] of
+    /// [`Self::new_with_env_filter`].  Events still reach the output of the
+    /// current target as well.
+    ///
+    /// `f` must emit events to a subscriber with the layer of this control,
+    /// typically the default subscriber.  Capturing does not tell threads
+    /// apart, so it captures events which other threads emit while `f` runs.
+    ///
+    /// Return one line per event, in the format `LEVEL target: message`,
+    /// followed by all other fields of the event as `name=value`.
+    ///
+    /// ```rust
+    /// use logcontrol_tracing::{LogControl1, LogLevel, PrettyLogControl1LayerFactory, TracingLogControl1};
+    /// use logcontrol_tracing::logcontrol::KnownLogTarget;
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// let (mut control, layer) = TracingLogControl1::new(
+    ///     PrettyLogControl1LayerFactory,
+    ///     false,
+    ///     "test".to_string(),
+    ///     KnownLogTarget::Null,
+    ///     tracing::Level::INFO,
+    /// )
+    /// .unwrap();
+    /// let subscriber = tracing_subscriber::Registry::default().with(layer);
+    /// tracing::subscriber::with_default(subscriber, || {
+    ///     control.set_level(LogLevel::Debug).unwrap();
+    ///     let lines = control.with_captured(|| tracing::debug!(target: "test", "Hello"));
+    ///     assert_eq!(lines, ["DEBUG test: Hello"]);
+    /// });
+    /// ```
+    pub fn with_captured<G: FnOnce()>(&self, f: G) -> Vec<String> {
+        self.capture.start();
+        f();
+        self.capture.stop()
+    }
+}
+
+/// A layer which captures events for `TracingLogControl1::with_captured`.
+///
+/// This layer is part of every [`crate::LogTargetLayer`], and formats every
+/// event into a line while capturing, and ignores all events otherwise.  Use
+/// [`crate::empty_target_layer`] to create an initial target layer.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureLayer {
+    state: Arc<CaptureState>,
+}
+
+#[derive(Debug, Default)]
+struct CaptureState {
+    /// Whether to capture events.
+    ///
+    /// Check this flag first, to avoid locking `lines` for every event.
+    active: AtomicBool,
+    /// Lines captured so far.
+    lines: Mutex<Vec<String>>,
+}
+
+impl CaptureLayer {
+    /// Discard all previously captured lines, and start capturing.
+    #[cfg(feature = "testing")]
+    fn start(&self) {
+        self.state
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.state.active.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop capturing and return all captured lines.
+    #[cfg(feature = "testing")]
+    fn stop(&self) -> Vec<String> {
+        self.state.active.store(false, Ordering::SeqCst);
+        std::mem::take(
+            &mut *self
+                .state
+                .lines
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+}
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if !self.state.active.load(Ordering::Relaxed) {
+            return;
+        }
+        let metadata = event.metadata();
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let line = format!(
+            "{} {}: {}{}",
+            metadata.level(),
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        );
+        self.state
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(line);
+    }
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use logcontrol::{KnownLogTarget, LogControl1, LogLevel};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::{EnvFilter, Registry};
+
+    use crate::{PrettyLogControl1LayerFactory, ReloadableLevel, TracingLogControl1};
+
+    fn emit() {
+        tracing::info!(target: "test", answer = 42, "info");
+        tracing::debug!(target: "test", "debug");
+        tracing::debug!(target: "noisy", "debug");
+    }
+
+    #[test]
+    fn capture_follows_level() {
+        let (mut control, layer) = TracingLogControl1::new(
+            PrettyLogControl1LayerFactory,
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::INFO,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(control.with_captured(emit), ["INFO test: info answer=42"]);
+
+            control.set_level(LogLevel::Info).unwrap();
+            assert_eq!(
+                control.with_captured(emit),
+                [
+                    "INFO test: info answer=42",
+                    "DEBUG test: debug",
+                    "DEBUG noisy: debug"
+                ]
+            );
+
+            control.set_reload_level(LogLevel::Err).unwrap();
+            assert!(control.with_captured(emit).is_empty());
+        });
+    }
+
+    #[test]
+    fn capture_follows_filters() {
+        let (mut control, layer) = TracingLogControl1::new_with_env_filter(
+            PrettyLogControl1LayerFactory,
+            EnvFilter::new("info,noisy=info"),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Null,
+            tracing::Level::DEBUG,
+        )
+        .unwrap();
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(
+                control.with_captured(emit),
+                ["INFO test: info answer=42", "DEBUG test: debug"]
+            );
+
+            control.set_target("null?filter=warn,test=info").unwrap();
+            assert_eq!(control.with_captured(emit), ["INFO test: info answer=42"]);
+        });
+        // Events which bypass the layer of the control are not captured
+        assert!(control.with_captured(emit).is_empty());
+    }
+}
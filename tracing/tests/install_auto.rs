@@ -0,0 +1,59 @@
+//! Install a control as global default subscriber.
+//!
+//! The global default subscriber can only be set once per process, so this
+//! test runs in a process of its own.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use logcontrol_tracing::journal::{JournalLayerFactory, JournalSink};
+use logcontrol_tracing::{
+    ConfigurableLogControl1LayerFactory, LogControl1, LogControl1Error, LogLevel,
+    TracingLogControl1,
+};
+
+/// Record the `MESSAGE` field of all journal entries.
+#[derive(Default)]
+struct Messages(Mutex<Vec<String>>);
+
+impl JournalSink for Messages {
+    fn send_payload(&self, payload: &[u8]) -> io::Result<()> {
+        // Messages in this test are single lines, which journal layers write
+        // length-encoded, i.e. as `MESSAGE\n`, a length, the message and `\n`
+        let start = payload
+            .windows(8)
+            .position(|window| window == b"MESSAGE\n")
+            .map(|position| position + 16)
+            .unwrap();
+        let end = start + payload[start..].iter().position(|b| *b == b'\n').unwrap();
+        let message = String::from_utf8(payload[start..end].to_vec()).unwrap();
+        self.0.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+#[test]
+fn install_auto() {
+    std::env::set_var("SYSTEMD_LOG_TARGET", "journal");
+    std::env::remove_var("SYSTEMD_LOG_LEVEL");
+    let messages = Arc::new(Messages::default());
+    let factory = ConfigurableLogControl1LayerFactory::default()
+        .with_journal(JournalLayerFactory::default().with_sink(messages.clone()));
+    let mut control = TracingLogControl1::install_auto(factory, tracing::Level::WARN).unwrap();
+    assert_eq!(control.target(), "journal");
+
+    tracing::info!("before");
+    tracing::warn!("warn");
+    control.set_level(LogLevel::Notice).unwrap();
+    tracing::info!("after");
+    tracing::debug!("debug");
+    assert_eq!(*messages.0.lock().unwrap(), ["warn", "after"]);
+
+    // The global default is taken now
+    let factory = ConfigurableLogControl1LayerFactory::default()
+        .with_journal(JournalLayerFactory::default().with_sink(messages.clone()));
+    assert!(matches!(
+        TracingLogControl1::install_auto(factory, tracing::Level::WARN),
+        Err(LogControl1Error::Failure(_))
+    ));
+}
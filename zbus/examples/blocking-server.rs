@@ -0,0 +1,49 @@
+//! A blocking zbus server which exposes the log control interface for `log`.
+//!
+//! Run as an ad-hoc service via
+//!
+//! ```
+//! $ systemd-run --user --pty \
+//!     --service-type=dbus --unit=log-control-example.service \
+//!     --property=BusName=de.swsnr.logcontrol.BlockingServerExample \
+//!     --setenv=RUST_LOG=trace \
+//!     ./target/debug/examples/blocking-server
+//! ```
+//!
+//! Then use `systemctl --user service-log-level log-control-example.service`
+//! or `systemctl --user service-log-target log-control-example.service` to test
+//! the interface.
+//!
+//! Unlike `simple-server` this example uses the blocking zbus API and needs no
+//! async runtime; zbus handles DBus calls on its own internal executor thread.
+
+use std::error::Error;
+use std::thread::sleep;
+use std::time::Duration;
+
+use log::{info, warn};
+use logcontrol_log::{LogController, LogFactory};
+use logcontrol_zbus::ConnectionBuilderExt;
+
+struct Factory;
+
+impl LogFactory for Factory {
+    fn create_console_log(&self) -> Result<Box<dyn log::Log>, logcontrol::LogControl1Error> {
+        Ok(Box::new(env_logger::Builder::from_default_env().build()))
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let control = LogController::install_auto(Factory, log::Level::Info)?;
+    let _conn = zbus::blocking::connection::Builder::session()?
+        .name("de.swsnr.logcontrol.BlockingServerExample")?
+        .serve_log_control(logcontrol_zbus::LogControl1::new(control))?
+        .build()?;
+
+    loop {
+        sleep(Duration::from_secs(5));
+        info!("An message at info level");
+        sleep(Duration::from_secs(1));
+        warn!("An message at warning level");
+    }
+}
@@ -23,6 +23,10 @@
 //! }
 //! ```
 //!
+//! [`ConnectionBuilderExt`] also extends the blocking
+//! `zbus::blocking::connection::Builder`, for applications without an async
+//! runtime; see the `blocking-server` example.
+//!
 //! Note that for `systemctl` to find the log control interface with
 //! `systemctl service-log-level` and `systemctl service-log-target` you need
 //! to make sure that
@@ -31,36 +35,165 @@
 //! - the unit file provides the claimed bus name in the `BusName` attribute.
 //!
 //! Otherwise systemd will not be able to change the log level or target.
+//!
+//! With the `client` feature `has_log_control` checks whether a service on
+//! a bus actually exposes the log control interface under
+//! [`logcontrol::DBUS_OBJ_PATH`]; this helps to verify `BusName` wiring.
+//!
+//! With the `signal` feature `run_until_signal` serves the interface and
+//! blocks until the service receives `SIGTERM` or `SIGINT`, for daemons which
+//! do their work on other threads.
+//!
+//! With the `p2p` feature `serve_p2p` serves the interface over a
+//! peer-to-peer connection on a socket pair, to test services and clients of
+//! the interface in-process, without a DBus daemon.
 
 #![deny(warnings, clippy::all)]
 #![forbid(unsafe_code)]
 
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use logcontrol::{LogControl1Error, LogLevel};
 use zbus::interface;
+use zbus::message::Header;
+use zbus::names::OwnedUniqueName;
+use zbus::object_server::SignalEmitter;
 
 pub use logcontrol;
 pub use logcontrol::DBUS_OBJ_PATH;
 
-fn to_fdo_error(error: LogControl1Error) -> zbus::fdo::Error {
+#[cfg(feature = "p2p")]
+mod p2p;
+#[cfg(feature = "signal")]
+mod run;
+
+#[cfg(feature = "p2p")]
+pub use p2p::serve_p2p;
+#[cfg(feature = "signal")]
+pub use run::{run_until_signal, run_until_signals, DEFAULT_SHUTDOWN_SIGNALS};
+
+/// The name of the log control DBus interface.
+pub const INTERFACE_NAME: &str = "org.freedesktop.LogControl1";
+
+/// The name of the DBus property for the log level.
+pub const PROP_LOG_LEVEL: &str = "LogLevel";
+
+/// The name of the DBus property for the log target.
+pub const PROP_LOG_TARGET: &str = "LogTarget";
+
+/// The name of the DBus property for the syslog identifier.
+pub const PROP_SYSLOG_IDENTIFIER: &str = "SyslogIdentifier";
+
+/// The name of the non-standard DBus property to mute logging.
+pub const PROP_MUTED: &str = "Muted";
+
+/// Remove surrounding whitespace from `target`, and reject empty targets.
+///
+/// `systemctl service-log-target` passes targets through verbatim; an empty
+/// target is a malformed request rather than an unsupported target.
+fn trim_target(target: &str) -> zbus::fdo::Result<&str> {
+    let target = target.trim();
+    if target.is_empty() {
+        Err(zbus::fdo::Error::InvalidArgs(
+            "Log target must not be empty".to_string(),
+        ))
+    } else {
+        Ok(target)
+    }
+}
+
+/// Map a [`LogControl1Error`] to a DBus error.
+///
+/// Map unsupported levels and targets to [`zbus::fdo::Error::NotSupported`],
+/// I/O errors to [`zbus::fdo::Error::IOError`], and all other errors to
+/// [`zbus::fdo::Error::Failed`].
+///
+/// [`LogControl1`] uses this mapping unless created with
+/// [`LogControl1::new_with_error_mapper`]; custom mappers can fall back to this
+/// function for errors they do not handle themselves.
+pub fn to_fdo_error(error: LogControl1Error) -> zbus::fdo::Error {
     use LogControl1Error::*;
     match error {
         UnsupportedLogLevel(_) | UnsupportedLogTarget(_) => {
             zbus::fdo::Error::NotSupported(error.to_string())
         }
         InputOutputError(error) => zbus::fdo::Error::IOError(error.to_string()),
+        ReloadFailed { .. } => zbus::fdo::Error::Failed(error.to_string()),
         Failure(msg) => zbus::fdo::Error::Failed(msg),
     }
 }
 
+/// A change of the log level or log target.
+#[derive(Debug, Clone)]
+struct Change {
+    /// When the change happened, in microseconds since the Unix epoch.
+    timestamp_usec: u64,
+    /// The name of the changed property, i.e. `LogLevel` or `LogTarget`.
+    property: &'static str,
+    /// The value before the change.
+    old: String,
+    /// The value after the change.
+    new: String,
+}
+
+/// The default number of changes [`LogControl1`] remembers.
+pub const DEFAULT_HISTORY_SIZE: usize = 16;
+
+/// A change requested over DBus, as passed to the authorization hook of [`LogControl1`].
+///
+/// See [`LogControl1::with_authorization`], and note that the hook gets no
+/// caller for changes requested through property writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation<'a> {
+    /// Set the `LogLevel` property to the given level.
+    ///
+    /// The hook gets the caller only if the `SetLogLevelNumeric` method
+    /// requested this change, and `None` for property writes.
+    SetLevel(LogLevel),
+    /// Set the `LogTarget` property to the given target.
+    ///
+    /// The hook never gets a caller for this operation.
+    SetTarget(&'a str),
+    /// Change level and target together with the `Reconfigure` method.
+    Reconfigure {
+        /// The requested level.
+        level: LogLevel,
+        /// The requested target.
+        target: &'a str,
+    },
+    /// Set the `Muted` property to the given value.
+    ///
+    /// The hook never gets a caller for this operation.
+    SetMuted(bool),
+    /// Reopen the current target with the `Reopen` method.
+    Reopen,
+}
+
+/// A mapper from control errors to DBus errors, see [`LogControl1::new_with_error_mapper`].
+type ErrorMapper = Box<dyn Fn(LogControl1Error) -> zbus::fdo::Error + Send + Sync>;
+
+/// A hook to authorize changes, see [`LogControl1::with_authorization`].
+type AuthorizationHook =
+    Box<dyn Fn(&Operation<'_>, Option<&OwnedUniqueName>) -> zbus::fdo::Result<()> + Send + Sync>;
+
 /// A [`zbus`] frontend for [`logcontrol::LogControl1`].
 ///
-/// See [crate documentation][`logcontrol-zbus`] for an example and further
+/// See [crate documentation][crate] for an example and further
 /// usage instructions.
+///
+/// The frontend remembers the last successful changes of the log level and the
+/// log target, and exposes them through the non-standard `GetChangeHistory`
+/// method.
 pub struct LogControl1<C>
 where
     C: logcontrol::LogControl1 + Send + Sync,
 {
     control: C,
+    history_size: usize,
+    history: VecDeque<Change>,
+    authorization: Option<AuthorizationHook>,
+    error_mapper: ErrorMapper,
 }
 
 impl<C> LogControl1<C>
@@ -68,8 +201,160 @@ where
     C: logcontrol::LogControl1 + Send + Sync + 'static,
 {
     /// Create a new DBus interface around the given log control interface.
+    ///
+    /// The interface remembers the last [`DEFAULT_HISTORY_SIZE`] changes.
     pub fn new(control: C) -> Self {
-        Self { control }
+        Self::with_history_size(control, DEFAULT_HISTORY_SIZE)
+    }
+
+    /// Create a new DBus interface which remembers the last `history_size` changes.
+    ///
+    /// Set `history_size` to 0 to disable the history.
+    pub fn with_history_size(control: C, history_size: usize) -> Self {
+        Self {
+            control,
+            history_size,
+            history: VecDeque::with_capacity(history_size),
+            authorization: None,
+            error_mapper: Box::new(to_fdo_error),
+        }
+    }
+
+    /// Create a new DBus interface which maps errors of `control` with `mapper`.
+    ///
+    /// Call `mapper` for every error of `control`, to turn it into the error
+    /// to return over DBus, e.g. to add context, or to report failures with
+    /// a DBus error name of the service.  By default the interface maps
+    /// errors with [`to_fdo_error`].
+    ///
+    /// Malformed requests and rejected authorizations do not reach `control`,
+    /// and thus do not pass through `mapper`.  The interface remembers the last
+    /// [`DEFAULT_HISTORY_SIZE`] changes.
+    ///
+    /// ```rust
+    /// # use logcontrol::test_util::TestLogControl1;
+    /// # use logcontrol::{KnownLogTarget, LogLevel};
+    /// use logcontrol::LogControl1Error;
+    /// use logcontrol_zbus::LogControl1;
+    ///
+    /// let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+    /// let iface = LogControl1::new_with_error_mapper(control, |error| match error {
+    ///     LogControl1Error::Failure(message) => {
+    ///         zbus::fdo::Error::Failed(format!("Logging setup failed: {message}"))
+    ///     }
+    ///     other => logcontrol_zbus::to_fdo_error(other),
+    /// });
+    /// ```
+    pub fn new_with_error_mapper<F>(control: C, mapper: F) -> Self
+    where
+        F: Fn(LogControl1Error) -> zbus::fdo::Error + Send + Sync + 'static,
+    {
+        Self {
+            error_mapper: Box::new(mapper),
+            ..Self::new(control)
+        }
+    }
+
+    /// Authorize all changes with the given `hook`.
+    ///
+    /// Before changing the level or the target, call `hook` with the requested
+    /// change and the unique bus name of the caller, and reject the change
+    /// with the error of `hook`, if any, e.g.
+    /// [`zbus::fdo::Error::AccessDenied`].  The hook only sees well-formed
+    /// requests; malformed levels or targets fail before.
+    ///
+    /// # Caller of property writes
+    ///
+    /// **`hook` gets no caller for property writes.**  zbus does not pass the
+    /// message header to property setters, so `hook` gets `None` as caller for
+    /// [`Operation::SetLevel`], [`Operation::SetTarget`], and
+    /// [`Operation::SetMuted`] from writes to the `LogLevel`, `LogTarget`, and
+    /// `Muted` properties, which is what `systemctl service-log-level` and
+    /// `systemctl service-log-target` do.  Only the methods `Reconfigure`,
+    /// `SetLogLevelNumeric`, and `Reopen` pass the caller to `hook`.
+    ///
+    /// A hook which decides by caller can thus not tell apart callers of
+    /// property writes; it must either allow or deny property writes from all
+    /// callers.  Use DBus policy to restrict who may write properties instead.
+    /// Peer-to-peer connections have no caller name at all.
+    ///
+    /// ```rust
+    /// # use logcontrol::test_util::TestLogControl1;
+    /// # use logcontrol::{KnownLogTarget, LogLevel};
+    /// use logcontrol_zbus::{LogControl1, Operation};
+    ///
+    /// // Never allow debug logging over DBus
+    /// let iface = LogControl1::new(TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal))
+    ///     .with_authorization(|operation, _caller| match operation {
+    ///         Operation::SetLevel(LogLevel::Debug)
+    ///         | Operation::Reconfigure { level: LogLevel::Debug, .. } => Err(
+    ///             zbus::fdo::Error::AccessDenied("Debug logging is disabled".to_string()),
+    ///         ),
+    ///         _ => Ok(()),
+    ///     });
+    /// ```
+    pub fn with_authorization<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Operation<'_>, Option<&OwnedUniqueName>) -> zbus::fdo::Result<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.authorization = Some(Box::new(hook));
+        self
+    }
+
+    /// Serve this interface on the given connection `builder`.
+    ///
+    /// Work with both the async `zbus::connection::Builder` and the blocking
+    /// `zbus::blocking::connection::Builder`, e.g. for services which set up
+    /// their connection before they start an async runtime.  See
+    /// [`ConnectionBuilderExt::serve_log_control`].
+    pub fn serve_on<B: AnyConnectionBuilder>(self, builder: B) -> zbus::Result<B> {
+        builder.serve_iface(self)
+    }
+
+    /// Ask the authorization hook, if any, whether `caller` may apply `operation`.
+    fn authorize(
+        &self,
+        operation: &Operation<'_>,
+        caller: Option<&OwnedUniqueName>,
+    ) -> zbus::fdo::Result<()> {
+        self.authorization
+            .as_ref()
+            .map_or(Ok(()), |hook| hook(operation, caller))
+    }
+
+    /// Fail if the underlying control reports that logging does not work.
+    fn check_healthy(&self) -> zbus::fdo::Result<()> {
+        if self.control.is_healthy() {
+            Ok(())
+        } else {
+            Err(zbus::fdo::Error::Failed(
+                "Logging does not work, see the Healthy property".to_string(),
+            ))
+        }
+    }
+
+    /// Remember a change of `property` from `old` to `new`.
+    fn record_change(&mut self, property: &'static str, old: String, new: String) {
+        if self.history_size == 0 {
+            return;
+        }
+        if self.history.len() == self.history_size {
+            self.history.pop_front();
+        }
+        let timestamp_usec = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| {
+                u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+            });
+        self.history.push_back(Change {
+            timestamp_usec,
+            property,
+            old,
+            new,
+        });
     }
 }
 
@@ -88,11 +373,21 @@ where
     }
 
     /// Set the new log level.
+    ///
+    /// Record the change in the history only if the level actually changed;
+    /// zbus emits `PropertiesChanged` for every successful write though.
     #[zbus(property)]
     fn set_log_level(&mut self, level: String) -> zbus::fdo::Result<()> {
         let level = LogLevel::try_from(level.as_str())
             .map_err(|error| zbus::fdo::Error::InvalidArgs(error.to_string()))?;
-        self.control.set_level(level).map_err(to_fdo_error)
+        self.authorize(&Operation::SetLevel(level), None)?;
+        let old = self.control.level();
+        self.control.set_level(level).map_err(&self.error_mapper)?;
+        let new = self.control.level();
+        if old != new {
+            self.record_change(PROP_LOG_LEVEL, old.to_string(), new.to_string());
+        }
+        Ok(())
     }
 
     /// Get the currently configured log target.
@@ -102,9 +397,21 @@ where
     }
 
     /// Change the log target.
+    ///
+    /// Like `LogLevel`, record the change only if the target actually changed.
     #[zbus(property)]
     async fn set_log_target(&mut self, target: String) -> zbus::fdo::Result<()> {
-        self.control.set_target(target).map_err(to_fdo_error)
+        let target = trim_target(&target)?;
+        self.authorize(&Operation::SetTarget(target), None)?;
+        let old = self.control.target().to_string();
+        self.control
+            .set_target(target)
+            .map_err(&self.error_mapper)?;
+        let new = self.control.target().to_string();
+        if old != new {
+            self.record_change(PROP_LOG_TARGET, old, new);
+        }
+        Ok(())
     }
 
     /// Get the syslog identifier used by the service.
@@ -112,31 +419,1271 @@ where
     fn syslog_identifier(&self) -> &str {
         self.control.syslog_identifier()
     }
+
+    /// Whether logging currently works.
+    ///
+    /// This property is a non-standard extension; it reports
+    /// [`logcontrol::LogControl1::is_healthy`] to surface silent logging
+    /// failures, e.g. when the journal is no longer reachable.
+    #[zbus(property)]
+    fn healthy(&self) -> bool {
+        self.control.is_healthy()
+    }
+
+    /// The name of the logging framework of the service.
+    ///
+    /// This property is a non-standard extension; it reports
+    /// [`logcontrol::LogControl1::backend_name`] for diagnostics tools.
+    #[zbus(property)]
+    fn backend(&self) -> &str {
+        self.control.backend_name()
+    }
+
+    /// The optional capabilities of the service's log control.
+    ///
+    /// This property is a non-standard extension; it reports the names of
+    /// [`logcontrol::LogControl1::capabilities`], e.g. `mute` or `reopen`, so
+    /// that tools can tell which extensions actually work.
+    #[zbus(property)]
+    fn capabilities(&self) -> Vec<&'static str> {
+        self.control.capabilities().names()
+    }
+
+    /// Whether logging is muted.
+    ///
+    /// This property is a non-standard extension; see
+    /// [`logcontrol::LogControl1::set_muted`].  While muted the service keeps
+    /// its level and target, and restores both when unmuted.
+    #[zbus(property)]
+    fn muted(&self) -> bool {
+        self.control.is_muted()
+    }
+
+    /// Mute or unmute logging.
+    #[zbus(property)]
+    fn set_muted(&mut self, muted: bool) -> zbus::fdo::Result<()> {
+        self.authorize(&Operation::SetMuted(muted), None)?;
+        self.control.set_muted(muted).map_err(&self.error_mapper)
+    }
+
+    /// Get the currently configured log level.
+    ///
+    /// Returns the same value as the `LogLevel` property, as a method call for
+    /// clients which cannot easily read properties.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` only has properties.  Clients which talk
+    /// to arbitrary services must use the `LogLevel` property instead.
+    ///
+    /// Unlike the property, fail with `Failed` if logging does not work, see
+    /// [`logcontrol::LogControl1::is_healthy`].
+    fn get_log_level(&self) -> zbus::fdo::Result<String> {
+        self.check_healthy()?;
+        Ok(self.control.level().to_string())
+    }
+
+    /// Get the syslog priority of the currently configured log level.
+    ///
+    /// Returns the level of the `LogLevel` property as number from `0` for
+    /// `emerg` to `7` for `debug`, see [`LogLevel::as_priority`], for clients
+    /// which prefer numeric levels.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` only has properties.
+    fn get_log_level_numeric(&self) -> u8 {
+        self.control.level().as_priority()
+    }
+
+    /// Change the log level to the given syslog priority.
+    ///
+    /// Like setting the `LogLevel` property, but with a number from `0` for
+    /// `emerg` to `7` for `debug`.  Reject other numbers with `InvalidArgs`.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` only has properties.
+    async fn set_log_level_numeric(
+        &mut self,
+        priority: u8,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        let level = LogLevel::try_from(priority).map_err(|_| {
+            zbus::fdo::Error::InvalidArgs(format!(
+                "Log level priority must be between 0 and 7, got {priority}"
+            ))
+        })?;
+        let caller = header.sender().map(|sender| sender.to_owned().into());
+        self.authorize(&Operation::SetLevel(level), caller.as_ref())?;
+        let old = self.control.level();
+        self.control.set_level(level).map_err(&self.error_mapper)?;
+        let new = self.control.level();
+        if old != new {
+            self.record_change(PROP_LOG_LEVEL, old.to_string(), new.to_string());
+            self.log_level_changed(&emitter).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the currently configured log target.
+    ///
+    /// Returns the same value as the `LogTarget` property, as a method call for
+    /// clients which cannot easily read properties.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` only has properties.  Clients which talk
+    /// to arbitrary services must use the `LogTarget` property instead.
+    ///
+    /// Like `GetLogLevel`, fail with `Failed` if logging does not work.
+    fn get_log_target(&self) -> zbus::fdo::Result<String> {
+        self.check_healthy()?;
+        Ok(self.control.target().to_string())
+    }
+
+    /// Change the log level and the log target together.
+    ///
+    /// Unlike writing the `LogLevel` and `LogTarget` properties one after
+    /// another, this method never exposes the new target with the old level,
+    /// if the underlying control implements [`logcontrol::LogControl1::reconfigure`]
+    /// atomically.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` only has properties.
+    async fn reconfigure(
+        &mut self,
+        level: String,
+        target: String,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        let level = LogLevel::try_from(level.as_str())
+            .map_err(|error| zbus::fdo::Error::InvalidArgs(error.to_string()))?;
+        let target = trim_target(&target)?;
+        let caller = header.sender().map(|sender| sender.to_owned().into());
+        self.authorize(&Operation::Reconfigure { level, target }, caller.as_ref())?;
+        let old_level = self.control.level();
+        let old_target = self.control.target().to_string();
+        let result = self
+            .control
+            .reconfigure(level, target)
+            .map_err(&self.error_mapper);
+        // Record and announce whatever changed, even if reconfiguring failed halfway.
+        let new_target = self.control.target().to_string();
+        if new_target != old_target {
+            self.record_change(PROP_LOG_TARGET, old_target, new_target);
+            self.log_target_changed(&emitter).await?;
+        }
+        let new_level = self.control.level();
+        if new_level != old_level {
+            self.record_change(PROP_LOG_LEVEL, old_level.to_string(), new_level.to_string());
+            self.log_level_changed(&emitter).await?;
+        }
+        result
+    }
+
+    /// Reopen the current log target.
+    ///
+    /// Close and recreate the logger for the current target, see
+    /// [`logcontrol::LogControl1::reopen`], e.g. from a logrotate `postrotate`
+    /// script.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` has no such method.
+    async fn reopen(&mut self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        let caller = header.sender().map(|sender| sender.to_owned().into());
+        self.authorize(&Operation::Reopen, caller.as_ref())?;
+        self.control.reopen().map_err(&self.error_mapper)
+    }
+
+    /// Get the last successful changes of the log level and the log target.
+    ///
+    /// Return an array of `(timestamp, property, old, new)` structs, oldest
+    /// change first.  `timestamp` is the time of the change in microseconds
+    /// since the Unix epoch, `property` is either `LogLevel` or `LogTarget`,
+    /// and `old` and `new` are the values before and after the change.
+    ///
+    /// This method is a non-standard extension; systemd's definition of
+    /// `org.freedesktop.LogControl1` has no such method.
+    fn get_change_history(&self) -> Vec<(u64, String, String, String)> {
+        self.history
+            .iter()
+            .map(|change| {
+                (
+                    change.timestamp_usec,
+                    change.property.to_string(),
+                    change.old.clone(),
+                    change.new.clone(),
+                )
+            })
+            .collect()
+    }
 }
 
 /// Extend `ConnectionBuilder` to serve log control interfaces.
 pub trait ConnectionBuilderExt {
     /// Serve the given log control interface on this connection builder.
+    ///
+    /// This only adds the interface under [`DBUS_OBJ_PATH`], and leaves name
+    /// ownership alone: request the bus name for the `BusName` of the unit
+    /// separately, e.g. with `name` on the builder, or through some other
+    /// mechanism.
+    ///
+    /// Return the builder, to serve further interfaces of the service at their
+    /// own paths:
+    ///
+    /// ```no_run
+    /// # use logcontrol::test_util::TestLogControl1;
+    /// # use logcontrol::{KnownLogTarget, LogLevel};
+    /// use logcontrol_zbus::{ConnectionBuilderExt, LogControl1};
+    ///
+    /// struct Greeter;
+    ///
+    /// #[zbus::interface(name = "de.swsnr.logcontrol.Greeter")]
+    /// impl Greeter {
+    ///     fn greet(&self, name: &str) -> String {
+    ///         format!("Hello {name}")
+    ///     }
+    /// }
+    ///
+    /// # let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+    /// let _conn = zbus::blocking::connection::Builder::session()?
+    ///     .name("de.swsnr.logcontrol.Example")?
+    ///     .serve_log_control(LogControl1::new(control))?
+    ///     .serve_at("/de/swsnr/logcontrol/Greeter", Greeter)?
+    ///     .build()?;
+    /// # Ok::<(), zbus::Error>(())
+    /// ```
     fn serve_log_control<C>(self, iface: LogControl1<C>) -> zbus::Result<Self>
     where
         Self: Sized,
         C: logcontrol::LogControl1 + Send + Sync + 'static;
+
+    /// Request the bus `name` and serve the given log control interface.
+    ///
+    /// Check `name` with [`validate_bus_name`] first, and fail with a clear
+    /// error if it's not a valid well-known bus name.  `name` must match the
+    /// `BusName` of the unit for `systemctl` to find the interface.
+    fn serve_log_control_with_name<C>(
+        self,
+        name: &str,
+        iface: LogControl1<C>,
+    ) -> zbus::Result<Self>
+    where
+        Self: Sized,
+        C: logcontrol::LogControl1 + Send + Sync + 'static;
+}
+
+mod sealed {
+    use crate::LogControl1;
+
+    /// The operations of connection builders which serve log control interfaces.
+    pub trait Sealed: Sized {
+        /// Serve `iface` under [`logcontrol::DBUS_OBJ_PATH`].
+        fn serve_iface<C>(self, iface: LogControl1<C>) -> zbus::Result<Self>
+        where
+            C: logcontrol::LogControl1 + Send + Sync + 'static;
+
+        /// Request the given bus `name`.
+        fn request_name(self, name: zbus::names::WellKnownName<'static>) -> zbus::Result<Self>;
+    }
+
+    impl Sealed for zbus::connection::Builder<'_> {
+        fn serve_iface<C>(self, iface: LogControl1<C>) -> zbus::Result<Self>
+        where
+            C: logcontrol::LogControl1 + Send + Sync + 'static,
+        {
+            self.serve_at(logcontrol::DBUS_OBJ_PATH, iface)
+        }
+
+        fn request_name(self, name: zbus::names::WellKnownName<'static>) -> zbus::Result<Self> {
+            self.name(name)
+        }
+    }
+
+    impl Sealed for zbus::blocking::connection::Builder<'_> {
+        fn serve_iface<C>(self, iface: LogControl1<C>) -> zbus::Result<Self>
+        where
+            C: logcontrol::LogControl1 + Send + Sync + 'static,
+        {
+            self.serve_at(logcontrol::DBUS_OBJ_PATH, iface)
+        }
+
+        fn request_name(self, name: zbus::names::WellKnownName<'static>) -> zbus::Result<Self> {
+            self.name(name)
+        }
+    }
 }
 
-impl ConnectionBuilderExt for zbus::connection::Builder<'_> {
+/// A zbus connection builder, either `zbus::connection::Builder` or
+/// `zbus::blocking::connection::Builder`.
+///
+/// This trait is sealed; it lets [`LogControl1::serve_on`] and
+/// [`ConnectionBuilderExt`] work with both builders.
+pub trait AnyConnectionBuilder: sealed::Sealed {}
+
+impl AnyConnectionBuilder for zbus::connection::Builder<'_> {}
+
+impl AnyConnectionBuilder for zbus::blocking::connection::Builder<'_> {}
+
+impl<B: AnyConnectionBuilder> ConnectionBuilderExt for B {
     fn serve_log_control<C>(self, iface: LogControl1<C>) -> zbus::Result<Self>
     where
         C: logcontrol::LogControl1 + Send + Sync + 'static,
     {
-        self.serve_at(DBUS_OBJ_PATH, iface)
+        self.serve_iface(iface)
     }
-}
 
-impl ConnectionBuilderExt for zbus::blocking::connection::Builder<'_> {
-    fn serve_log_control<C>(self, iface: LogControl1<C>) -> zbus::Result<Self>
+    fn serve_log_control_with_name<C>(self, name: &str, iface: LogControl1<C>) -> zbus::Result<Self>
     where
         C: logcontrol::LogControl1 + Send + Sync + 'static,
     {
-        self.serve_at(DBUS_OBJ_PATH, iface)
+        self.request_name(validate_bus_name(name)?.into_owned())?
+            .serve_iface(iface)
+    }
+}
+
+/// Check that `name` is a valid well-known DBus name.
+///
+/// This can't tell whether `name` matches the `BusName` of the unit, but it
+/// catches typos early, with an error which names the offending bus name,
+/// instead of a generic error when building the connection.
+pub fn validate_bus_name(name: &str) -> zbus::Result<zbus::names::WellKnownName<'_>> {
+    zbus::names::WellKnownName::try_from(name).map_err(|error| {
+        zbus::Error::Failure(format!(
+            "Invalid bus name {name:?} for the log control interface: {error}"
+        ))
+    })
+}
+
+/// Assert at compile time that `C` can be served over DBus.
+///
+/// This function does nothing at runtime.  [`LogControl1`] requires the
+/// underlying control to be `Send + Sync + 'static`; if a custom control
+/// accidentally holds a field which is not, e.g. an `Rc` or a `RefCell`, the
+/// compiler reports the missing bound far away from the offending type, deep
+/// inside the generated interface code.  Call this function in a test of the
+/// custom control to get an error which names the control instead:
+///
+/// ```
+/// # use logcontrol::*;
+/// struct Settings {
+///     level: LogLevel,
+///     target: KnownLogTarget,
+///     name: String,
+/// }
+///
+/// logcontrol::impl_log_control1!(Settings { level: level, target: target, syslog_identifier: name });
+///
+/// logcontrol_zbus::assert_sendable::<Settings>();
+/// ```
+pub fn assert_sendable<C>()
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+}
+
+/// Serve the given log control interface on an existing connection.
+///
+/// Add `iface` under [`DBUS_OBJ_PATH`] to the object server of `conn`.  Like
+/// [`ConnectionBuilderExt::serve_log_control`] this function does not touch
+/// name ownership, so it works for connections which already own the bus
+/// name of the service, or which obtain it later.
+///
+/// If `conn` serves no other interface yet, its object server starts in the
+/// background, and may miss method calls which arrive in the meantime.  Prefer
+/// [`ConnectionBuilderExt::serve_log_control`] for new connections.
+///
+/// Return `false` if `conn` already serves a log control interface.
+pub async fn register_log_control<C>(
+    conn: &zbus::Connection,
+    iface: LogControl1<C>,
+) -> zbus::Result<bool>
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+    conn.object_server().at(DBUS_OBJ_PATH, iface).await
+}
+
+/// A placeholder control, to introspect the interface without a real control.
+struct IntrospectionOnly;
+
+impl logcontrol::LogControl1 for IntrospectionOnly {
+    fn level(&self) -> LogLevel {
+        LogLevel::Info
+    }
+
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        Err(LogControl1Error::UnsupportedLogLevel(level))
+    }
+
+    fn target(&self) -> &str {
+        "null"
+    }
+
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        Err(LogControl1Error::UnsupportedLogTarget(
+            target.as_ref().to_string(),
+        ))
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        ""
+    }
+}
+
+/// Get the introspection XML of the log control interface.
+///
+/// Return the XML which [`LogControl1`] reports for [`DBUS_OBJ_PATH`] in
+/// response to `org.freedesktop.DBus.Introspectable.Introspect`, restricted to
+/// the log control interface itself, as a complete introspection document.
+/// This allows to compare the interface against the definition of systemd
+/// offline, e.g. in CI.
+pub fn introspection_xml() -> String {
+    let mut xml = String::from(
+        "<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\"
+ \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">
+<node>
+",
+    );
+    zbus::object_server::Interface::introspect_to_writer(
+        &LogControl1::new(IntrospectionOnly),
+        &mut xml,
+        2,
+    );
+    xml.push_str("</node>\n");
+    xml
+}
+
+/// Check whether `bus_name` exposes the log control interface.
+///
+/// Introspect [`DBUS_OBJ_PATH`] of `bus_name` on `conn`, and return whether
+/// the object implements `org.freedesktop.LogControl1`.
+///
+/// Return an error if introspection fails, e.g. because `bus_name` is not
+/// on the bus.
+#[cfg(feature = "client")]
+pub async fn has_log_control(conn: &zbus::Connection, bus_name: &str) -> zbus::Result<bool> {
+    let proxy = zbus::fdo::IntrospectableProxy::builder(conn)
+        .destination(bus_name)?
+        .path(DBUS_OBJ_PATH)?
+        .build()
+        .await?;
+    implements_log_control(&proxy.introspect().await?)
+}
+
+/// Whether the introspection `xml` of an object has the log control interface.
+#[cfg(feature = "client")]
+fn implements_log_control(xml: &str) -> zbus::Result<bool> {
+    let node = zbus_xml::Node::from_reader(xml.as_bytes())
+        .map_err(|error| zbus::Error::Failure(format!("Invalid introspection data: {error}")))?;
+    Ok(node
+        .interfaces()
+        .iter()
+        .any(|interface| interface.name() == INTERFACE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    use async_std::stream::StreamExt;
+    use logcontrol::test_util::TestLogControl1;
+    use logcontrol::{
+        Debounced, KnownLogTarget, LogControl1Operation, LogLevel, Logged, SharedLogControl1,
+    };
+    use static_assertions::assert_impl_all;
+    use zbus::{proxy, Guid};
+
+    use crate::{
+        assert_sendable, validate_bus_name, ConnectionBuilderExt, IntrospectionOnly, LogControl1,
+        Operation, DBUS_OBJ_PATH, INTERFACE_NAME, PROP_LOG_LEVEL, PROP_LOG_TARGET,
+        PROP_SYSLOG_IDENTIFIER,
+    };
+
+    type LoggedTestLogControl1 = Logged<TestLogControl1, fn(&LogControl1Operation<'_>)>;
+
+    // The wrappers of logcontrol must remain servable over DBus.
+    assert_impl_all!(SharedLogControl1<TestLogControl1>: Send, Sync);
+    assert_impl_all!(Debounced<TestLogControl1>: Send, Sync);
+    assert_impl_all!(LoggedTestLogControl1: Send, Sync);
+    assert_impl_all!(LogControl1<SharedLogControl1<TestLogControl1>>: Send, Sync);
+
+    #[test]
+    fn wrappers_are_sendable() {
+        assert_sendable::<TestLogControl1>();
+        assert_sendable::<SharedLogControl1<TestLogControl1>>();
+        assert_sendable::<Debounced<SharedLogControl1<TestLogControl1>>>();
+        assert_sendable::<LoggedTestLogControl1>();
+        assert_sendable::<
+            logcontrol_log::LogController<logcontrol_log::WriterLogFactory<std::io::Stderr>>,
+        >();
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.LogControl1",
+        default_path = "/org/freedesktop/LogControl1",
+        gen_blocking = false
+    )]
+    trait LogControl1Client {
+        fn get_log_level(&self) -> zbus::Result<String>;
+
+        fn get_log_target(&self) -> zbus::Result<String>;
+
+        fn get_change_history(&self) -> zbus::Result<Vec<(u64, String, String, String)>>;
+
+        fn reconfigure(&self, level: &str, target: &str) -> zbus::Result<()>;
+
+        fn reopen(&self) -> zbus::Result<()>;
+
+        fn get_log_level_numeric(&self) -> zbus::Result<u8>;
+
+        fn set_log_level_numeric(&self, priority: u8) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn log_level(&self) -> zbus::Result<String>;
+
+        #[zbus(property)]
+        fn set_log_level(&self, level: &str) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn log_target(&self) -> zbus::Result<String>;
+
+        #[zbus(property)]
+        fn set_log_target(&self, target: &str) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn healthy(&self) -> zbus::Result<bool>;
+
+        #[zbus(property)]
+        fn backend(&self) -> zbus::Result<String>;
+
+        #[zbus(property)]
+        fn capabilities(&self) -> zbus::Result<Vec<String>>;
+
+        #[zbus(property)]
+        fn muted(&self) -> zbus::Result<bool>;
+
+        #[zbus(property)]
+        fn set_muted(&self, muted: bool) -> zbus::Result<()>;
+    }
+
+    /// Serve `iface` under [`DBUS_OBJ_PATH`] over a peer-to-peer connection.
+    ///
+    /// Return the server and client connections.
+    async fn serve_p2p<I>(iface: I) -> (zbus::Connection, zbus::Connection)
+    where
+        I: zbus::object_server::Interface,
+    {
+        let (server_socket, client_socket) = UnixStream::pair().unwrap();
+        let server = async_std::task::spawn(
+            zbus::connection::Builder::unix_stream(server_socket)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at(DBUS_OBJ_PATH, iface)
+                .unwrap()
+                .build(),
+        );
+        let client = zbus::connection::Builder::unix_stream(client_socket)
+            .p2p()
+            .build()
+            .await
+            .unwrap();
+        (server.await.unwrap(), client)
+    }
+
+    #[test]
+    fn reload_failed_maps_to_failed() {
+        let error = logcontrol::LogControl1Error::reload_failed(
+            "Failed to reload level layer",
+            std::io::Error::new(std::io::ErrorKind::Other, "subscriber gone"),
+        );
+        assert!(matches!(
+            crate::to_fdo_error(error),
+            zbus::fdo::Error::Failed(message)
+                if message == "Failed to reload level layer: subscriber gone"
+        ));
+    }
+
+    struct Greeter;
+
+    #[zbus::interface(name = "de.swsnr.logcontrol.Greeter")]
+    impl Greeter {
+        fn greet(&self, name: &str) -> String {
+            format!("Hello {name}")
+        }
+    }
+
+    #[async_std::test]
+    async fn serve_log_control_with_other_interface() {
+        let (server_socket, client_socket) = UnixStream::pair().unwrap();
+        let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+        let server = async_std::task::spawn(
+            zbus::connection::Builder::unix_stream(server_socket)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_log_control(LogControl1::new(control))
+                .unwrap()
+                .serve_at("/de/swsnr/logcontrol/Greeter", Greeter)
+                .unwrap()
+                .build(),
+        );
+        let client = zbus::connection::Builder::unix_stream(client_socket)
+            .p2p()
+            .build()
+            .await
+            .unwrap();
+        let _server = server.await.unwrap();
+
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "info");
+        let reply = client
+            .call_method(
+                None::<&str>,
+                "/de/swsnr/logcontrol/Greeter",
+                Some("de.swsnr.logcontrol.Greeter"),
+                "Greet",
+                &("logcontrol"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            reply.body().deserialize::<String>().unwrap(),
+            "Hello logcontrol"
+        );
+    }
+
+    #[async_std::test]
+    async fn get_log_level_and_target() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "info");
+        assert_eq!(proxy.get_log_target().await.unwrap(), "journal");
+        assert_eq!(proxy.log_level().await.unwrap(), "info");
+        assert_eq!(proxy.log_target().await.unwrap(), "journal");
+
+        proxy.set_log_level("debug").await.unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "debug");
+    }
+
+    #[async_std::test]
+    async fn change_history() {
+        let (_server, client) = serve_p2p(LogControl1::with_history_size(
+            TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal),
+            3,
+        ))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert!(proxy.get_change_history().await.unwrap().is_empty());
+
+        proxy.set_log_level("debug").await.unwrap();
+        proxy.set_log_target("auto").await.unwrap();
+        // Failed changes are not recorded
+        assert!(proxy.set_log_target("kmsg").await.is_err());
+        proxy.set_log_level("warning").await.unwrap();
+        proxy.set_log_target("null").await.unwrap();
+        // Writes which change nothing are not recorded either
+        proxy.set_log_level("warning").await.unwrap();
+        proxy.set_log_target("null").await.unwrap();
+        proxy.set_log_level_numeric(4).await.unwrap();
+
+        let history = proxy.get_change_history().await.unwrap();
+        let changes = history
+            .iter()
+            .map(|(_, property, old, new)| (property.as_str(), old.as_str(), new.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                ("LogTarget", "journal", "console"),
+                ("LogLevel", "debug", "warning"),
+                ("LogTarget", "console", "null"),
+            ]
+        );
+        let timestamps = history.iter().map(|change| change.0).collect::<Vec<_>>();
+        assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    /// Get the name of a DBus error returned from a method call or a property write.
+    fn error_name(error: zbus::Error) -> String {
+        match error {
+            zbus::Error::MethodError(name, _, _) => name.to_string(),
+            zbus::Error::FDO(error) => zbus::DBusError::name(&*error).to_string(),
+            other => panic!("Unexpected error: {other:?}"),
+        }
+    }
+
+    #[async_std::test]
+    async fn reconfigure() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        // Populate the property cache of the proxy
+        assert_eq!(proxy.log_target().await.unwrap(), "journal");
+        let mut target_changes = proxy.receive_log_target_changed().await;
+        proxy.reconfigure("debug", "console").await.unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "debug");
+        assert_eq!(proxy.get_log_target().await.unwrap(), "console");
+        // Reconfigure announces its changes, which updates the cached property
+        async_std::future::timeout(Duration::from_secs(5), async {
+            while let Some(change) = target_changes.next().await {
+                if change.get().await.unwrap() == "console" {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(proxy.log_target().await.unwrap(), "console");
+
+        assert_eq!(
+            error_name(proxy.reconfigure("foo", "null").await.unwrap_err()),
+            "org.freedesktop.DBus.Error.InvalidArgs"
+        );
+        assert_eq!(
+            error_name(proxy.reconfigure("warning", "kmsg").await.unwrap_err()),
+            "org.freedesktop.DBus.Error.NotSupported"
+        );
+        assert_eq!(proxy.get_log_level().await.unwrap(), "debug");
+        assert_eq!(proxy.get_log_target().await.unwrap(), "console");
+
+        let history = proxy.get_change_history().await.unwrap();
+        let changes = history
+            .iter()
+            .map(|(_, property, old, new)| (property.as_str(), old.as_str(), new.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                ("LogTarget", "journal", "console"),
+                ("LogLevel", "info", "debug"),
+            ]
+        );
+    }
+
+    #[async_std::test]
+    async fn authorization() {
+        let iface = LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        ))
+        .with_authorization(|operation, caller| {
+            // Peer-to-peer connections have no unique names
+            assert!(caller.is_none());
+            match operation {
+                Operation::SetTarget(_) => Ok(()),
+                Operation::SetLevel(_)
+                | Operation::Reconfigure { .. }
+                | Operation::SetMuted(_)
+                | Operation::Reopen => Err(zbus::fdo::Error::AccessDenied(
+                    "Level changes denied".to_string(),
+                )),
+            }
+        });
+        let (_server, client) = serve_p2p(iface).await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            error_name(proxy.set_log_level("debug").await.unwrap_err()),
+            "org.freedesktop.DBus.Error.AccessDenied"
+        );
+        assert_eq!(
+            error_name(proxy.reconfigure("debug", "console").await.unwrap_err()),
+            "org.freedesktop.DBus.Error.AccessDenied"
+        );
+        assert_eq!(
+            error_name(proxy.set_muted(true).await.unwrap_err()),
+            "org.freedesktop.DBus.Error.AccessDenied"
+        );
+        assert!(!proxy.muted().await.unwrap());
+        assert_eq!(
+            error_name(proxy.reopen().await.unwrap_err()),
+            "org.freedesktop.DBus.Error.AccessDenied"
+        );
+        assert_eq!(
+            error_name(proxy.set_log_level_numeric(7).await.unwrap_err()),
+            "org.freedesktop.DBus.Error.AccessDenied"
+        );
+        // Malformed requests fail before authorization
+        assert_eq!(
+            error_name(proxy.set_log_level("foo").await.unwrap_err()),
+            "org.freedesktop.DBus.Error.InvalidArgs"
+        );
+        proxy.set_log_target("console").await.unwrap();
+
+        assert_eq!(proxy.get_log_level().await.unwrap(), "info");
+        assert_eq!(proxy.get_log_target().await.unwrap(), "console");
+        let history = proxy.get_change_history().await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].1, "LogTarget");
+    }
+
+    #[async_std::test]
+    async fn empty_target() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        for target in ["", "  ", "\n"] {
+            assert_eq!(
+                error_name(proxy.set_log_target(target).await.unwrap_err()),
+                "org.freedesktop.DBus.Error.InvalidArgs"
+            );
+            assert_eq!(
+                error_name(proxy.reconfigure("debug", target).await.unwrap_err()),
+                "org.freedesktop.DBus.Error.InvalidArgs"
+            );
+        }
+        assert_eq!(proxy.log_level().await.unwrap(), "info");
+        assert_eq!(proxy.log_target().await.unwrap(), "journal");
+
+        proxy.set_log_target(" null\n").await.unwrap();
+        assert_eq!(proxy.get_log_target().await.unwrap(), "null");
+    }
+
+    #[async_std::test]
+    async fn introspection_uses_names() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = zbus::fdo::IntrospectableProxy::builder(&client)
+            .destination("de.swsnr.logcontrol.Test")
+            .unwrap()
+            .path(DBUS_OBJ_PATH)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let xml = proxy.introspect().await.unwrap();
+        assert!(xml.contains(&format!("<interface name=\"{INTERFACE_NAME}\">")));
+        for property in [PROP_LOG_LEVEL, PROP_LOG_TARGET, PROP_SYSLOG_IDENTIFIER] {
+            assert!(
+                xml.contains(&format!("<property name=\"{property}\"")),
+                "Property {property} missing in {xml}"
+            );
+        }
+    }
+
+    #[async_std::test]
+    async fn serve_on_async_builder() {
+        let (server_socket, client_socket) = UnixStream::pair().unwrap();
+        let iface = LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        ));
+        let server = async_std::task::spawn(
+            iface
+                .serve_on(
+                    zbus::connection::Builder::unix_stream(server_socket)
+                        .server(Guid::generate())
+                        .unwrap()
+                        .p2p(),
+                )
+                .unwrap()
+                .build(),
+        );
+        let client = zbus::connection::Builder::unix_stream(client_socket)
+            .p2p()
+            .build()
+            .await
+            .unwrap();
+        let _server = server.await.unwrap();
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "info");
+    }
+
+    #[async_std::test]
+    async fn serve_on_blocking_builder() {
+        let (server_socket, client_socket) = UnixStream::pair().unwrap();
+        let iface = LogControl1::new(TestLogControl1::new(
+            LogLevel::Debug,
+            KnownLogTarget::Console,
+        ));
+        let server = async_std::task::spawn_blocking(move || {
+            iface
+                .serve_on(
+                    zbus::blocking::connection::Builder::unix_stream(server_socket)
+                        .server(Guid::generate())
+                        .unwrap()
+                        .p2p(),
+                )
+                .unwrap()
+                .build()
+        });
+        let client = zbus::connection::Builder::unix_stream(client_socket)
+            .p2p()
+            .build()
+            .await
+            .unwrap();
+        let _server = server.await.unwrap();
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "debug");
+        assert_eq!(proxy.get_log_target().await.unwrap(), "console");
+    }
+
+    #[async_std::test]
+    async fn register_log_control() {
+        let control = || {
+            LogControl1::new(TestLogControl1::new(
+                LogLevel::Info,
+                KnownLogTarget::Journal,
+            ))
+        };
+
+        let (server_socket, client_socket) = UnixStream::pair().unwrap();
+        // Serve something else at build time, so that the object server runs
+        // before the connection reads the first call from the client.
+        // Otherwise the object server starts lazily in the background when
+        // registering the interface, and may miss the client's first call.
+        let server = async_std::task::spawn(
+            zbus::connection::Builder::unix_stream(server_socket)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/de/swsnr/logcontrol/Other", control())
+                .unwrap()
+                .build(),
+        );
+        let client = zbus::connection::Builder::unix_stream(client_socket)
+            .p2p()
+            .build()
+            .await
+            .unwrap();
+        let server = server.await.unwrap();
+
+        assert!(crate::register_log_control(&server, control())
+            .await
+            .unwrap());
+        assert!(!crate::register_log_control(&server, control())
+            .await
+            .unwrap());
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.get_log_level().await.unwrap(), "info");
+    }
+
+    #[test]
+    fn introspection_xml() {
+        let xml = crate::introspection_xml();
+        assert!(xml.contains(&format!("<interface name=\"{INTERFACE_NAME}\">")));
+        for property in [
+            "<property name=\"LogLevel\" type=\"s\" access=\"readwrite\"/>",
+            "<property name=\"LogTarget\" type=\"s\" access=\"readwrite\"/>",
+            "<property name=\"SyslogIdentifier\" type=\"s\" access=\"read\"/>",
+        ] {
+            assert!(
+                xml.contains(property),
+                "Property {property} missing in {xml}"
+            );
+        }
+    }
+
+    #[async_std::test]
+    async fn healthy() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert!(proxy.healthy().await.unwrap());
+    }
+
+    #[async_std::test]
+    async fn unhealthy() {
+        let (_server, client) = serve_p2p(LogControl1::new(
+            TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal).with_healthy(false),
+        ))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert!(!proxy.healthy().await.unwrap());
+        // The methods report the unhealthy logger, but the properties still work
+        assert_eq!(
+            error_name(proxy.get_log_level().await.unwrap_err()),
+            "org.freedesktop.DBus.Error.Failed"
+        );
+        assert_eq!(
+            error_name(proxy.get_log_target().await.unwrap_err()),
+            "org.freedesktop.DBus.Error.Failed"
+        );
+        assert_eq!(proxy.log_level().await.unwrap(), "info");
+    }
+
+    #[async_std::test]
+    async fn backend() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.backend().await.unwrap(), "unknown");
+    }
+
+    #[async_std::test]
+    async fn capabilities() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(
+            proxy.capabilities().await.unwrap(),
+            ["mute", "reopen", "health"]
+        );
+
+        let (_server, client) = serve_p2p(LogControl1::new(IntrospectionOnly)).await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert!(proxy.capabilities().await.unwrap().is_empty());
+    }
+
+    #[async_std::test]
+    async fn numeric_level() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        assert_eq!(proxy.get_log_level_numeric().await.unwrap(), 6);
+        for priority in 0..=7 {
+            proxy.set_log_level_numeric(priority).await.unwrap();
+            assert_eq!(proxy.get_log_level_numeric().await.unwrap(), priority);
+        }
+        assert_eq!(proxy.get_log_level().await.unwrap(), "debug");
+
+        for priority in [8, 42, u8::MAX] {
+            assert_eq!(
+                error_name(proxy.set_log_level_numeric(priority).await.unwrap_err()),
+                "org.freedesktop.DBus.Error.InvalidArgs"
+            );
+        }
+        assert_eq!(proxy.get_log_level().await.unwrap(), "debug");
+
+        let history = proxy.get_change_history().await.unwrap();
+        let last_change = history
+            .last()
+            .map(|(_, property, old, new)| (property.as_str(), old.as_str(), new.as_str()));
+        assert_eq!(last_change, Some(("LogLevel", "info", "debug")));
+    }
+
+    #[async_std::test]
+    async fn default_error_mapper() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        let error = proxy.set_log_target("kmsg").await.unwrap_err();
+        assert_eq!(error_name(error), "org.freedesktop.DBus.Error.NotSupported");
+    }
+
+    #[async_std::test]
+    async fn custom_error_mapper() {
+        let iface = LogControl1::new_with_error_mapper(
+            TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal),
+            |error| match error {
+                logcontrol::LogControl1Error::UnsupportedLogTarget(target) => {
+                    zbus::fdo::Error::InvalidArgs(format!("Use journal or console, not {target}"))
+                }
+                other => crate::to_fdo_error(other),
+            },
+        );
+        let (_server, client) = serve_p2p(iface).await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        match proxy.reconfigure("debug", "kmsg").await.unwrap_err() {
+            zbus::Error::MethodError(name, Some(message), _) => {
+                assert_eq!(name.as_str(), "org.freedesktop.DBus.Error.InvalidArgs");
+                assert_eq!(message, "Use journal or console, not kmsg");
+            }
+            other => panic!("Unexpected error: {other:?}"),
+        }
+        proxy.set_log_target("console").await.unwrap();
+    }
+
+    #[async_std::test]
+    async fn reopen() {
+        let (server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        let proxy = LogControl1ClientProxy::new(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap();
+        proxy.reopen().await.unwrap();
+        proxy.reopen().await.unwrap();
+        let iface = server
+            .object_server()
+            .interface::<_, LogControl1<TestLogControl1>>(DBUS_OBJ_PATH)
+            .await
+            .unwrap();
+        assert_eq!(iface.get().await.control.reopen_count(), 2);
+        assert_eq!(proxy.log_target().await.unwrap(), "journal");
+    }
+
+    #[async_std::test]
+    async fn muted() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        // Read properties back from the service rather than from the cache
+        let proxy = LogControl1ClientProxy::builder(&client)
+            .destination("de.swsnr.logcontrol.Test")
+            .unwrap()
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+        assert!(!proxy.muted().await.unwrap());
+        proxy.set_muted(true).await.unwrap();
+        assert!(proxy.muted().await.unwrap());
+        assert_eq!(proxy.log_level().await.unwrap(), "info");
+        assert_eq!(proxy.log_target().await.unwrap(), "journal");
+        proxy.set_muted(false).await.unwrap();
+        assert!(!proxy.muted().await.unwrap());
+    }
+
+    #[cfg(feature = "client")]
+    #[async_std::test]
+    async fn has_log_control() {
+        let (_server, client) = serve_p2p(LogControl1::new(TestLogControl1::new(
+            LogLevel::Info,
+            KnownLogTarget::Journal,
+        )))
+        .await;
+        assert!(crate::has_log_control(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap());
+    }
+
+    #[cfg(feature = "client")]
+    struct Unrelated;
+
+    #[cfg(feature = "client")]
+    #[zbus::interface(name = "de.swsnr.logcontrol.Unrelated")]
+    impl Unrelated {
+        fn ping(&self) {}
+    }
+
+    #[cfg(feature = "client")]
+    #[async_std::test]
+    async fn has_no_log_control() {
+        let (_server, client) = serve_p2p(Unrelated).await;
+        assert!(!crate::has_log_control(&client, "de.swsnr.logcontrol.Test")
+            .await
+            .unwrap());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn implements_log_control_parses_xml() {
+        let xml = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name='org.freedesktop.DBus.Peer'>
+    <method name='Ping'/>
+  </interface>
+  <interface
+      name='org.freedesktop.LogControl1' >
+    <property name='LogLevel' type='s' access='readwrite'/>
+  </interface>
+</node>"#;
+        assert!(crate::implements_log_control(xml).unwrap());
+        assert!(!crate::implements_log_control(
+            "<node><interface name='org.freedesktop.LogControl2'/></node>"
+        )
+        .unwrap());
+        assert!(crate::implements_log_control("<node><interface").is_err());
+    }
+
+    #[test]
+    fn valid_bus_names() {
+        for name in ["de.swsnr.logcontrol.Example", "com.example.foo_bar", "a.b"] {
+            assert_eq!(validate_bus_name(name).unwrap().as_str(), name);
+        }
+    }
+
+    #[test]
+    fn invalid_bus_names() {
+        for name in [
+            "",
+            "example",
+            "com..example",
+            "com.example.",
+            "1com.example",
+            ":1.42",
+        ] {
+            match validate_bus_name(name) {
+                Err(zbus::Error::Failure(message)) => {
+                    assert!(message.contains(&format!("{name:?}")), "{message}")
+                }
+                other => panic!("Unexpected result for {name:?}: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn serve_log_control_with_invalid_name() {
+        let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Console);
+        let result = zbus::blocking::connection::Builder::session()
+            .unwrap()
+            .serve_log_control_with_name("com.example.", LogControl1::new(control));
+        assert!(matches!(result, Err(zbus::Error::Failure(_))));
+
+        let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Console);
+        let result = zbus::connection::Builder::session()
+            .unwrap()
+            .serve_log_control_with_name("com.example.Valid", LogControl1::new(control));
+        assert!(result.is_ok());
     }
 }
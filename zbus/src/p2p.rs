@@ -0,0 +1,95 @@
+//! Serve the log control interface over a peer-to-peer connection.
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::Poll;
+
+use zbus::connection::Builder;
+use zbus::{Connection, Guid};
+
+use crate::{ConnectionBuilderExt, LogControl1};
+
+/// Serve `iface` over a peer-to-peer connection, without a bus daemon.
+///
+/// `server` and `client` wrap the two ends of a connected socket pair, e.g.
+/// from `Builder::unix_stream`.  Serve `iface` under [`crate::DBUS_OBJ_PATH`]
+/// on the `server` end, connect the `client` end, and return both
+/// connections once both completed the handshake.  Talk to the interface
+/// through the client connection, e.g. with a proxy; peer-to-peer
+/// connections need no destination, but zbus proxies still require one, so
+/// any well-known name does.
+///
+/// This allows in-process tests of services and their clients, e.g. in CI
+/// runners which have no DBus daemon:
+///
+/// ```rust
+/// # use logcontrol::test_util::TestLogControl1;
+/// # use logcontrol::{KnownLogTarget, LogLevel};
+/// use std::os::unix::net::UnixStream;
+///
+/// use logcontrol_zbus::LogControl1;
+/// use zbus::connection::Builder;
+///
+/// # async_std::task::block_on(async {
+/// let (server_socket, client_socket) = UnixStream::pair()?;
+/// let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+/// let (_server, client) = logcontrol_zbus::serve_p2p(
+///     LogControl1::new(control),
+///     Builder::unix_stream(server_socket),
+///     Builder::unix_stream(client_socket),
+/// )
+/// .await?;
+/// let proxy = zbus::fdo::PropertiesProxy::builder(&client)
+///     .destination("de.swsnr.logcontrol.Test")?
+///     .path(logcontrol_zbus::DBUS_OBJ_PATH)?
+///     .build()
+///     .await?;
+/// let interface = logcontrol_zbus::INTERFACE_NAME.try_into()?;
+/// let level = proxy.get(interface, "LogLevel").await?;
+/// assert_eq!(String::try_from(level)?, "info");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+pub async fn serve_p2p<C>(
+    iface: LogControl1<C>,
+    server: Builder<'_>,
+    client: Builder<'_>,
+) -> zbus::Result<(Connection, Connection)>
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+    let server = server
+        .server(Guid::generate())?
+        .p2p()
+        .serve_log_control(iface)?
+        .build();
+    let client = client.p2p().build();
+    // The handshake needs both ends at once, so drive both builders together
+    let mut server = pin!(server);
+    let mut client = pin!(client);
+    let mut server_result = None;
+    let mut client_result = None;
+    std::future::poll_fn(|cx| {
+        if server_result.is_none() {
+            if let Poll::Ready(result) = server.as_mut().poll(cx) {
+                server_result = Some(result);
+            }
+        }
+        if client_result.is_none() {
+            if let Poll::Ready(result) = client.as_mut().poll(cx) {
+                client_result = Some(result);
+            }
+        }
+        match (&server_result, &client_result) {
+            // Stop early if either end fails; the other end may never finish then
+            (Some(Err(_)), _) | (_, Some(Err(_))) | (Some(_), Some(_)) => Poll::Ready(()),
+            _ => Poll::Pending,
+        }
+    })
+    .await;
+    match (server_result, client_result) {
+        (Some(Ok(server)), Some(Ok(client))) => Ok((server, client)),
+        (Some(Err(error)), _) | (_, Some(Err(error))) => Err(error),
+        _ => unreachable!("poll_fn returns only when both ends finished or one failed"),
+    }
+}
@@ -0,0 +1,301 @@
+//! Serve the log control interface until the service shuts down.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::flag::register_conditional_default;
+use signal_hook::iterator::Signals;
+use zbus::blocking::connection::Builder;
+use zbus::names::WellKnownName;
+
+use crate::{validate_bus_name, ConnectionBuilderExt, LogControl1, DBUS_OBJ_PATH};
+
+/// The signals which stop [`run_until_signal`]: `SIGTERM` and `SIGINT`.
+///
+/// systemd sends `SIGTERM` to stop a service; `SIGINT` stops services which
+/// run in a terminal, e.g. with `systemd-run --pty`.
+pub const DEFAULT_SHUTDOWN_SIGNALS: &[i32] = &[SIGTERM, SIGINT];
+
+/// Serve `control` under `bus_name` until `SIGTERM` or `SIGINT` arrives.
+///
+/// Connect to the session bus if `$DBUS_SESSION_BUS_ADDRESS` is set, as it is
+/// for user services of systemd, and to the system bus otherwise.  See
+/// [`run_until_signals`] for details and errors.
+///
+/// ```no_run
+/// # use logcontrol::test_util::TestLogControl1;
+/// # use logcontrol::{KnownLogTarget, LogLevel};
+/// # let control = TestLogControl1::new(LogLevel::Info, KnownLogTarget::Journal);
+/// // Do the actual work of the service on other threads, then
+/// logcontrol_zbus::run_until_signal(control, "de.swsnr.logcontrol.Example")?;
+/// # Ok::<(), zbus::Error>(())
+/// ```
+pub fn run_until_signal<C>(control: C, bus_name: &str) -> zbus::Result<()>
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+    let builder = if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() {
+        Builder::session()?
+    } else {
+        Builder::system()?
+    };
+    run_until_signals(builder, control, bus_name, DEFAULT_SHUTDOWN_SIGNALS)
+}
+
+/// Serve `control` under `bus_name` on `builder` until one of `signals` arrives.
+///
+/// Request `bus_name`, serve `control` under [`DBUS_OBJ_PATH`], and block the
+/// current thread until the process receives any of the given `signals`.
+/// Then release `bus_name` and remove the interface, which drops `control`
+/// once calls still in flight finished; controls which buffer output flush on
+/// drop, e.g. `logcontrol_log::LogController`.
+///
+/// Register handlers for `signals` before connecting, and unregister them
+/// before returning.  signal-hook never removes its low-level signal handler
+/// though, so before returning, also arm a handler which emulates the default
+/// action of each of `signals`; the process then terminates on `SIGTERM` or
+/// `SIGINT` again, even if serving failed early.  Later calls disarm these
+/// handlers while they serve.
+///
+/// Fail if `bus_name` is invalid, see [`validate_bus_name`], if registering
+/// signal handlers fails, or if serving the interface or releasing the name
+/// fails.
+pub fn run_until_signals<C>(
+    builder: Builder<'_>,
+    control: C,
+    bus_name: &str,
+    signals: &[i32],
+) -> zbus::Result<()>
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+    let name = validate_bus_name(bus_name)?.into_owned();
+    let _default_actions = DefaultActions::suspend(signals)?;
+    let mut signals = Signals::new(signals)?;
+    let handle = signals.handle();
+    let (sender, receiver) = channel();
+    let forward = thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            let _ = sender.send(());
+        }
+    });
+    let result = serve_until(builder, LogControl1::new(control), Some(name), &receiver);
+    handle.close();
+    // The thread only waits for signals, and ends once the handle is closed
+    let _ = forward.join();
+    result
+}
+
+/// The conditions of the default actions registered for signals, by signal.
+///
+/// signal-hook cannot remove its handler for a signal, so we register a
+/// handler which emulates the default action once per signal, and keep it
+/// around for the rest of the process.
+type DefaultActionConditions = HashMap<i32, (Arc<AtomicBool>, usize)>;
+
+fn default_action_conditions() -> &'static Mutex<DefaultActionConditions> {
+    static CONDITIONS: OnceLock<Mutex<DefaultActionConditions>> = OnceLock::new();
+    CONDITIONS.get_or_init(Mutex::default)
+}
+
+/// Suspend the default actions of signals while this guard exists.
+///
+/// The emulated default action of a signal runs whenever no guard for this
+/// signal exists, i.e. while no [`run_until_signals`] serves.
+struct DefaultActions(Vec<i32>);
+
+impl DefaultActions {
+    fn suspend(signals: &[i32]) -> std::io::Result<Self> {
+        let mut conditions = default_action_conditions()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut guard = Self(Vec::with_capacity(signals.len()));
+        for signal in signals {
+            match conditions.get_mut(signal) {
+                Some((condition, count)) => {
+                    condition.store(false, Ordering::SeqCst);
+                    *count += 1;
+                }
+                None => {
+                    let condition = Arc::new(AtomicBool::new(false));
+                    register_conditional_default(*signal, condition.clone())?;
+                    conditions.insert(*signal, (condition, 1));
+                }
+            }
+            guard.0.push(*signal);
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for DefaultActions {
+    fn drop(&mut self) {
+        let mut conditions = default_action_conditions()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for signal in &self.0 {
+            if let Some((condition, count)) = conditions.get_mut(signal) {
+                *count -= 1;
+                if *count == 0 {
+                    condition.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+/// Serve `iface` on `builder` until `shutdown` receives a message or disconnects.
+///
+/// Request `name` if given, and release it again before returning.
+fn serve_until<C>(
+    builder: Builder<'_>,
+    iface: LogControl1<C>,
+    name: Option<WellKnownName<'static>>,
+    shutdown: &Receiver<()>,
+) -> zbus::Result<()>
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+    let builder = match &name {
+        Some(name) => builder.name(name.clone())?,
+        None => builder,
+    };
+    let connection = builder.serve_log_control(iface)?.build()?;
+    // A disconnected channel means shutdown as well
+    let _ = shutdown.recv();
+    if let Some(name) = name {
+        connection.release_name(name)?;
+    }
+    connection
+        .object_server()
+        .remove::<LogControl1<C>, _>(DBUS_OBJ_PATH)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use logcontrol::KnownLogTarget;
+    use logcontrol_log::{LogController, LogFactory};
+    use zbus::Guid;
+
+    use super::{run_until_signals, serve_until};
+    use crate::LogControl1;
+
+    /// Count flushes of all console loggers.
+    struct CountFlushes(Arc<AtomicUsize>);
+
+    impl log::Log for CountFlushes {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, _record: &log::Record) {}
+
+        fn flush(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct Factory(Arc<AtomicUsize>);
+
+    impl LogFactory for Factory {
+        fn create_console_log(&self) -> Result<Box<dyn log::Log>, logcontrol::LogControl1Error> {
+            Ok(Box::new(CountFlushes(self.0.clone())))
+        }
+    }
+
+    #[test]
+    fn serve_until_shutdown() {
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let (control, _logger) = LogController::new(
+            Factory(flushes.clone()),
+            false,
+            "test".to_string(),
+            KnownLogTarget::Console,
+            log::Level::Info,
+        )
+        .unwrap();
+        let (server_socket, client_socket) = UnixStream::pair().unwrap();
+        let (shutdown, receiver) = channel();
+        let server = thread::spawn(move || {
+            let builder = zbus::blocking::connection::Builder::unix_stream(server_socket)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p();
+            serve_until(builder, LogControl1::new(control), None, &receiver)
+        });
+        let client = zbus::blocking::connection::Builder::unix_stream(client_socket)
+            .p2p()
+            .build()
+            .unwrap();
+        let reply = client
+            .call_method(
+                None::<&str>,
+                crate::DBUS_OBJ_PATH,
+                Some(crate::INTERFACE_NAME),
+                "GetLogTarget",
+                &(),
+            )
+            .unwrap();
+        assert_eq!(reply.body().deserialize::<String>().unwrap(), "console");
+        assert_eq!(flushes.load(Ordering::SeqCst), 0);
+
+        shutdown.send(()).unwrap();
+        server.join().unwrap().unwrap();
+        // Removing the interface dropped the controller, which flushed the
+        // logger; the executor may still hold the interface for a moment, to
+        // finish the call above
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while flushes.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(flushes.load(Ordering::SeqCst), 1);
+    }
+
+    /// The environment variable which makes [`signals_terminate_after_return`] run as child.
+    const CHILD_VAR: &str = "LOGCONTROL_ZBUS_SIGNAL_CHILD";
+
+    #[test]
+    fn signals_terminate_after_return() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Command;
+
+        use signal_hook::consts::SIGTERM;
+
+        if std::env::var_os(CHILD_VAR).is_some() {
+            // Serving fails right away, because the peer is gone
+            let (socket, peer) = UnixStream::pair().unwrap();
+            drop(peer);
+            let builder = zbus::blocking::connection::Builder::unix_stream(socket).p2p();
+            let control = logcontrol::test_util::TestLogControl1::new(
+                logcontrol::LogLevel::Info,
+                KnownLogTarget::Console,
+            );
+            assert!(
+                run_until_signals(builder, control, "de.swsnr.logcontrol.Test", &[SIGTERM])
+                    .is_err()
+            );
+            signal_hook::low_level::raise(SIGTERM).unwrap();
+            thread::sleep(Duration::from_secs(5));
+            std::process::exit(0);
+        }
+
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "run::tests::signals_terminate_after_return"])
+            .env(CHILD_VAR, "1")
+            .status()
+            .unwrap();
+        assert_eq!(status.signal(), Some(SIGTERM), "{status:?}");
+    }
+}
@@ -0,0 +1,175 @@
+//! Serve the interface and talk to it over a peer-to-peer connection.
+
+use std::os::unix::net::UnixStream;
+
+use logcontrol::test_util::TestLogControl1;
+use logcontrol::{KnownLogTarget, LogControl1Error, LogLevel};
+use logcontrol_zbus::LogControl1;
+use zbus::connection::Builder;
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.LogControl1",
+    default_path = "/org/freedesktop/LogControl1",
+    gen_blocking = false
+)]
+trait LogControl1Client {
+    #[zbus(property)]
+    fn log_level(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_log_level(&self, level: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn log_target(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_log_target(&self, target: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn syslog_identifier(&self) -> zbus::Result<String>;
+}
+
+/// A control which only stores level and target, and supports no real targets.
+///
+/// Accept all levels, but only the `null` target and `auto`, which selects `null`.
+struct NoOpLogControl1 {
+    level: LogLevel,
+    target: KnownLogTarget,
+}
+
+impl logcontrol::LogControl1 for NoOpLogControl1 {
+    fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    fn set_level(&mut self, level: LogLevel) -> Result<(), LogControl1Error> {
+        self.level = level;
+        Ok(())
+    }
+
+    fn target(&self) -> &str {
+        self.target.as_str()
+    }
+
+    fn set_target<S: AsRef<str>>(&mut self, target: S) -> Result<(), LogControl1Error> {
+        self.target = match KnownLogTarget::try_from(target.as_ref())? {
+            KnownLogTarget::Null | KnownLogTarget::Auto => KnownLogTarget::Null,
+            other => return Err(LogControl1Error::UnsupportedLogTarget(other.to_string())),
+        };
+        Ok(())
+    }
+
+    fn syslog_identifier(&self) -> &str {
+        "noop"
+    }
+}
+
+/// Serve `control` over a peer-to-peer connection and connect a proxy to it.
+///
+/// Return the server connection, to keep it alive, and the proxy.
+async fn serve<C>(control: C) -> (zbus::Connection, LogControl1ClientProxy<'static>)
+where
+    C: logcontrol::LogControl1 + Send + Sync + 'static,
+{
+    let (server_socket, client_socket) = UnixStream::pair().unwrap();
+    let (server, client) = logcontrol_zbus::serve_p2p(
+        LogControl1::new(control),
+        Builder::unix_stream(server_socket),
+        Builder::unix_stream(client_socket),
+    )
+    .await
+    .unwrap();
+    // Read properties back from the service rather than from the cache
+    let proxy = LogControl1ClientProxy::builder(&client)
+        .destination("de.swsnr.logcontrol.Test")
+        .unwrap()
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await
+        .unwrap();
+    (server, proxy)
+}
+
+/// Get the DBus error name of `error`.
+fn error_name(error: zbus::Error) -> String {
+    match error {
+        zbus::Error::MethodError(name, _, _) => name.to_string(),
+        zbus::Error::FDO(error) => zbus::DBusError::name(&*error).to_string(),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[async_std::test]
+async fn get_and_set_over_p2p() {
+    let (_server, proxy) = serve(TestLogControl1::new(
+        LogLevel::Info,
+        KnownLogTarget::Journal,
+    ))
+    .await;
+
+    assert_eq!(proxy.log_level().await.unwrap(), "info");
+    assert_eq!(proxy.log_target().await.unwrap(), "journal");
+    assert_eq!(proxy.syslog_identifier().await.unwrap(), "test");
+
+    proxy.set_log_level("debug").await.unwrap();
+    proxy.set_log_target("console").await.unwrap();
+    assert_eq!(proxy.log_level().await.unwrap(), "debug");
+    assert_eq!(proxy.log_target().await.unwrap(), "console");
+
+    assert!(proxy.set_log_target("kmsg").await.is_err());
+    assert_eq!(proxy.log_target().await.unwrap(), "console");
+}
+
+#[async_std::test]
+async fn level_round_trip() {
+    let (_server, proxy) = serve(NoOpLogControl1 {
+        level: LogLevel::Notice,
+        target: KnownLogTarget::Null,
+    })
+    .await;
+    assert_eq!(proxy.syslog_identifier().await.unwrap(), "noop");
+    for level in LogLevel::VARIANTS {
+        proxy.set_log_level(level).await.unwrap();
+        assert_eq!(proxy.log_level().await.unwrap(), *level);
+    }
+}
+
+#[async_std::test]
+async fn target_round_trip() {
+    let (_server, proxy) = serve(NoOpLogControl1 {
+        level: LogLevel::Notice,
+        target: KnownLogTarget::Null,
+    })
+    .await;
+    proxy.set_log_target("auto").await.unwrap();
+    assert_eq!(proxy.log_target().await.unwrap(), "null");
+    // The interface ignores surrounding whitespace
+    proxy.set_log_target(" null\n").await.unwrap();
+    assert_eq!(proxy.log_target().await.unwrap(), "null");
+}
+
+#[async_std::test]
+async fn error_mapping() {
+    let (_server, proxy) = serve(NoOpLogControl1 {
+        level: LogLevel::Notice,
+        target: KnownLogTarget::Null,
+    })
+    .await;
+    let errors = [
+        (proxy.set_log_level("verbose").await, "InvalidArgs"),
+        (proxy.set_log_level("").await, "InvalidArgs"),
+        (proxy.set_log_target("").await, "InvalidArgs"),
+        (proxy.set_log_target("journal").await, "NotSupported"),
+        (proxy.set_log_target("file").await, "NotSupported"),
+    ];
+    for (result, expected) in errors {
+        assert_eq!(
+            error_name(result.unwrap_err()),
+            format!("org.freedesktop.DBus.Error.{expected}")
+        );
+    }
+    // Failed changes leave level and target alone
+    assert_eq!(proxy.log_level().await.unwrap(), "notice");
+    assert_eq!(proxy.log_target().await.unwrap(), "null");
+}